@@ -0,0 +1,526 @@
+#![no_main]
+
+//! Calls `process_instruction` in-process with arbitrary instruction bytes and
+//! structurally valid account arrays.
+//!
+//! Accounts are laid out the way the runtime serializes them so `realloc` and
+//! `assign` behave, system program CPIs are emulated, and every other CPI is
+//! recorded and treated as successful. The target fails on any panic and on
+//! any successful instruction whose net change to delegated boost balances
+//! isn't backed by a matching boost deposit or withdrawal.
+
+use std::sync::{Mutex, Once};
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use ore_miner_delegation::{
+    pda::{
+        delegated_boost_pda, delegated_boost_v2_pda, delegated_stake_pda, managed_proof_pda,
+        managed_proof_settings_pda,
+    },
+    state::{DelegatedBoost, DelegatedBoostV2, DelegatedStake, ManagedProof, ManagedProofSettings},
+    utils::{write_account_header, AccountDeserializeV1, Discriminator, LayoutVersion},
+};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS},
+    instruction::Instruction,
+    program_error::ProgramError,
+    program_stubs::{set_syscall_stubs, SyscallStubs},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction::SystemInstruction,
+    system_program, sysvar,
+};
+
+const MAX_ACCOUNTS: usize = 32;
+
+#[derive(Arbitrary, Debug)]
+struct FuzzInput {
+    unix_timestamp: i64,
+    instruction: u8,
+    data: Vec<u8>,
+    pool: Vec<FuzzAccount>,
+    picks: Vec<u8>,
+}
+
+#[derive(Arbitrary, Debug)]
+struct FuzzAccount {
+    kind: AccountKind,
+    is_signer: bool,
+    is_writable: bool,
+    lamports: u32,
+    truncate: Option<u8>,
+}
+
+#[derive(Arbitrary, Debug)]
+enum AccountKind {
+    Wallet(u8),
+    Mint(u8),
+    ManagedProof {
+        miner: u8,
+    },
+    DelegatedStake {
+        miner: u8,
+        staker: u8,
+        amount: u64,
+    },
+    DelegatedBoost {
+        miner: u8,
+        staker: u8,
+        mint: u8,
+        amount: u64,
+    },
+    DelegatedBoostV2 {
+        miner: u8,
+        staker: u8,
+        mint: u8,
+        amount: u64,
+        locked_until: i64,
+        lock_bonus_bps: u64,
+    },
+    ManagedProofSettings {
+        miner: u8,
+        allowlist_enabled: bool,
+        allowlist_root: [u8; 32],
+        min_delegation: u64,
+        min_remaining_balance: u64,
+        lock_bonus_bps: u64,
+        min_lock_duration: i64,
+        deposit_fee_bps: u64,
+        deposit_fee_recipient: u8,
+        pending_deposit_fee_bps: u64,
+        pending_deposit_fee_at: i64,
+    },
+    Uninitialized(UninitializedPda),
+    Program(ProgramKind),
+    Sysvar(SysvarKind),
+    Raw {
+        key: [u8; 32],
+        program_owned: bool,
+        data: Vec<u8>,
+    },
+}
+
+#[derive(Arbitrary, Debug)]
+enum UninitializedPda {
+    ManagedProof { miner: u8 },
+    DelegatedStake { miner: u8, staker: u8 },
+    DelegatedBoostV2 { miner: u8, staker: u8, mint: u8 },
+    ManagedProofSettings { miner: u8 },
+}
+
+#[derive(Arbitrary, Debug)]
+enum ProgramKind {
+    System,
+    Token,
+    AssociatedToken,
+    Ore,
+    OreBoost,
+    GlobalBoost,
+}
+
+#[derive(Arbitrary, Debug)]
+enum SysvarKind {
+    Rent,
+    SlotHashes,
+    Instructions,
+}
+
+fn wallet(i: u8) -> Pubkey {
+    Pubkey::new_from_array([1 + i % 4; 32])
+}
+
+fn mint(i: u8) -> Pubkey {
+    Pubkey::new_from_array([0x80 + i % 2; 32])
+}
+
+fn program_account<T: Discriminator + LayoutVersion + bytemuck::Pod>(account: &T) -> Vec<u8> {
+    let mut data = vec![0u8; 8];
+    write_account_header::<T>(&mut data);
+    data.extend_from_slice(bytemuck::bytes_of(account));
+    data
+}
+
+/// Account storage laid out like the runtime's input buffer: the original
+/// data length sits right before the key and the serialized data length right
+/// before the data, followed by the realloc headroom.
+#[repr(C)]
+struct KeySlot {
+    original_data_len: u32,
+    key: Pubkey,
+}
+
+struct MockAccount {
+    key: Box<KeySlot>,
+    lamports: u64,
+    buffer: Vec<u8>,
+    data_len: usize,
+    owner: Pubkey,
+    executable: bool,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+impl MockAccount {
+    fn new(account: &FuzzAccount) -> Self {
+        let (key, owner, executable, mut data) = match &account.kind {
+            AccountKind::Wallet(i) => (wallet(*i), system_program::id(), false, vec![]),
+            AccountKind::Mint(i) => (mint(*i), spl_token::id(), false, vec![0; 82]),
+            AccountKind::ManagedProof { miner } => {
+                let (key, bump) = managed_proof_pda(wallet(*miner));
+                let mut state: ManagedProof = bytemuck::Zeroable::zeroed();
+                state.bump = bump;
+                state.miner_authority = wallet(*miner);
+                (key, ore_miner_delegation::id(), false, program_account(&state))
+            }
+            AccountKind::DelegatedStake {
+                miner,
+                staker,
+                amount,
+            } => {
+                let (key, bump) = delegated_stake_pda(wallet(*miner), wallet(*staker));
+                let mut state: DelegatedStake = bytemuck::Zeroable::zeroed();
+                state.bump = bump;
+                state.amount = *amount;
+                (key, ore_miner_delegation::id(), false, program_account(&state))
+            }
+            AccountKind::DelegatedBoost {
+                miner,
+                staker,
+                mint: m,
+                amount,
+            } => {
+                let (key, bump) = delegated_boost_pda(wallet(*miner), wallet(*staker), mint(*m));
+                let mut state: DelegatedBoost = bytemuck::Zeroable::zeroed();
+                state.bump = bump;
+                state.managed_proof_pubkey = managed_proof_pda(wallet(*miner)).0;
+                state.amount = *amount;
+                (key, ore_miner_delegation::id(), false, program_account(&state))
+            }
+            AccountKind::DelegatedBoostV2 {
+                miner,
+                staker,
+                mint: m,
+                amount,
+                locked_until,
+                lock_bonus_bps,
+            } => {
+                let (key, bump) =
+                    delegated_boost_v2_pda(wallet(*miner), wallet(*staker), mint(*m));
+                let mut state: DelegatedBoostV2 = bytemuck::Zeroable::zeroed();
+                state.bump = bump;
+                state.managed_proof_pubkey = managed_proof_pda(wallet(*miner)).0;
+                state.authority = wallet(*staker);
+                state.mint = mint(*m);
+                state.amount = *amount;
+                state.fee_payer = wallet(*staker);
+                state.locked_until = *locked_until;
+                state.lock_bonus_bps = *lock_bonus_bps;
+                (key, ore_miner_delegation::id(), false, program_account(&state))
+            }
+            AccountKind::ManagedProofSettings {
+                miner,
+                allowlist_enabled,
+                allowlist_root,
+                min_delegation,
+                min_remaining_balance,
+                lock_bonus_bps,
+                min_lock_duration,
+                deposit_fee_bps,
+                deposit_fee_recipient,
+                pending_deposit_fee_bps,
+                pending_deposit_fee_at,
+            } => {
+                let (key, bump) = managed_proof_settings_pda(wallet(*miner));
+                let mut state: ManagedProofSettings = bytemuck::Zeroable::zeroed();
+                state.bump = bump;
+                state.allowlist_enabled = *allowlist_enabled as u8;
+                state.managed_proof = managed_proof_pda(wallet(*miner)).0;
+                state.allowlist_root = *allowlist_root;
+                state.min_delegation = *min_delegation;
+                state.min_remaining_balance = *min_remaining_balance;
+                state.lock_bonus_bps = *lock_bonus_bps;
+                state.min_lock_duration = *min_lock_duration;
+                state.deposit_fee_bps = *deposit_fee_bps;
+                state.deposit_fee_recipient = wallet(*deposit_fee_recipient);
+                state.pending_deposit_fee_bps = *pending_deposit_fee_bps;
+                state.pending_deposit_fee_at = *pending_deposit_fee_at;
+                (key, ore_miner_delegation::id(), false, program_account(&state))
+            }
+            AccountKind::Uninitialized(pda) => {
+                let key = match pda {
+                    UninitializedPda::ManagedProof { miner } => managed_proof_pda(wallet(*miner)).0,
+                    UninitializedPda::DelegatedStake { miner, staker } => {
+                        delegated_stake_pda(wallet(*miner), wallet(*staker)).0
+                    }
+                    UninitializedPda::DelegatedBoostV2 {
+                        miner,
+                        staker,
+                        mint: m,
+                    } => delegated_boost_v2_pda(wallet(*miner), wallet(*staker), mint(*m)).0,
+                    UninitializedPda::ManagedProofSettings { miner } => {
+                        managed_proof_settings_pda(wallet(*miner)).0
+                    }
+                };
+                (key, system_program::id(), false, vec![])
+            }
+            AccountKind::Program(program) => {
+                let key = match program {
+                    ProgramKind::System => system_program::id(),
+                    ProgramKind::Token => spl_token::id(),
+                    ProgramKind::AssociatedToken => spl_associated_token_account_id(),
+                    ProgramKind::Ore => ore_api::id(),
+                    ProgramKind::OreBoost => ore_boost_api::id(),
+                    ProgramKind::GlobalBoost => ore_miner_delegation::global_boost::GLOBAL_BOOST_ID,
+                };
+                (key, solana_program::bpf_loader_upgradeable::id(), true, vec![])
+            }
+            AccountKind::Sysvar(sysvar) => {
+                let key = match sysvar {
+                    SysvarKind::Rent => sysvar::rent::id(),
+                    SysvarKind::SlotHashes => sysvar::slot_hashes::id(),
+                    SysvarKind::Instructions => sysvar::instructions::id(),
+                };
+                (key, sysvar::id(), false, vec![])
+            }
+            AccountKind::Raw {
+                key,
+                program_owned,
+                data,
+            } => {
+                let owner = if *program_owned {
+                    ore_miner_delegation::id()
+                } else {
+                    spl_token::id()
+                };
+                (Pubkey::new_from_array(*key), owner, false, data.clone())
+            }
+        };
+
+        if let Some(len) = account.truncate {
+            data.truncate(len as usize);
+        }
+
+        let data_len = data.len();
+        let mut buffer = vec![0u8; 8 + data_len + MAX_PERMITTED_DATA_INCREASE];
+        buffer[..8].copy_from_slice(&(data_len as u64).to_le_bytes());
+        buffer[8..8 + data_len].copy_from_slice(&data);
+
+        Self {
+            key: Box::new(KeySlot {
+                original_data_len: data_len as u32,
+                key,
+            }),
+            lamports: account.lamports as u64,
+            buffer,
+            data_len,
+            owner,
+            executable,
+            is_signer: account.is_signer,
+            is_writable: account.is_writable,
+        }
+    }
+
+    fn info(&mut self) -> AccountInfo {
+        AccountInfo::new(
+            &self.key.key,
+            self.is_signer,
+            self.is_writable,
+            &mut self.lamports,
+            &mut self.buffer[8..8 + self.data_len],
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
+
+fn spl_associated_token_account_id() -> Pubkey {
+    solana_program::pubkey!("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL")
+}
+
+#[derive(Default)]
+struct Cpis {
+    boost_deposited: u128,
+    boost_withdrawn: u128,
+}
+
+static CPIS: Mutex<Cpis> = Mutex::new(Cpis {
+    boost_deposited: 0,
+    boost_withdrawn: 0,
+});
+static UNIX_TIMESTAMP: Mutex<i64> = Mutex::new(0);
+static STUBS: Once = Once::new();
+
+struct FuzzStubs;
+
+impl FuzzStubs {
+    fn find<'a, 'b>(infos: &'a [AccountInfo<'b>], key: &Pubkey) -> Option<&'a AccountInfo<'b>> {
+        infos.iter().find(|info| info.key == key)
+    }
+
+    fn move_lamports(from: &AccountInfo, to: &AccountInfo, lamports: u64) -> ProgramResult {
+        let from_balance = from
+            .lamports()
+            .checked_sub(lamports)
+            .ok_or(ProgramError::InsufficientFunds)?;
+        let to_balance = to
+            .lamports()
+            .checked_add(lamports)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        **from.try_borrow_mut_lamports()? = from_balance;
+        **to.try_borrow_mut_lamports()? = to_balance;
+        Ok(())
+    }
+
+    fn invoke_system(instruction: &Instruction, infos: &[AccountInfo]) -> ProgramResult {
+        let ix: SystemInstruction = bincode::deserialize(&instruction.data)
+            .map_err(|_| ProgramError::InvalidInstructionData)?;
+        let from = instruction
+            .accounts
+            .first()
+            .and_then(|meta| Self::find(infos, &meta.pubkey))
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        let to = instruction
+            .accounts
+            .get(1)
+            .and_then(|meta| Self::find(infos, &meta.pubkey))
+            .ok_or(ProgramError::NotEnoughAccountKeys)?;
+        match ix {
+            SystemInstruction::CreateAccount {
+                lamports,
+                space,
+                owner,
+            } => {
+                if !to.data_is_empty() || *to.owner != system_program::id() {
+                    return Err(ProgramError::AccountAlreadyInitialized);
+                }
+                Self::move_lamports(from, to, lamports)?;
+                to.realloc(space as usize, true)?;
+                to.assign(&owner);
+                Ok(())
+            }
+            SystemInstruction::Transfer { lamports } => Self::move_lamports(from, to, lamports),
+            _ => Ok(()),
+        }
+    }
+
+    fn record_boost(instruction: &Instruction) {
+        let Some(amount) = instruction
+            .data
+            .get(1..9)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()) as u128)
+        else {
+            return;
+        };
+        let deposit = ore_boost_api::sdk::deposit(Pubkey::default(), Pubkey::default(), 0);
+        let withdraw = ore_boost_api::sdk::withdraw(Pubkey::default(), Pubkey::default(), 0);
+        let mut cpis = CPIS.lock().unwrap();
+        if instruction.data[0] == deposit.data[0] {
+            cpis.boost_deposited += amount;
+        } else if instruction.data[0] == withdraw.data[0] {
+            cpis.boost_withdrawn += amount;
+        }
+    }
+}
+
+impl SyscallStubs for FuzzStubs {
+    fn sol_log(&self, _message: &str) {}
+
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        if instruction.program_id == system_program::id() {
+            return Self::invoke_system(instruction, account_infos);
+        }
+        if instruction.program_id == ore_boost_api::id() {
+            Self::record_boost(instruction);
+        }
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: *UNIX_TIMESTAMP.lock().unwrap(),
+            ..Clock::default()
+        };
+        unsafe { *(var_addr as *mut Clock) = clock };
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe { *(var_addr as *mut Rent) = Rent::default() };
+        SUCCESS
+    }
+}
+
+/// Sum of all delegated boost balances, v1 and v2, held by the given accounts.
+fn delegated_boost_total(infos: &[AccountInfo]) -> u128 {
+    infos
+        .iter()
+        .filter(|info| *info.owner == ore_miner_delegation::id())
+        .map(|info| {
+            let data = info.data.borrow();
+            if let Ok(boost) = DelegatedBoostV2::try_from_bytes(&data) {
+                boost.amount as u128
+            } else if let Ok(boost) = DelegatedBoost::try_from_bytes(&data) {
+                boost.amount as u128
+            } else {
+                0
+            }
+        })
+        .sum()
+}
+
+fuzz_target!(|input: FuzzInput| {
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(FuzzStubs));
+    });
+    *UNIX_TIMESTAMP.lock().unwrap() = input.unix_timestamp;
+    *CPIS.lock().unwrap() = Cpis::default();
+
+    // Identical keys share one account so aliasing matches the runtime.
+    let mut mocks: Vec<MockAccount> = Vec::new();
+    for account in input.pool.iter().take(MAX_ACCOUNTS) {
+        let mock = MockAccount::new(account);
+        if mocks.iter().all(|m| m.key.key != mock.key.key) {
+            mocks.push(mock);
+        }
+    }
+    if mocks.is_empty() {
+        return;
+    }
+    let pool: Vec<AccountInfo> = mocks.iter_mut().map(MockAccount::info).collect();
+    let accounts: Vec<AccountInfo> = input
+        .picks
+        .iter()
+        .take(MAX_ACCOUNTS)
+        .map(|i| pool[*i as usize % pool.len()].clone())
+        .collect();
+
+    let mut instruction_data = vec![input.instruction];
+    instruction_data.extend_from_slice(&input.data);
+
+    let before = delegated_boost_total(&pool);
+    let result = ore_miner_delegation::process_instruction(
+        &ore_miner_delegation::id(),
+        &accounts,
+        &instruction_data,
+    );
+    if result.is_err() {
+        return;
+    }
+    let after = delegated_boost_total(&pool);
+
+    let cpis = CPIS.lock().unwrap();
+    assert_eq!(
+        after + cpis.boost_withdrawn,
+        before + cpis.boost_deposited,
+        "delegated boost balances changed without a matching boost deposit or withdrawal",
+    );
+});
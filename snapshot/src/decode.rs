@@ -0,0 +1,106 @@
+use std::mem::size_of;
+
+use bytemuck::Pod;
+use ore_miner_delegation::{
+    state::{DelegatedBoost, DelegatedBoostV2, DelegatedStake, ManagedProof},
+    utils::{AccountDiscriminator, LayoutVersion},
+};
+
+use crate::{RawAccount, SnapshotError};
+
+/// A delegation account decoded to the current layout of its type.
+#[derive(Clone, Copy, Debug)]
+pub enum DecodedAccount {
+    ManagedProof(ManagedProof),
+    DelegatedStake(DelegatedStake),
+    DelegatedBoost(DelegatedBoost),
+    DelegatedBoostV2(DelegatedBoostV2),
+}
+
+/// Decode a raw account. Returns `None` for accounts of other programs and for
+/// program accounts that hold no positions, such as settings, sessions and
+/// distributions. Inputs without an owner, such as base64 dumps, can hold any
+/// program's accounts, so one whose length doesn't fit the type its first byte
+/// names is taken to be foreign and skipped rather than failing the export.
+pub fn decode(account: &RawAccount) -> Result<Option<DecodedAccount>, SnapshotError> {
+    if account
+        .owner
+        .is_some_and(|owner| owner != ore_miner_delegation::id())
+    {
+        return Ok(None);
+    }
+
+    let Some(discriminator) = account
+        .data
+        .first()
+        .and_then(|discriminator| AccountDiscriminator::try_from(*discriminator).ok())
+    else {
+        return Ok(None);
+    };
+
+    let fits = match discriminator {
+        AccountDiscriminator::ManagedProof => fits::<ManagedProof>(account),
+        AccountDiscriminator::DelegatedStake => fits::<DelegatedStake>(account),
+        AccountDiscriminator::DelegatedBoost => fits::<DelegatedBoost>(account),
+        AccountDiscriminator::DelegatedBoostV2 => fits::<DelegatedBoostV2>(account),
+        AccountDiscriminator::ManagedProofSettings
+        | AccountDiscriminator::StakerSession
+        | AccountDiscriminator::Distribution
+        | AccountDiscriminator::ClaimBitmap => return Ok(None),
+    };
+    if account.owner.is_none() && !fits {
+        return Ok(None);
+    }
+
+    let decoded = match discriminator {
+        AccountDiscriminator::ManagedProof => DecodedAccount::ManagedProof(upgraded(account)?),
+        AccountDiscriminator::DelegatedStake => DecodedAccount::DelegatedStake(upgraded(account)?),
+        AccountDiscriminator::DelegatedBoost => DecodedAccount::DelegatedBoost(upgraded(account)?),
+        AccountDiscriminator::DelegatedBoostV2 => {
+            DecodedAccount::DelegatedBoostV2(upgraded(account)?)
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(decoded))
+}
+
+/// Whether the account's length is one some layout of `T` can have: at least
+/// the header and at most the current layout.
+fn fits<T: Pod>(account: &RawAccount) -> bool {
+    (8..=8 + size_of::<T>()).contains(&account.data.len())
+}
+
+/// Read an account the way `UpgradeAccount` would leave it: data written with
+/// an older layout is zero extended and given the defaults of the new fields,
+/// so snapshots of unmigrated accounts decode the same as migrated ones.
+fn upgraded<T: Pod + LayoutVersion>(account: &RawAccount) -> Result<T, SnapshotError> {
+    let decode_error = |message: String| SnapshotError::Decode {
+        address: account.address.to_string(),
+        message,
+    };
+
+    let space = 8 + size_of::<T>();
+    if !fits::<T>(account) {
+        return Err(decode_error(format!(
+            "unexpected data length {}",
+            account.data.len()
+        )));
+    }
+
+    let mut data = account.data[8..].to_vec();
+    data.resize(size_of::<T>(), 0);
+    let mut state: T = bytemuck::pod_read_unaligned(&data);
+
+    let from_version = account.data[1];
+    if from_version > T::layout_version() {
+        return Err(decode_error(format!(
+            "layout version {from_version} is newer than this tool"
+        )));
+    }
+    if account.data.len() < space || from_version < T::layout_version() {
+        state.upgrade_defaults(from_version);
+    }
+
+    Ok(state)
+}
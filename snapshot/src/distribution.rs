@@ -0,0 +1,155 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use ore_miner_delegation::{
+    consts::MAX_DISTRIBUTION_CLAIMS,
+    merkle::{distribution_leaf, MerkleTree},
+    state::DelegatedBoostV2,
+};
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+
+use crate::{export::{Position, PositionKind}, Snapshot, SnapshotError};
+
+/// One staker's share of a distribution, at its leaf index in the tree.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Allocation {
+    pub index: u32,
+    pub staker: String,
+    pub amount: u64,
+    /// Hex encoded sibling nodes to pass to `ClaimDistribution`.
+    pub proof: Vec<String>,
+}
+
+/// A distribution ready to post with `PostDistribution`.
+#[derive(Clone, Debug, Serialize)]
+pub struct DistributionTree {
+    pub root: String,
+    /// Sum of the allocations. Rounding leaves at most one unit per staker of
+    /// the requested amount undistributed.
+    pub total: u64,
+    pub allocations: Vec<Allocation>,
+    #[serde(skip)]
+    root_bytes: [u8; 32],
+    #[serde(skip)]
+    proofs: Vec<Vec<[u8; 32]>>,
+}
+
+impl DistributionTree {
+    /// Build a tree over explicit `(staker, amount)` allocations, in the
+    /// order given. Fails with more allocations than `PostDistribution`
+    /// accepts.
+    pub fn new(allocations: &[(Pubkey, u64)]) -> Result<Self, SnapshotError> {
+        if allocations.len() > MAX_DISTRIBUTION_CLAIMS as usize {
+            return Err(SnapshotError::TooManyAllocations {
+                count: allocations.len(),
+                max: MAX_DISTRIBUTION_CLAIMS,
+            });
+        }
+
+        let leaves = allocations
+            .iter()
+            .enumerate()
+            .map(|(index, (staker, amount))| distribution_leaf(index as u32, staker, *amount))
+            .collect();
+        let tree = MerkleTree::new(leaves);
+        let proofs: Vec<Vec<[u8; 32]>> = (0..allocations.len())
+            .map(|index| tree.proof(index).unwrap_or_default())
+            .collect();
+
+        let allocations: Vec<Allocation> = allocations
+            .iter()
+            .zip(&proofs)
+            .enumerate()
+            .map(|(index, ((staker, amount), proof))| Allocation {
+                index: index as u32,
+                staker: staker.to_string(),
+                amount: *amount,
+                proof: proof.iter().map(hex).collect(),
+            })
+            .collect();
+
+        Ok(Self {
+            root: hex(&tree.root()),
+            total: allocations
+                .iter()
+                .map(|allocation| allocation.amount)
+                .fold(0, u64::saturating_add),
+            allocations,
+            root_bytes: tree.root(),
+            proofs,
+        })
+    }
+
+    /// Split `amount` across the stakers of the boost positions in `mint` on
+    /// `managed_proof`, the proof the distribution is posted for, pro rata to
+    /// each position's `DelegatedBoostV2::reward_weight_bps` at `now`.
+    /// Delegated stake earns no boost rewards and gets no share. Stakers are
+    /// ordered by address so the same snapshot always gives the same root.
+    /// Fails like [`DistributionTree::new`] when too many stakers get a share.
+    pub fn by_lock_weight(
+        snapshot: &Snapshot,
+        managed_proof: &Pubkey,
+        mint: &Pubkey,
+        amount: u64,
+        now: i64,
+    ) -> Result<Self, SnapshotError> {
+        let managed_proof = Some(managed_proof.to_string());
+        let mint = Some(mint.to_string());
+
+        let mut weights: BTreeMap<Pubkey, u128> = BTreeMap::new();
+        for position in &snapshot.positions {
+            if position.kind == PositionKind::Stake
+                || position.managed_proof != managed_proof
+                || position.mint != mint
+            {
+                continue;
+            }
+            let Some(staker) = position
+                .staker
+                .as_deref()
+                .and_then(|staker| Pubkey::from_str(staker).ok())
+            else {
+                continue;
+            };
+            *weights.entry(staker).or_default() += weight(position, now);
+        }
+
+        let total_weight: u128 = weights.values().sum();
+        let allocations: Vec<(Pubkey, u64)> = weights
+            .into_iter()
+            .map(|(staker, weight)| {
+                let share = (amount as u128)
+                    .saturating_mul(weight)
+                    .checked_div(total_weight)
+                    .unwrap_or(0);
+                (staker, share as u64)
+            })
+            .filter(|(_, share)| *share > 0)
+            .collect();
+
+        Self::new(&allocations)
+    }
+
+    pub fn root_bytes(&self) -> [u8; 32] {
+        self.root_bytes
+    }
+
+    /// Proof of the allocation at `index`, as passed to
+    /// `instruction::claim_distribution`.
+    pub fn proof(&self, index: usize) -> Option<Vec<[u8; 32]>> {
+        self.proofs.get(index).cloned()
+    }
+}
+
+/// Reward weight of a boost position at `now`: its amount in basis points.
+/// Legacy boosts have no lock, so they weigh the base weight.
+fn weight(position: &Position, now: i64) -> u128 {
+    let mut delegated_boost: DelegatedBoostV2 = bytemuck::Zeroable::zeroed();
+    delegated_boost.locked_until = position.locked_until;
+    delegated_boost.lock_bonus_bps = position.lock_bonus_bps;
+    position.amount as u128 * delegated_boost.reward_weight_bps(now) as u128
+}
+
+fn hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
@@ -0,0 +1,373 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use ore_api::consts::MINT_ADDRESS;
+use ore_miner_delegation::{
+    consts::{DELEGATED_BOOST, DELEGATED_STAKE},
+    pda::{managed_proof_pda, ManagedProofId},
+};
+use serde::Serialize;
+use solana_program::pubkey::Pubkey;
+
+use crate::{decode, DecodedAccount, RawAccount, SnapshotError};
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PositionKind {
+    Stake,
+    Boost,
+    BoostV2,
+}
+
+impl PositionKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PositionKind::Stake => "stake",
+            PositionKind::Boost => "boost",
+            PositionKind::BoostV2 => "boost_v2",
+        }
+    }
+}
+
+/// One `DelegatedStake`, `DelegatedBoost` or `DelegatedBoostV2` account.
+/// Stakes are positions in ORE, so their mint is the ORE mint. The staker,
+/// miner, mint or managed proof is `None` when it could not be recovered from
+/// the dump.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+pub struct Position {
+    pub staker: Option<String>,
+    pub miner: Option<String>,
+    pub mint: Option<String>,
+    pub kind: PositionKind,
+    pub address: String,
+    pub managed_proof: Option<String>,
+    pub amount: u64,
+    pub locked_until: i64,
+    pub lock_bonus_bps: u64,
+    pub payer: Option<String>,
+}
+
+/// The sum of a staker's positions with one miner in one mint.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Total {
+    pub staker: Option<String>,
+    pub miner: Option<String>,
+    pub mint: Option<String>,
+    pub amount: u64,
+    pub positions: u64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct ManagedProofRow {
+    pub address: String,
+    pub miner: Option<String>,
+    pub index: Option<u16>,
+    pub payer: Option<String>,
+    pub total_submissions: u64,
+    pub total_rewards: u64,
+    pub last_mine_at: i64,
+    pub best_difficulty: u32,
+}
+
+/// Normalized tables of every position in a dump, sorted so the same dump
+/// always exports byte for byte the same files.
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    pub managed_proofs: Vec<ManagedProofRow>,
+    pub positions: Vec<Position>,
+    pub totals: Vec<Total>,
+}
+
+impl Snapshot {
+    /// Build the tables from raw accounts. `DelegatedStake` and
+    /// `DelegatedBoost` accounts do not store their staker, so it is recovered
+    /// by looking their address up among the addresses derived once for every
+    /// staker known from the dump, which are the `DelegatedBoostV2`
+    /// authorities and the miners, plus `extra_stakers`.
+    pub fn build(accounts: &[RawAccount], extra_stakers: &[Pubkey]) -> Result<Self, SnapshotError> {
+        let mut managed_proofs = BTreeMap::new();
+        let mut accounts_by_address = BTreeMap::new();
+        for account in accounts {
+            let Some(decoded) = decode(account)? else {
+                continue;
+            };
+            if let DecodedAccount::ManagedProof(managed_proof) = decoded {
+                managed_proofs.insert(account.address, managed_proof);
+            }
+            accounts_by_address.insert(account.address, decoded);
+        }
+
+        // The miner is the proof's seed; proofs whose authority no longer
+        // derives their address are kept without one.
+        let miners: BTreeMap<Pubkey, Pubkey> = managed_proofs
+            .iter()
+            .filter_map(|(address, managed_proof)| {
+                let id = ManagedProofId {
+                    miner: managed_proof.miner_authority,
+                    index: managed_proof.index(),
+                };
+                (managed_proof_pda(id).0 == *address).then_some((*address, id.miner))
+            })
+            .collect();
+
+        let mut stakers: BTreeSet<Pubkey> = extra_stakers.iter().copied().collect();
+        stakers.extend(miners.values().copied());
+        let mut mints = BTreeSet::from([MINT_ADDRESS]);
+        for decoded in accounts_by_address.values() {
+            if let DecodedAccount::DelegatedBoostV2(delegated_boost) = decoded {
+                stakers.insert(delegated_boost.authority);
+                mints.insert(delegated_boost.mint);
+            }
+        }
+
+        // Derive every candidate address once, so each position is a lookup
+        // rather than a search over stakers, mints and proofs.
+        let mut stakes: BTreeMap<Pubkey, (Pubkey, Pubkey)> = BTreeMap::new();
+        for managed_proof in managed_proofs.keys() {
+            for staker in &stakers {
+                let address = derive(&[DELEGATED_STAKE, staker.as_ref(), managed_proof.as_ref()]);
+                stakes.insert(address, (*staker, *managed_proof));
+            }
+        }
+        let boosted_proofs: BTreeSet<Pubkey> = accounts_by_address
+            .values()
+            .filter_map(|decoded| match decoded {
+                DecodedAccount::DelegatedBoost(delegated_boost) => {
+                    Some(delegated_boost.managed_proof_pubkey)
+                }
+                _ => None,
+            })
+            .collect();
+        let mut boosts: BTreeMap<Pubkey, (Pubkey, Pubkey)> = BTreeMap::new();
+        for managed_proof in &boosted_proofs {
+            for staker in &stakers {
+                for mint in &mints {
+                    let address = derive(&[
+                        DELEGATED_BOOST,
+                        staker.as_ref(),
+                        mint.as_ref(),
+                        managed_proof.as_ref(),
+                    ]);
+                    boosts.insert(address, (*staker, *mint));
+                }
+            }
+        }
+
+        let key = |key: &Pubkey| Some(key.to_string());
+        let miner_of = |managed_proof: &Pubkey| miners.get(managed_proof).map(Pubkey::to_string);
+
+        let mut positions = vec![];
+        for (address, decoded) in &accounts_by_address {
+            let position = match decoded {
+                DecodedAccount::ManagedProof(_) => continue,
+                DecodedAccount::DelegatedStake(delegated_stake) => {
+                    let staker_and_proof = stakes.get(address);
+                    Position {
+                        staker: staker_and_proof.and_then(|(staker, _)| key(staker)),
+                        miner: staker_and_proof.and_then(|(_, proof)| miner_of(proof)),
+                        mint: key(&MINT_ADDRESS),
+                        kind: PositionKind::Stake,
+                        address: address.to_string(),
+                        managed_proof: staker_and_proof.and_then(|(_, proof)| key(proof)),
+                        amount: delegated_stake.amount,
+                        locked_until: 0,
+                        lock_bonus_bps: 0,
+                        // zero for stakes opened before the payer was recorded
+                        payer: (delegated_stake.payer != Pubkey::default())
+                            .then(|| delegated_stake.payer.to_string()),
+                    }
+                }
+                DecodedAccount::DelegatedBoost(delegated_boost) => {
+                    let managed_proof = delegated_boost.managed_proof_pubkey;
+                    let staker_and_mint = boosts.get(address);
+                    Position {
+                        staker: staker_and_mint.and_then(|(staker, _)| key(staker)),
+                        miner: miner_of(&managed_proof),
+                        mint: staker_and_mint.and_then(|(_, mint)| key(mint)),
+                        kind: PositionKind::Boost,
+                        address: address.to_string(),
+                        managed_proof: key(&managed_proof),
+                        amount: delegated_boost.amount,
+                        locked_until: 0,
+                        lock_bonus_bps: 0,
+                        payer: None,
+                    }
+                }
+                DecodedAccount::DelegatedBoostV2(delegated_boost) => Position {
+                    staker: key(&delegated_boost.authority),
+                    miner: miner_of(&delegated_boost.managed_proof_pubkey),
+                    mint: key(&delegated_boost.mint),
+                    kind: PositionKind::BoostV2,
+                    address: address.to_string(),
+                    managed_proof: key(&delegated_boost.managed_proof_pubkey),
+                    amount: delegated_boost.amount,
+                    locked_until: delegated_boost.locked_until,
+                    lock_bonus_bps: delegated_boost.lock_bonus_bps,
+                    payer: key(&delegated_boost.fee_payer),
+                },
+            };
+            positions.push(position);
+        }
+        positions.sort();
+
+        let mut totals: BTreeMap<_, Total> = BTreeMap::new();
+        for position in &positions {
+            let group = (position.staker.clone(), position.miner.clone(), position.mint.clone());
+            let total = totals.entry(group).or_insert_with(|| Total {
+                staker: position.staker.clone(),
+                miner: position.miner.clone(),
+                mint: position.mint.clone(),
+                amount: 0,
+                positions: 0,
+            });
+            total.amount = total.amount.saturating_add(position.amount);
+            total.positions += 1;
+        }
+
+        let managed_proofs = managed_proofs
+            .iter()
+            .map(|(address, managed_proof)| ManagedProofRow {
+                address: address.to_string(),
+                miner: miner_of(address),
+                index: managed_proof.index(),
+                payer: key(&managed_proof.payer),
+                total_submissions: managed_proof.total_submissions,
+                total_rewards: managed_proof.total_rewards,
+                last_mine_at: managed_proof.last_mine_at,
+                best_difficulty: managed_proof.best_difficulty,
+            })
+            .collect();
+
+        Ok(Self {
+            managed_proofs,
+            positions,
+            totals: totals.into_values().collect(),
+        })
+    }
+
+    /// Write `positions`, `totals` and `managed_proofs` tables into `dir`, as
+    /// both `.csv` and `.json`.
+    pub fn write(&self, dir: &Path) -> Result<(), SnapshotError> {
+        write_table(dir, "positions", &self.positions)?;
+        write_table(dir, "totals", &self.totals)?;
+        write_table(dir, "managed_proofs", &self.managed_proofs)?;
+        Ok(())
+    }
+}
+
+/// The delegation program's PDA of `seeds`. Accounts are only ever opened at
+/// their canonical bump, so this is the only address the seeds can have.
+fn derive(seeds: &[&[u8]]) -> Pubkey {
+    Pubkey::find_program_address(seeds, &ore_miner_delegation::id()).0
+}
+
+/// A row of a CSV table.
+pub trait CsvRow {
+    fn header() -> &'static [&'static str];
+    fn fields(&self) -> Vec<String>;
+}
+
+fn optional(value: &Option<String>) -> String {
+    value.clone().unwrap_or_default()
+}
+
+impl CsvRow for Position {
+    fn header() -> &'static [&'static str] {
+        &[
+            "staker",
+            "miner",
+            "mint",
+            "kind",
+            "address",
+            "managed_proof",
+            "amount",
+            "locked_until",
+            "lock_bonus_bps",
+            "payer",
+        ]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            optional(&self.staker),
+            optional(&self.miner),
+            optional(&self.mint),
+            self.kind.as_str().to_string(),
+            self.address.clone(),
+            optional(&self.managed_proof),
+            self.amount.to_string(),
+            self.locked_until.to_string(),
+            self.lock_bonus_bps.to_string(),
+            optional(&self.payer),
+        ]
+    }
+}
+
+impl CsvRow for Total {
+    fn header() -> &'static [&'static str] {
+        &["staker", "miner", "mint", "amount", "positions"]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            optional(&self.staker),
+            optional(&self.miner),
+            optional(&self.mint),
+            self.amount.to_string(),
+            self.positions.to_string(),
+        ]
+    }
+}
+
+impl CsvRow for ManagedProofRow {
+    fn header() -> &'static [&'static str] {
+        &[
+            "address",
+            "miner",
+            "index",
+            "payer",
+            "total_submissions",
+            "total_rewards",
+            "last_mine_at",
+            "best_difficulty",
+        ]
+    }
+
+    fn fields(&self) -> Vec<String> {
+        vec![
+            self.address.clone(),
+            optional(&self.miner),
+            self.index.map(|index| index.to_string()).unwrap_or_default(),
+            optional(&self.payer),
+            self.total_submissions.to_string(),
+            self.total_rewards.to_string(),
+            self.last_mine_at.to_string(),
+            self.best_difficulty.to_string(),
+        ]
+    }
+}
+
+/// Every field is an address or a number, so no field needs quoting.
+pub fn to_csv<T: CsvRow>(rows: &[T]) -> String {
+    let mut csv = T::header().join(",");
+    csv.push('\n');
+    for row in rows {
+        csv.push_str(&row.fields().join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+fn write_table<T: CsvRow + Serialize>(dir: &Path, name: &str, rows: &[T]) -> Result<(), SnapshotError> {
+    let write = |file: String, contents: String| {
+        let path = dir.join(file);
+        fs::write(&path, contents).map_err(|source| SnapshotError::Io { path, source })
+    };
+
+    write(format!("{name}.csv"), to_csv(rows))?;
+    let json = serde_json::to_string_pretty(rows).expect("tables serialize to json");
+    write(format!("{name}.json"), json + "\n")
+}
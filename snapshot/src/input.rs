@@ -0,0 +1,132 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::Value;
+use solana_program::pubkey::Pubkey;
+
+use crate::SnapshotError;
+
+/// An account read from a dump, before decoding.
+#[derive(Clone, Debug)]
+pub struct RawAccount {
+    pub address: Pubkey,
+    /// `None` for dumps that only hold the data, such as base64 account files.
+    pub owner: Option<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+/// Read every account in `path`. A directory is read file by file in name
+/// order, so the same dump always yields the same accounts.
+///
+/// `.json` files hold the output of `solana account --output json`, an array
+/// of such entries, or an RPC `getProgramAccounts` response. Any other file
+/// holds the base64 data of a single account and is named after its address,
+/// e.g. `<address>.b64`.
+pub fn read_path(path: &Path) -> Result<Vec<RawAccount>, SnapshotError> {
+    let io = |source| SnapshotError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+
+    if !path.is_dir() {
+        return read_file(path);
+    }
+
+    let mut files: Vec<PathBuf> = fs::read_dir(path)
+        .map_err(io)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()
+        .map_err(io)?;
+    files.sort();
+
+    let mut accounts = vec![];
+    for file in files.iter().filter(|file| file.is_file()) {
+        accounts.extend(read_file(file)?);
+    }
+
+    Ok(accounts)
+}
+
+fn read_file(path: &Path) -> Result<Vec<RawAccount>, SnapshotError> {
+    let contents = fs::read_to_string(path).map_err(|source| SnapshotError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let parse_error = |message: String| SnapshotError::Parse {
+        path: path.to_path_buf(),
+        message,
+    };
+
+    if path.extension().is_some_and(|extension| extension == "json") {
+        let value: Value = serde_json::from_str(&contents).map_err(|e| parse_error(e.to_string()))?;
+        return parse_json(&value).map_err(parse_error);
+    }
+
+    let address = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| parse_error("file name is not an account address".to_string()))?;
+
+    Ok(vec![RawAccount {
+        address: parse_pubkey(address).map_err(parse_error)?,
+        owner: None,
+        data: decode_base64(contents.trim()).map_err(parse_error)?,
+    }])
+}
+
+fn parse_json(value: &Value) -> Result<Vec<RawAccount>, String> {
+    match value {
+        Value::Array(entries) => entries.iter().map(parse_entry).collect(),
+        Value::Object(object) => match object.get("result") {
+            Some(result) => parse_json(result.get("value").unwrap_or(result)),
+            None => Ok(vec![parse_entry(value)?]),
+        },
+        _ => Err("expected an account or an array of accounts".to_string()),
+    }
+}
+
+/// Parse a `{ "pubkey": .., "account": { "owner": .., "data": .. } }` entry.
+fn parse_entry(entry: &Value) -> Result<RawAccount, String> {
+    let address = entry
+        .get("pubkey")
+        .and_then(Value::as_str)
+        .ok_or("account entry has no pubkey")?;
+    let account = entry.get("account").ok_or("account entry has no account")?;
+    let owner = account
+        .get("owner")
+        .and_then(Value::as_str)
+        .map(parse_pubkey)
+        .transpose()?;
+
+    // The CLI and RPC encode data as `[data, encoding]`.
+    let data = match account.get("data") {
+        Some(Value::String(data)) => decode_base64(data)?,
+        Some(Value::Array(parts)) => match (parts.first(), parts.get(1)) {
+            (Some(Value::String(data)), Some(Value::String(encoding))) if encoding == "base64" => {
+                decode_base64(data)?
+            }
+            _ => return Err(format!("{address}: account data must be base64 encoded")),
+        },
+        _ => return Err(format!("{address}: account entry has no data")),
+    };
+
+    Ok(RawAccount {
+        address: parse_pubkey(address)?,
+        owner,
+        data,
+    })
+}
+
+fn parse_pubkey(value: &str) -> Result<Pubkey, String> {
+    Pubkey::from_str(value).map_err(|_| format!("invalid address {value}"))
+}
+
+fn decode_base64(value: &str) -> Result<Vec<u8>, String> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| format!("invalid base64 data: {e}"))
+}
@@ -0,0 +1,35 @@
+//! Offline export of ore-miner-delegation positions.
+//!
+//! Reads dumps of program owned accounts, decodes the delegation accounts in
+//! them and writes normalized CSV and JSON tables, so accounting and airdrops
+//! can work from a fixed snapshot instead of a live RPC. The snapshot can
+//! also be turned into the merkle tree of a reward distribution.
+
+pub mod decode;
+pub mod distribution;
+pub mod export;
+pub mod input;
+
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+pub use decode::{decode, DecodedAccount};
+pub use distribution::DistributionTree;
+pub use export::Snapshot;
+pub use input::{read_path, RawAccount};
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("{path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("{path}: {message}")]
+    Parse { path: PathBuf, message: String },
+    #[error("{address}: {message}")]
+    Decode { address: String, message: String },
+    #[error("distribution has {count} allocations, at most {max} can be claimed")]
+    TooManyAllocations { count: usize, max: u32 },
+}
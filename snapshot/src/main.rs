@@ -0,0 +1,138 @@
+use std::{
+    path::PathBuf,
+    process::ExitCode,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use ore_miner_delegation_snapshot::{read_path, DistributionTree, Snapshot, SnapshotError};
+use solana_program::pubkey::Pubkey;
+
+const USAGE: &str = "\
+usage: ore-delegation-snapshot <dump>... --out <dir> [--staker <address>]...
+           [--distribute <managed proof> <mint> <amount> [--now <unix timestamp>]]
+
+Reads program accounts from each <dump>, a .json account file, a base64
+account file named <address>.b64, or a directory of them, and writes
+positions, totals and managed_proofs tables as .csv and .json into <dir>.
+Pass --staker for stakers of DelegatedStake and DelegatedBoost accounts that
+cannot be recovered from the dump itself.
+
+With --distribute, <amount> is split across the boost stakers of <managed
+proof> in <mint> by their lock weight at --now (default: the current time),
+and the root and claim proofs are written to distribution.json.";
+
+struct Args {
+    dumps: Vec<PathBuf>,
+    out: PathBuf,
+    stakers: Vec<Pubkey>,
+    distribute: Option<(Pubkey, Pubkey, u64)>,
+    now: Option<i64>,
+}
+
+fn parse_pubkey(value: Option<String>) -> Result<Pubkey, String> {
+    let value = value.ok_or("missing address")?;
+    Pubkey::from_str(&value).map_err(|_| format!("invalid address {value}"))
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut dumps = vec![];
+    let mut out = None;
+    let mut stakers = vec![];
+    let mut distribute = None;
+    let mut now = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--out" => out = Some(PathBuf::from(args.next().ok_or("--out needs a directory")?)),
+            "--staker" => stakers.push(parse_pubkey(args.next())?),
+            "--distribute" => {
+                let managed_proof = parse_pubkey(args.next())?;
+                let mint = parse_pubkey(args.next())?;
+                let amount = args.next().ok_or("--distribute needs an amount")?;
+                let amount = amount
+                    .parse()
+                    .map_err(|_| format!("invalid amount {amount}"))?;
+                distribute = Some((managed_proof, mint, amount));
+            }
+            "--now" => {
+                let value = args.next().ok_or("--now needs a unix timestamp")?;
+                now = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("invalid timestamp {value}"))?,
+                );
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            _ => dumps.push(PathBuf::from(arg)),
+        }
+    }
+
+    match (dumps.is_empty(), out) {
+        (false, Some(out)) => Ok(Args {
+            dumps,
+            out,
+            stakers,
+            distribute,
+            now,
+        }),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = args
+        .dumps
+        .iter()
+        .map(|dump| read_path(dump))
+        .collect::<Result<Vec<_>, _>>()
+        .and_then(|accounts| Snapshot::build(&accounts.concat(), &args.stakers))
+        .and_then(|snapshot| {
+            std::fs::create_dir_all(&args.out).map_err(|source| SnapshotError::Io {
+                path: args.out.clone(),
+                source,
+            })?;
+            snapshot.write(&args.out)?;
+
+            if let Some((managed_proof, mint, amount)) = args.distribute {
+                let now = args.now.unwrap_or_else(|| {
+                    SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+                });
+                let tree =
+                    DistributionTree::by_lock_weight(&snapshot, &managed_proof, &mint, amount, now)?;
+                let path = args.out.join("distribution.json");
+                let json = serde_json::to_string_pretty(&tree).expect("distribution serializes to json");
+                std::fs::write(&path, json + "\n")
+                    .map_err(|source| SnapshotError::Io { path, source })?;
+            }
+
+            Ok(snapshot)
+        });
+
+    match result {
+        Ok(snapshot) => {
+            println!(
+                "exported {} positions across {} managed proofs to {}",
+                snapshot.positions.len(),
+                snapshot.managed_proofs.len(),
+                args.out.display()
+            );
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
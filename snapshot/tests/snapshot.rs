@@ -0,0 +1,235 @@
+use std::{fs, path::PathBuf, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ore_api::consts::MINT_ADDRESS;
+use ore_miner_delegation::{
+    consts::MAX_DISTRIBUTION_CLAIMS,
+    merkle::{distribution_leaf, verify},
+    pda::{delegated_boost_v2_pda, delegated_stake_pda, managed_proof_pda},
+    state::{DelegatedBoostV2, DelegatedStake, ManagedProof},
+    utils::{write_account_header, Discriminator, LayoutVersion},
+};
+use ore_miner_delegation_snapshot::{
+    export::{to_csv, Position, PositionKind},
+    read_path, DistributionTree, Snapshot, SnapshotError,
+};
+use solana_program::pubkey::Pubkey;
+
+fn account_data<T: Discriminator + LayoutVersion + bytemuck::Pod>(account: &T) -> Vec<u8> {
+    let mut data = vec![0u8; 8];
+    write_account_header::<T>(&mut data);
+    data.extend_from_slice(bytemuck::bytes_of(account));
+    data
+}
+
+fn json_entry(address: Pubkey, data: &[u8]) -> serde_json::Value {
+    serde_json::json!({
+        "pubkey": address.to_string(),
+        "account": {
+            "lamports": 1_000_000,
+            "data": [STANDARD.encode(data), "base64"],
+            "owner": ore_miner_delegation::id().to_string(),
+            "executable": false,
+            "rentEpoch": 0,
+        }
+    })
+}
+
+fn dump_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("ore-delegation-snapshot-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn test_snapshot_exports_positions() {
+    let miner = Pubkey::new_unique();
+    let staker = Pubkey::new_unique();
+    let boost_mint = Pubkey::new_unique();
+
+    let (managed_proof_address, bump) = managed_proof_pda(miner);
+    let mut managed_proof: ManagedProof = bytemuck::Zeroable::zeroed();
+    managed_proof.bump = bump;
+    managed_proof.miner_authority = miner;
+    managed_proof.payer = miner;
+    managed_proof.total_rewards = 7;
+
+    let (stake_address, bump) = delegated_stake_pda(miner, staker);
+    let mut stake: DelegatedStake = bytemuck::Zeroable::zeroed();
+    stake.bump = bump;
+    stake.amount = 300;
+    stake.payer = staker;
+
+    let (boost_address, bump) = delegated_boost_v2_pda(miner, staker, boost_mint);
+    let mut boost: DelegatedBoostV2 = bytemuck::Zeroable::zeroed();
+    boost.bump = bump;
+    boost.managed_proof_pubkey = managed_proof_address;
+    boost.authority = staker;
+    boost.mint = boost_mint;
+    boost.amount = 500;
+    boost.fee_payer = staker;
+    boost.locked_until = 42;
+
+    let dir = dump_dir("positions");
+    let entries = serde_json::json!([
+        json_entry(managed_proof_address, &account_data(&managed_proof)),
+        json_entry(boost_address, &account_data(&boost)),
+    ]);
+    fs::write(dir.join("accounts.json"), entries.to_string()).unwrap();
+
+    // The miner's own stake, dumped as a base64 file in its layout from
+    // before the payer was recorded.
+    let (miner_stake_address, bump) = delegated_stake_pda(miner, miner);
+    let mut miner_stake: DelegatedStake = bytemuck::Zeroable::zeroed();
+    miner_stake.bump = bump;
+    miner_stake.amount = 100;
+    let mut legacy = account_data(&miner_stake);
+    legacy.truncate(8 + 16);
+    legacy[1] = 0;
+    fs::write(dir.join(format!("{miner_stake_address}.b64")), STANDARD.encode(&legacy)).unwrap();
+
+    // The staker is only known from the boost, so the stake is still matched.
+    fs::write(
+        dir.join("stake.json"),
+        json_entry(stake_address, &account_data(&stake)).to_string(),
+    )
+    .unwrap();
+
+    let accounts = read_path(&dir).unwrap();
+    assert_eq!(accounts.len(), 4);
+    let snapshot = Snapshot::build(&accounts, &[]).unwrap();
+
+    assert_eq!(snapshot.managed_proofs.len(), 1);
+    assert_eq!(snapshot.managed_proofs[0].miner, Some(miner.to_string()));
+    assert_eq!(snapshot.managed_proofs[0].total_rewards, 7);
+
+    assert_eq!(snapshot.positions.len(), 3);
+    for position in &snapshot.positions {
+        assert_eq!(position.miner, Some(miner.to_string()));
+        assert_eq!(position.managed_proof, Some(managed_proof_address.to_string()));
+    }
+
+    let miner_position = snapshot
+        .positions
+        .iter()
+        .find(|position| position.address == miner_stake_address.to_string())
+        .unwrap();
+    assert_eq!(miner_position.staker, Some(miner.to_string()));
+    assert_eq!(miner_position.amount, 100);
+    assert_eq!(miner_position.payer, None);
+
+    let stake_position = snapshot
+        .positions
+        .iter()
+        .find(|position| position.address == stake_address.to_string())
+        .unwrap();
+    assert_eq!(stake_position.staker, Some(staker.to_string()));
+    assert_eq!(stake_position.mint, Some(MINT_ADDRESS.to_string()));
+
+    let total = snapshot
+        .totals
+        .iter()
+        .find(|total| total.mint == Some(boost_mint.to_string()))
+        .unwrap();
+    assert_eq!(total.staker, Some(staker.to_string()));
+    assert_eq!(total.amount, 500);
+    assert_eq!(total.positions, 1);
+
+    // Exporting the same dump twice gives the same tables.
+    let again = Snapshot::build(&read_path(&dir).unwrap(), &[]).unwrap();
+    assert_eq!(to_csv(&again.positions), to_csv(&snapshot.positions));
+    assert!(to_csv(&snapshot.positions).starts_with("staker,miner,mint,kind,address,"));
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_snapshot_skips_foreign_accounts() {
+    let dir = dump_dir("foreign");
+    let mut entry = json_entry(Pubkey::new_unique(), &[101, 0, 0, 0, 0, 0, 0, 0]);
+    entry["account"]["owner"] = Pubkey::new_unique().to_string().into();
+    fs::write(dir.join("foreign.json"), entry.to_string()).unwrap();
+
+    // A base64 dump has no owner; another program's account whose first byte
+    // happens to name a stake, but longer than any stake layout, is skipped.
+    let stake: DelegatedStake = bytemuck::Zeroable::zeroed();
+    let mut foreign = account_data(&stake);
+    foreign.extend_from_slice(&[0; 64]);
+    let address = Pubkey::new_unique();
+    fs::write(dir.join(format!("{address}.b64")), STANDARD.encode(&foreign)).unwrap();
+
+    let snapshot = Snapshot::build(&read_path(&dir).unwrap(), &[]).unwrap();
+    assert!(snapshot.positions.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_distribution_by_lock_weight() {
+    let miner = Pubkey::new_unique();
+    let managed_proof = managed_proof_pda(miner).0;
+    let mint = Pubkey::new_unique();
+    let unlocked = Pubkey::new_unique();
+    let locked = Pubkey::new_unique();
+
+    let position = |staker: &Pubkey, locked_until: i64| Position {
+        staker: Some(staker.to_string()),
+        miner: Some(miner.to_string()),
+        mint: Some(mint.to_string()),
+        kind: PositionKind::BoostV2,
+        address: Pubkey::new_unique().to_string(),
+        managed_proof: Some(managed_proof.to_string()),
+        amount: 100,
+        locked_until,
+        lock_bonus_bps: 10_000,
+        payer: None,
+    };
+    // Neither delegated stake nor the miner's other proofs get a share.
+    let stake = Position {
+        kind: PositionKind::Stake,
+        ..position(&Pubkey::new_unique(), 0)
+    };
+    let other_proof = Position {
+        managed_proof: Some(managed_proof_pda((miner, 1)).0.to_string()),
+        ..position(&Pubkey::new_unique(), 0)
+    };
+    let snapshot = Snapshot {
+        positions: vec![position(&unlocked, 0), position(&locked, 1_000), stake, other_proof],
+        ..Snapshot::default()
+    };
+
+    // the locked position earns the bonus until it unlocks
+    let tree =
+        DistributionTree::by_lock_weight(&snapshot, &managed_proof, &mint, 300, 500).unwrap();
+    assert_eq!(tree.total, 300);
+    assert_eq!(tree.allocations.len(), 2);
+    for allocation in &tree.allocations {
+        let staker = Pubkey::from_str(&allocation.staker).unwrap();
+        let expected = if staker == locked { 200 } else { 100 };
+        assert_eq!(allocation.amount, expected);
+
+        let proof = tree.proof(allocation.index as usize).unwrap();
+        assert!(verify(
+            &proof,
+            &tree.root_bytes(),
+            distribution_leaf(allocation.index, &staker, allocation.amount)
+        ));
+    }
+
+    let tree =
+        DistributionTree::by_lock_weight(&snapshot, &managed_proof, &mint, 300, 1_000).unwrap();
+    assert!(tree.allocations.iter().all(|allocation| allocation.amount == 150));
+}
+
+#[test]
+fn test_distribution_rejects_more_allocations_than_claimable() {
+    let allocations = vec![(Pubkey::new_unique(), 1); MAX_DISTRIBUTION_CLAIMS as usize + 1];
+    assert!(matches!(
+        DistributionTree::new(&allocations),
+        Err(SnapshotError::TooManyAllocations { .. })
+    ));
+
+    let tree = DistributionTree::new(&allocations[..2]).unwrap();
+    assert_eq!(tree.total, 2);
+}
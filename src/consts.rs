@@ -2,3 +2,26 @@ pub const MANAGED_PROOF: &[u8] = b"managed-proof-account";
 pub const DELEGATED_STAKE: &[u8] = b"delegated-stake";
 pub const DELEGATED_BOOST: &[u8] = b"delegated-boost";
 pub const DELEGATED_BOOST_V2: &[u8] = b"v2-delegated-boost";
+pub const MANAGED_PROOF_SETTINGS: &[u8] = b"managed-proof-settings";
+pub const STAKER_SESSION: &[u8] = b"staker-session";
+pub const DISTRIBUTION: &[u8] = b"distribution";
+pub const CLAIM_BITMAP: &[u8] = b"claim-bitmap";
+
+/// Most allocations a single distribution can hold, one bit each in its
+/// `ClaimBitmap`.
+pub const MAX_DISTRIBUTION_CLAIMS: u32 = 8 * 1024;
+
+/// Highest deposit fee a miner can charge on boost delegations, in basis
+/// points of the deposit.
+pub const MAX_DEPOSIT_FEE_BPS: u64 = 5_000;
+
+/// Shortest notice, in seconds, a miner can give before a fee increase takes
+/// effect.
+pub const MIN_FEE_CHANGE_DELAY: i64 = 24 * 60 * 60;
+
+/// Reward weight of an unlocked boost delegation, in basis points.
+pub const BASE_REWARD_WEIGHT_BPS: u64 = 10_000;
+
+/// Each mine moves a managed proof's reward rate this fraction of the way,
+/// 1/n, towards the latest sample.
+pub const REWARD_RATE_SMOOTHING: u64 = 8;
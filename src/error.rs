@@ -1,7 +1,10 @@
-use num_enum::IntoPrimitive;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use solana_program::{instruction::InstructionError, program_error::ProgramError};
 use thiserror::Error;
 
-#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, IntoPrimitive)]
+/// Errors returned by this program as `ProgramError::Custom(error as u32)`.
+/// Variants are only ever appended so the codes stay stable for clients.
+#[derive(Debug, Error, Clone, Copy, PartialEq, Eq, IntoPrimitive, TryFromPrimitive)]
 #[repr(u32)]
 pub enum OreDelegationError {
     #[error("Stake delegation window is currently closed")]
@@ -12,10 +15,89 @@ pub enum OreDelegationError {
     CloseAccountFeePayerMissmatch,
     #[error("Instruction has been removed")]
     InstructionRemoved,
+    #[error("Staker is not on the managed proof allowlist")]
+    StakerNotAllowlisted,
+    #[error("Delegation amount is below the managed proof minimum")]
+    DelegationBelowMinimum,
+    #[error("Delegation is locked")]
+    DelegationLocked,
+    #[error("Lock must extend the current lock and meet the minimum lock duration")]
+    InvalidLockDuration,
+    #[error("Account uses an older layout and must be upgraded")]
+    AccountNeedsUpgrade,
+    #[error("Account already uses the current layout")]
+    AccountAlreadyUpgraded,
+    #[error("Staker session has expired")]
+    SessionExpired,
+    #[error("Amount exceeds the staker session allowance")]
+    SessionAllowanceExceeded,
+    #[error("Withdrawals must go to a token account owned by the staker")]
+    InvalidWithdrawalDestination,
+    #[error("Account discriminator does not match the expected account type")]
+    InvalidAccountDiscriminator,
+    #[error("Account address does not match the expected PDA")]
+    InvalidAccountAddress,
+    #[error("Account must be writable")]
+    AccountNotWritable,
+    #[error("Account data does not match the account layout")]
+    InvalidAccountLayout,
+    #[error("Token account is for a different mint")]
+    TokenAccountMintMismatch,
+    #[error("Program account is not executable")]
+    ProgramNotExecutable,
+    #[error("Distribution allocation is not part of the posted root")]
+    InvalidDistributionProof,
+    #[error("Distribution allocation has already been claimed")]
+    DistributionAlreadyClaimed,
+    #[error("Claims exceed the distribution total")]
+    DistributionExhausted,
+    #[error("Distribution holds more allocations than a claim bitmap can track")]
+    TooManyDistributionClaims,
+    #[error("Deposit fee is above the maximum")]
+    DepositFeeTooHigh,
+    #[error("Deposit fee token account is not the fee recipient's account for this mint")]
+    InvalidDepositFeeAccount,
+    #[error("Fee change delay can't be shortened")]
+    FeeChangeDelayTooShort,
+    #[error("Deposit fee is above the maximum the staker accepted")]
+    DepositFeeAboveAccepted,
+    #[error("Global boost reservations are per proof and can't be shared by a batch")]
+    GlobalBoostInBatch,
+    #[error("Staker token account is already delegated to another key")]
+    TokenAccountAlreadyDelegated,
+    #[error("Distribution can only be closed once it has expired")]
+    DistributionNotExpired,
+    #[error("Global boost reservation holds another boost")]
+    ReservedForAnotherBoost,
+    #[error("Staker session is for another managed proof")]
+    SessionManagedProofMismatch,
+    #[error("Global boost reservation rotated to another boost")]
+    RotatedToAnotherBoost,
 }
 
-impl From<OreDelegationError> for solana_program::program_error::ProgramError {
+impl OreDelegationError {
+    /// Map an error returned by this program back to its variant. Custom codes
+    /// raised by other programs during a CPI share the same number space, so
+    /// only use this for errors known to come from this program.
+    pub fn from_program_error(error: &ProgramError) -> Option<Self> {
+        match error {
+            ProgramError::Custom(code) => Self::try_from(*code).ok(),
+            _ => None,
+        }
+    }
+
+    /// Same as [`OreDelegationError::from_program_error`] for the instruction
+    /// error of a failed transaction.
+    pub fn from_instruction_error(error: &InstructionError) -> Option<Self> {
+        match error {
+            InstructionError::Custom(code) => Self::try_from(*code).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl From<OreDelegationError> for ProgramError {
     fn from(e: OreDelegationError) -> Self {
-        solana_program::program_error::ProgramError::Custom(e as u32)
+        ProgramError::Custom(e as u32)
     }
 }
@@ -2,7 +2,7 @@ use ore_api::{consts::TREASURY_TOKENS_ADDRESS, state::proof_pda};
 use solana_program::pubkey;
 use steel::*;
 
-use crate::pda::managed_proof_pda;
+use crate::pda::{managed_proof_pda, ManagedProofId};
 
 pub static GLOBAL_BOOST_ID: Pubkey = pubkey!("BoosTyJFPPtrqJTdi49nnztoEWDJXfDRhyb2fha6PPy");
 
@@ -23,6 +23,14 @@ pub const CHECKPOINT: &[u8] = b"checkpoint";
 /// The seed of the reservation PDA.
 pub const RESERVATION: &[u8] = b"reservation";
 
+/// Seconds a reservation stays on one boost before it can be rotated. Copied
+/// from the rotation interval in `api/src/consts.rs` of regolith-labs/ore-boost,
+/// which the global boost program enforces, so the two must change together.
+pub const ROTATION_INTERVAL: i64 = 60;
+
+/// The number of boosts the directory can hold.
+pub const MAX_BOOSTS: usize = 256;
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
 #[rustfmt::skip]
@@ -67,7 +75,121 @@ instruction!(BoostInstruction, Initialize);
 instruction!(BoostInstruction, Register);
 instruction!(BoostInstruction, Rotate);
 
+// The account layouts below mirror the global boost program's definitions in
+// regolith-labs/ore-boost, `api/src/state/` (`config.rs`, `directory.rs`,
+// `reservation.rs`, `checkpoint.rs`), with the discriminators of
+// `api/src/state/mod.rs`. They are kept by hand, not derived, so check them
+// against that source whenever it changes.
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive)]
+pub enum BoostAccount {
+    Boost = 100,
+    Config = 101,
+    Stake = 102,
+    Checkpoint = 103,
+    Directory = 104,
+    Reservation = 105,
+}
+
+/// Global boost program settings.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct Config {
+    /// The admin allowed to add and update boosts.
+    pub admin: Pubkey,
+}
+
+/// The boosts a reservation can be rotated onto.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct Directory {
+    pub boosts: [Pubkey; MAX_BOOSTS],
+    pub len: u64,
+}
+
+impl Directory {
+    /// The active boosts, without the unused tail of `boosts`.
+    pub fn active_boosts(&self) -> &[Pubkey] {
+        &self.boosts[..(self.len as usize).min(MAX_BOOSTS)]
+    }
+}
+
+/// The boost an ORE proof is currently reserved to mine with.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct Reservation {
+    /// The ORE proof holding the reservation.
+    pub authority: Pubkey,
+    /// The reserved boost, or the default pubkey before the first rotation.
+    pub boost: Pubkey,
+    /// When the reservation last rotated.
+    pub ts: i64,
+}
 
+/// Progress of a boost's periodic reward checkpoint.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct Checkpoint {
+    pub boost: Pubkey,
+    /// The id of the next stake account to process.
+    pub current_id: u64,
+    pub total_pending_deposits: u64,
+    pub total_rewards: u64,
+    pub total_stakers: u64,
+    /// When the last checkpoint finished.
+    pub ts: i64,
+}
+
+account!(BoostAccount, Config);
+account!(BoostAccount, Directory);
+account!(BoostAccount, Reservation);
+account!(BoostAccount, Checkpoint);
+
+/// Where a managed proof's reservation points, as read by a keeper deciding
+/// whether to send `rotate_global_boost`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ReservationStatus {
+    /// The reserved boost, `None` until the reservation first rotates.
+    pub boost: Option<Pubkey>,
+    /// The earliest timestamp the reservation can rotate at.
+    pub next_rotation_at: i64,
+}
+
+impl ReservationStatus {
+    pub fn can_rotate(&self, now: i64) -> bool {
+        self.boost.is_none() || now >= self.next_rotation_at
+    }
+}
+
+impl Reservation {
+    pub fn status(&self) -> ReservationStatus {
+        ReservationStatus {
+            boost: (self.boost != Pubkey::default()).then_some(self.boost),
+            next_rotation_at: self.ts.saturating_add(ROTATION_INTERVAL),
+        }
+    }
+}
+
+/// Fetch the PDA of the reservation account of a managed proof's ORE proof.
+pub fn managed_proof_reservation_pda(miner: impl Into<ManagedProofId>) -> (Pubkey, u8) {
+    let managed_proof_address = managed_proof_pda(miner);
+    reservation_pda(proof_pda(managed_proof_address.0).0)
+}
+
+/// Decode the reservation account data of `miner`'s managed proof and
+/// report where it points.
+pub fn reservation_status(
+    miner: impl Into<ManagedProofId>,
+    data: &[u8],
+) -> Result<ReservationStatus, ProgramError> {
+    let managed_proof_address = managed_proof_pda(miner);
+    let reservation = Reservation::try_from_bytes(data)?;
+    if reservation.authority != proof_pda(managed_proof_address.0).0 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(reservation.status())
+}
 
 /// Fetch the PDA of the boost account.
 pub fn boost_pda(mint: Pubkey) -> (Pubkey, u8) {
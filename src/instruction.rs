@@ -5,13 +5,14 @@ use ore_api::{consts::TREASURY_TOKENS_ADDRESS, state::proof_pda};
 use ore_boost_api::state::{boost_pda, stake_pda};
 use solana_program::{
     instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
     pubkey::Pubkey,
     system_program, sysvar,
 };
 use spl_associated_token_account::get_associated_token_address;
 
 use crate::{
-    global_boost::{directory_pda, reservation_pda, GLOBAL_BOOST_ID}, impl_instruction_from_bytes, impl_to_bytes, pda::{delegated_boost_pda, delegated_boost_v2_pda, delegated_stake_pda, managed_proof_pda}
+    global_boost::{directory_pda, reservation_pda, GLOBAL_BOOST_ID}, impl_instruction_from_bytes, impl_to_bytes, pda::{claim_bitmap_pda, delegated_boost_pda, delegated_boost_v2_pda, delegated_stake_pda, distribution_pda, managed_proof_pda, managed_proof_settings_pda, staker_session_pda, ManagedProofId}
 };
 
 #[repr(u8)]
@@ -34,6 +35,20 @@ pub enum Instructions {
     RegisterGlobalBoost,
     RotateGlobalBoost,
     UpdateMiningAuthority,
+    InitManagedProofSettings,
+    UpdateAllowlist,
+    UpdateDelegationLimits,
+    LockDelegateBoostV2,
+    UpdateLockTerms,
+    UpgradeAccount,
+    MineBatch,
+    ApproveStakerSession,
+    RevokeStakerSession,
+    ExitDelegateBoostV2,
+    PostDistribution,
+    ClaimDistribution,
+    UpdateDepositFee,
+    CloseDistribution,
 }
 
 impl Into<Vec<u8>> for Instructions {
@@ -51,8 +66,22 @@ impl Instructions {
 impl_to_bytes!(MineArgs);
 impl_instruction_from_bytes!(MineArgs);
 
-pub fn open_managed_proof(miner: Pubkey) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct OpenManagedProofArgs {
+    pub index: [u8; 2],
+}
+
+impl_to_bytes!(OpenManagedProofArgs);
+impl_instruction_from_bytes!(OpenManagedProofArgs);
+
+/// Open a managed proof. Pass a miner key for its original proof, or
+/// `ManagedProofId::indexed(miner, index)` for one of its indexed proofs.
+/// `payer` pays the rent and can be a sponsor other than the miner.
+pub fn open_managed_proof(miner: impl Into<ManagedProofId>, payer: Pubkey) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
     let ore_proof_address = proof_pda(managed_proof_address.0);
 
     Instruction {
@@ -65,14 +94,31 @@ pub fn open_managed_proof(miner: Pubkey) -> Instruction {
             AccountMeta::new_readonly(sysvar::rent::id(), false),
             AccountMeta::new_readonly(ore_api::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(payer, true),
         ],
-        data: Instructions::OpenManagedProof.to_vec(),
+        data: [
+            Instructions::OpenManagedProof.to_vec(),
+            // The original proof keeps its index-less instruction data.
+            managed_proof
+                .index
+                .map(|index| {
+                    OpenManagedProofArgs {
+                        index: index.to_le_bytes(),
+                    }
+                    .to_bytes()
+                    .to_vec()
+                })
+                .unwrap_or_default(),
+        ]
+        .concat(),
     }
 }
 
-pub fn init_delegate_stake(staker: Pubkey, miner: Pubkey, payer: Pubkey) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
-    let delegated_stake_address = delegated_stake_pda(miner, staker);
+pub fn init_delegate_stake(staker: Pubkey, miner: impl Into<ManagedProofId>, payer: Pubkey) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_stake_address = delegated_stake_pda(managed_proof, staker);
 
     Instruction {
         program_id: crate::id(),
@@ -96,14 +142,84 @@ pub struct MineArgs {
     pub nonce: [u8; 8],
 }
 
-pub fn mine_with_boost(miner: Pubkey, bus: Pubkey, solution: Solution) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
+/// Mine without a boost.
+pub fn mine(miner: impl Into<ManagedProofId>, bus: Pubkey, solution: Solution) -> Instruction {
+    mine_with_boost_accounts(miner, bus, solution, vec![])
+}
+
+/// Mine with the ORE boost config and its proof.
+pub fn mine_with_boost(miner: impl Into<ManagedProofId>, bus: Pubkey, solution: Solution) -> Instruction {
+    mine_with_boost_accounts(miner, bus, solution, boost_config_accounts())
+}
+
+/// Mine with the global boost currently reserved for the managed proof.
+pub fn mine_with_global_boost(
+    miner: impl Into<ManagedProofId>,
+    bus: Pubkey,
+    solution: Solution,
+    boost: Pubkey,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let managed_proof_address = managed_proof_pda(managed_proof);
     let ore_proof_address = proof_pda(managed_proof_address.0);
-    let delegated_stake_address = delegated_stake_pda(miner, miner);
-    let boost_config = ore_boost_api::state::config_pda();
-    let boost_proof = ore_api::state::proof_pda(boost_config.0);
+    let boost_proof = ore_api::state::proof_pda(boost);
+    let reservation = reservation_pda(ore_proof_address.0);
 
-    let accounts = vec![
+    mine_with_boost_accounts(
+        managed_proof,
+        bus,
+        solution,
+        vec![
+            AccountMeta::new_readonly(boost, false),
+            AccountMeta::new(boost_proof.0, false),
+            AccountMeta::new(reservation.0, false),
+        ],
+    )
+}
+
+/// Mine with the global boost reserved for the managed proof, rotating the
+/// reservation first if it holds another boost. `boost` should be the boost
+/// the reservation will hold after any rotation; otherwise the mine fails.
+pub fn mine_and_rotate_global_boost(
+    miner: impl Into<ManagedProofId>,
+    bus: Pubkey,
+    solution: Solution,
+    boost: Pubkey,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let ore_proof_address = proof_pda(managed_proof_address.0);
+    let boost_proof = ore_api::state::proof_pda(boost);
+    let reservation = reservation_pda(ore_proof_address.0);
+
+    mine_with_boost_accounts(
+        managed_proof,
+        bus,
+        solution,
+        vec![
+            AccountMeta::new_readonly(boost, false),
+            AccountMeta::new(boost_proof.0, false),
+            AccountMeta::new(reservation.0, false),
+            AccountMeta::new_readonly(directory_pda().0, false),
+            AccountMeta::new_readonly(TREASURY_TOKENS_ADDRESS, false),
+            AccountMeta::new_readonly(GLOBAL_BOOST_ID, false),
+        ],
+    )
+}
+
+fn mine_with_boost_accounts(
+    miner: impl Into<ManagedProofId>,
+    bus: Pubkey,
+    solution: Solution,
+    boost_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let ore_proof_address = proof_pda(managed_proof_address.0);
+    let delegated_stake_address = delegated_stake_pda(managed_proof, miner);
+
+    let mut accounts = vec![
         AccountMeta::new(miner, true),
         AccountMeta::new(managed_proof_address.0, false),
         AccountMeta::new(bus, false),
@@ -114,9 +230,8 @@ pub fn mine_with_boost(miner: Pubkey, bus: Pubkey, solution: Solution) -> Instru
         AccountMeta::new_readonly(sysvar::instructions::id(), false),
         AccountMeta::new_readonly(ore_api::id(), false),
         AccountMeta::new_readonly(system_program::id(), false),
-        AccountMeta::new_readonly(boost_config.0, false),
-        AccountMeta::new(boost_proof.0, false)
     ];
+    accounts.extend(boost_accounts);
 
     Instruction {
         program_id: crate::id(),
@@ -134,6 +249,78 @@ pub fn mine_with_boost(miner: Pubkey, bus: Pubkey, solution: Solution) -> Instru
     }
 }
 
+fn boost_config_accounts() -> Vec<AccountMeta> {
+    let boost_config = ore_boost_api::state::config_pda();
+    let boost_proof = ore_api::state::proof_pda(boost_config.0);
+
+    vec![
+        AccountMeta::new_readonly(boost_config.0, false),
+        AccountMeta::new(boost_proof.0, false),
+    ]
+}
+
+/// One managed proof's solution in a [`mine_batch`] instruction.
+#[derive(Clone, Copy)]
+pub struct MineBatchEntry {
+    /// The managed proof to mine, `miner_key.into()` for a miner's original proof.
+    pub miner: ManagedProofId,
+    pub bus: Pubkey,
+    pub solution: Solution,
+}
+
+/// Mine several managed proofs in one instruction, without a boost.
+pub fn mine_batch(entries: &[MineBatchEntry]) -> Instruction {
+    mine_batch_with_boost_accounts(entries, vec![])
+}
+
+/// Mine several managed proofs in one instruction with the ORE boost config
+/// and its proof.
+pub fn mine_batch_with_boost(entries: &[MineBatchEntry]) -> Instruction {
+    mine_batch_with_boost_accounts(entries, boost_config_accounts())
+}
+
+fn mine_batch_with_boost_accounts(
+    entries: &[MineBatchEntry],
+    boost_accounts: Vec<AccountMeta>,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new_readonly(ore_api::consts::CONFIG_ADDRESS, false),
+        AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+        AccountMeta::new_readonly(sysvar::instructions::id(), false),
+        AccountMeta::new_readonly(ore_api::id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    let mut data = Instructions::MineBatch.to_vec();
+
+    for entry in entries {
+        let managed_proof_address = managed_proof_pda(entry.miner);
+        let ore_proof_address = proof_pda(managed_proof_address.0);
+        let delegated_stake_address = delegated_stake_pda(entry.miner, entry.miner.miner);
+
+        accounts.extend([
+            AccountMeta::new(entry.miner.miner, true),
+            AccountMeta::new(managed_proof_address.0, false),
+            AccountMeta::new(ore_proof_address.0, false),
+            AccountMeta::new(entry.bus, false),
+            AccountMeta::new(delegated_stake_address.0, false),
+        ]);
+        data.extend_from_slice(
+            MineArgs {
+                digest: entry.solution.d,
+                nonce: entry.solution.n,
+            }
+            .to_bytes(),
+        );
+    }
+    accounts.extend(boost_accounts);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts,
+        data,
+    }
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct DelegateStakeArgs {
@@ -143,10 +330,12 @@ pub struct DelegateStakeArgs {
 impl_to_bytes!(DelegateStakeArgs);
 impl_instruction_from_bytes!(DelegateStakeArgs);
 
-pub fn delegate_stake(staker: Pubkey, miner: Pubkey, amount: u64) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
+pub fn delegate_stake(staker: Pubkey, miner: impl Into<ManagedProofId>, amount: u64) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
     let ore_proof_address = proof_pda(managed_proof_address.0);
-    let delegated_stake_address = delegated_stake_pda(miner, staker);
+    let delegated_stake_address = delegated_stake_pda(managed_proof, staker);
 
     let staker_token_account =
         get_associated_token_address(&staker, &ore_api::consts::MINT_ADDRESS);
@@ -191,13 +380,15 @@ impl_instruction_from_bytes!(UndelegateStakeArgs);
 
 pub fn undelegate_stake(
     staker: Pubkey,
-    miner: Pubkey,
+    miner: impl Into<ManagedProofId>,
     beneficiary_token_account: Pubkey,
     amount: u64,
 ) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
     let ore_proof_address = proof_pda(managed_proof_address.0);
-    let delegated_stake_address = delegated_stake_pda(miner, staker);
+    let delegated_stake_address = delegated_stake_pda(managed_proof, staker);
 
     Instruction {
         program_id: crate::id(),
@@ -225,8 +416,14 @@ pub fn undelegate_stake(
     }
 }
 
-pub fn open_managed_proof_boost(miner: Pubkey, mint: Pubkey) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
+pub fn open_managed_proof_boost(
+    miner: impl Into<ManagedProofId>,
+    payer: Pubkey,
+    mint: Pubkey,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
     let (boost_pda, _) = ore_boost_api::state::boost_pda(mint);
     let (stake_pda, _) = ore_boost_api::state::stake_pda(managed_proof_address.0, boost_pda);
 
@@ -241,6 +438,7 @@ pub fn open_managed_proof_boost(miner: Pubkey, mint: Pubkey) -> Instruction {
             AccountMeta::new(stake_pda, false),
             AccountMeta::new_readonly(system_program::ID, false),
             AccountMeta::new_readonly(ore_boost_api::id(), false),
+            AccountMeta::new(payer, true),
 
         ],
         data: Instructions::OpenManagedProofBoost.into(),
@@ -251,14 +449,45 @@ pub fn open_managed_proof_boost(miner: Pubkey, mint: Pubkey) -> Instruction {
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct DelegateBoostArgs {
     pub amount: [u8; 8],
+    /// Highest deposit fee the staker accepts, in basis points.
+    pub max_deposit_fee_bps: [u8; 8],
 }
 
 impl_to_bytes!(DelegateBoostArgs);
 impl_instruction_from_bytes!(DelegateBoostArgs);
 
-pub fn delegate_boost(staker: Pubkey, miner: Pubkey, mint: Pubkey, amount: u64) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
-    let delegated_boost_address = delegated_boost_pda(miner, staker, mint);
+impl DelegateBoostArgs {
+    /// A fee limit no fee exceeds, so the fee goes unchecked.
+    pub const NO_LIMIT: u64 = u64::MAX;
+
+    /// `None` leaves the deposit fee unchecked.
+    pub fn new(amount: u64, max_deposit_fee_bps: Option<u64>) -> Self {
+        Self {
+            amount: amount.to_le_bytes(),
+            max_deposit_fee_bps: max_deposit_fee_bps
+                .unwrap_or(Self::NO_LIMIT)
+                .to_le_bytes(),
+        }
+    }
+
+    /// Split the args off the instruction data, returning them and the data
+    /// that follows. Data holding only an amount, as sent before the fee
+    /// limit was added, is read as args without a limit.
+    pub fn parse(data: &[u8]) -> Result<(Self, &[u8]), ProgramError> {
+        if let Ok(amount) = <[u8; 8]>::try_from(data) {
+            return Ok((Self::new(u64::from_le_bytes(amount), None), &[]));
+        }
+
+        let (args, rest) = crate::utils::split_instruction_data::<Self>(data)?;
+        Ok((*Self::try_from_bytes(args)?, rest))
+    }
+}
+
+pub fn delegate_boost(staker: Pubkey, miner: impl Into<ManagedProofId>, mint: Pubkey, amount: u64) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_boost_address = delegated_boost_pda(managed_proof, staker, mint);
 
     let staker_token_account =
         get_associated_token_address(&staker, &mint);
@@ -288,19 +517,17 @@ pub fn delegate_boost(staker: Pubkey, miner: Pubkey, mint: Pubkey, amount: u64)
         ],
         data: [
             Instructions::DelegateBoost.to_vec(),
-            DelegateBoostArgs {
-                amount: amount.to_le_bytes(),
-            }
-            .to_bytes()
-            .to_vec(),
+            DelegateBoostArgs::new(amount, None).to_bytes().to_vec(),
         ]
         .concat(),
     }
 }
 
-pub fn init_delegate_boost(staker: Pubkey, miner: Pubkey, payer: Pubkey, mint: Pubkey) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
-    let delegated_boost_address = delegated_boost_pda(miner, staker, mint);
+pub fn init_delegate_boost(staker: Pubkey, miner: impl Into<ManagedProofId>, payer: Pubkey, mint: Pubkey) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_boost_address = delegated_boost_pda(managed_proof, staker, mint);
 
     Instruction {
         program_id: crate::id(),
@@ -327,9 +554,11 @@ pub struct UndelegateBoostArgs {
 impl_to_bytes!(UndelegateBoostArgs);
 impl_instruction_from_bytes!(UndelegateBoostArgs);
 
-pub fn undelegate_boost(staker: Pubkey, miner: Pubkey, mint: Pubkey, amount: u64) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
-    let delegated_boost_address = delegated_boost_pda(miner, staker, mint);
+pub fn undelegate_boost(staker: Pubkey, miner: impl Into<ManagedProofId>, mint: Pubkey, amount: u64) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_boost_address = delegated_boost_pda(managed_proof, staker, mint);
 
     let staker_token_account =
         get_associated_token_address(&staker, &mint);
@@ -369,9 +598,46 @@ pub fn undelegate_boost(staker: Pubkey, miner: Pubkey, mint: Pubkey, amount: u64
     }
 }
 
-pub fn init_delegate_boost_v2(staker: Pubkey, miner: Pubkey, payer: Pubkey, mint: Pubkey) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
-    let delegated_boost_address = delegated_boost_v2_pda(miner, staker, mint);
+/// Create the staker's v2 delegation account for `mint`.
+///
+/// Since 0.11 the managed proof's settings account is a required account of
+/// `InitDelegateBoostV2`, `DelegateBoostV2` and `UndelegateBoostV2`, whether
+/// or not the miner has initialized it. Instructions built by earlier
+/// versions of this crate fail with `NotEnoughAccountKeys`.
+pub fn init_delegate_boost_v2(staker: Pubkey, miner: impl Into<ManagedProofId>, payer: Pubkey, mint: Pubkey) -> Instruction {
+    init_delegate_boost_v2_with_encoded_proof(staker, miner, payer, mint, vec![])
+}
+
+/// Like `init_delegate_boost_v2`, for a managed proof with an allowlist.
+/// Fails if the proof has more nodes than fit its one byte count.
+pub fn init_delegate_boost_v2_with_proof(
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    payer: Pubkey,
+    mint: Pubkey,
+    allowlist_proof: &[[u8; 32]],
+) -> Result<Instruction, ProgramError> {
+    Ok(init_delegate_boost_v2_with_encoded_proof(
+        staker,
+        miner,
+        payer,
+        mint,
+        merkle_proof_to_bytes(allowlist_proof)?,
+    ))
+}
+
+fn init_delegate_boost_v2_with_encoded_proof(
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    payer: Pubkey,
+    mint: Pubkey,
+    allowlist_proof: Vec<u8>,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_boost_address = delegated_boost_v2_pda(managed_proof, staker, mint);
+    let settings_address = managed_proof_settings_pda(managed_proof);
 
     Instruction {
         program_id: crate::id(),
@@ -384,14 +650,65 @@ pub fn init_delegate_boost_v2(staker: Pubkey, miner: Pubkey, payer: Pubkey, mint
             AccountMeta::new_readonly(mint, false),
             AccountMeta::new_readonly(sysvar::rent::id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(settings_address.0, false),
         ],
-        data: Instructions::InitDelegateBoostV2.into(),
+        data: [
+            Instructions::InitDelegateBoostV2.to_vec(),
+            allowlist_proof,
+        ]
+        .concat(),
     }
 }
 
-pub fn delegate_boost_v2(staker: Pubkey, miner: Pubkey, mint: Pubkey, amount: u64) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
-    let delegated_boost_address = delegated_boost_v2_pda(miner, staker, mint);
+/// Delegate `amount` of the staker's `mint`. Takes the managed proof's
+/// settings account since 0.11, see `init_delegate_boost_v2`.
+///
+/// The instruction fails if, when it lands, the managed proof's deposit fee
+/// is above `max_deposit_fee_bps`, so a fee raised after the staker signed
+/// can't be charged. `None` accepts any fee.
+pub fn delegate_boost_v2(
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    mint: Pubkey,
+    amount: u64,
+    max_deposit_fee_bps: Option<u64>,
+) -> Instruction {
+    delegate_boost_v2_with_encoded_proof(staker, miner, mint, amount, max_deposit_fee_bps, vec![])
+}
+
+/// Like `delegate_boost_v2`, for a managed proof with an allowlist.
+/// Fails if the proof has more nodes than fit its one byte count.
+pub fn delegate_boost_v2_with_proof(
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    mint: Pubkey,
+    amount: u64,
+    max_deposit_fee_bps: Option<u64>,
+    allowlist_proof: &[[u8; 32]],
+) -> Result<Instruction, ProgramError> {
+    Ok(delegate_boost_v2_with_encoded_proof(
+        staker,
+        miner,
+        mint,
+        amount,
+        max_deposit_fee_bps,
+        merkle_proof_to_bytes(allowlist_proof)?,
+    ))
+}
+
+fn delegate_boost_v2_with_encoded_proof(
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    mint: Pubkey,
+    amount: u64,
+    max_deposit_fee_bps: Option<u64>,
+    allowlist_proof: Vec<u8>,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_boost_address = delegated_boost_v2_pda(managed_proof, staker, mint);
+    let settings_address = managed_proof_settings_pda(managed_proof);
 
     let staker_token_account =
         get_associated_token_address(&staker, &mint);
@@ -434,23 +751,26 @@ pub fn delegate_boost_v2(staker: Pubkey, miner: Pubkey, mint: Pubkey, amount: u6
             AccountMeta::new(ore_api::consts::TREASURY_TOKENS_ADDRESS, false),
             AccountMeta::new_readonly(ore_api::ID, false),
             AccountMeta::new_readonly(spl_token::id(), false),
-
+            AccountMeta::new_readonly(settings_address.0, false),
         ],
         data: [
             Instructions::DelegateBoostV2.to_vec(),
-            DelegateBoostArgs {
-                amount: amount.to_le_bytes(),
-            }
-            .to_bytes()
-            .to_vec(),
+            DelegateBoostArgs::new(amount, max_deposit_fee_bps).to_bytes().to_vec(),
+            allowlist_proof,
         ]
         .concat(),
     }
 }
 
-pub fn undelegate_boost_v2(staker: Pubkey, miner: Pubkey, mint: Pubkey, amount: u64) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
-    let delegated_boost_address = delegated_boost_v2_pda(miner, staker, mint);
+/// Undelegate `amount` of the staker's `mint` back to its token account.
+/// Takes the managed proof's settings account since 0.11, see
+/// `init_delegate_boost_v2`.
+pub fn undelegate_boost_v2(staker: Pubkey, miner: impl Into<ManagedProofId>, mint: Pubkey, amount: u64) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_boost_address = delegated_boost_v2_pda(managed_proof, staker, mint);
+    let settings_address = managed_proof_settings_pda(managed_proof);
 
     let staker_token_account =
         get_associated_token_address(&staker, &mint);
@@ -492,6 +812,7 @@ pub fn undelegate_boost_v2(staker: Pubkey, miner: Pubkey, mint: Pubkey, amount:
             AccountMeta::new(ore_api::consts::TREASURY_TOKENS_ADDRESS, false),
             AccountMeta::new_readonly(ore_api::ID, false),
             AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(settings_address.0, false),
         ],
         data: [
             Instructions::UndelegateBoostV2.to_vec(),
@@ -505,10 +826,150 @@ pub fn undelegate_boost_v2(staker: Pubkey, miner: Pubkey, mint: Pubkey, amount:
     }
 }
 
-pub fn migrate_boost_to_v2(staker: Pubkey, miner: Pubkey, mint: Pubkey) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
-    let delegated_boost_address = delegated_boost_pda(miner, staker, mint);
-    let delegated_boost_address_v2 = delegated_boost_v2_pda(miner, staker, mint);
+/// Withdraw the staker's whole boost delegation, close the position and
+/// refund its rent to `payer`, the fee payer that opened it.
+pub fn exit_delegate_boost_v2(
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    payer: Pubkey,
+    mint: Pubkey,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_boost_address = delegated_boost_v2_pda(managed_proof, staker, mint);
+
+    let staker_token_account =
+        get_associated_token_address(&staker, &mint);
+    let managed_proof_token_account =
+        get_associated_token_address(&managed_proof_address.0, &mint);
+
+    let boost_address = boost_pda(mint).0;
+    let config_address = ore_boost_api::state::config_pda().0;
+    let deposits_address =
+        spl_associated_token_account::get_associated_token_address(&boost_address, &mint);
+
+    let config_proof_address = proof_pda(config_address).0;
+    let rewards_address = spl_associated_token_account::get_associated_token_address(
+        &config_address,
+        &ore_api::consts::MINT_ADDRESS,
+    );
+    let sender_address = spl_associated_token_account::get_associated_token_address(&managed_proof_address.0, &mint);
+    let stake_address = stake_pda(managed_proof_address.0, boost_address).0;
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(staker, true),
+            AccountMeta::new_readonly(miner, false),
+            AccountMeta::new(payer, false),
+            AccountMeta::new(managed_proof_address.0, false),
+            AccountMeta::new(managed_proof_token_account, false),
+            AccountMeta::new(delegated_boost_address.0, false),
+            AccountMeta::new(boost_address, false),
+            AccountMeta::new(config_address, false),
+            AccountMeta::new(deposits_address, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(config_proof_address, false),
+            AccountMeta::new(rewards_address, false),
+            AccountMeta::new(sender_address, false),
+            AccountMeta::new(staker_token_account, false),
+            AccountMeta::new(stake_address, false),
+            AccountMeta::new_readonly(ore_boost_api::id(), false),
+            AccountMeta::new_readonly(ore_api::consts::TREASURY_ADDRESS, false),
+            AccountMeta::new(ore_api::consts::TREASURY_TOKENS_ADDRESS, false),
+            AccountMeta::new_readonly(ore_api::ID, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: Instructions::ExitDelegateBoostV2.into(),
+    }
+}
+
+/// Have `operator` delegate `amount` of the staker's `mint` under a staker
+/// session, drawing on its allowance.
+pub fn delegate_boost_v2_with_session(
+    operator: Pubkey,
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    mint: Pubkey,
+    amount: u64,
+) -> Instruction {
+    with_staker_session(
+        delegate_boost_v2(staker, miner, mint, amount, None),
+        operator,
+        staker,
+        mint,
+    )
+}
+
+/// Have `operator` undelegate `amount` of the staker's `mint` under a staker
+/// session. The tokens go to the staker's associated token account.
+pub fn undelegate_boost_v2_with_session(
+    operator: Pubkey,
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    mint: Pubkey,
+    amount: u64,
+) -> Instruction {
+    with_staker_session(
+        undelegate_boost_v2(staker, miner, mint, amount),
+        operator,
+        staker,
+        mint,
+    )
+}
+
+/// Delegate to a managed proof that charges a deposit fee, paid to
+/// `fee_recipient`'s associated token account for `mint`.
+pub fn delegate_boost_v2_with_deposit_fee(
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    mint: Pubkey,
+    amount: u64,
+    max_deposit_fee_bps: Option<u64>,
+    fee_recipient: Pubkey,
+) -> Instruction {
+    with_deposit_fee_account(
+        delegate_boost_v2(staker, miner, mint, amount, max_deposit_fee_bps),
+        fee_recipient,
+        mint,
+    )
+}
+
+/// Add the deposit fee recipient's token account to a `delegate_boost_v2`
+/// instruction. It goes last, after any staker session accounts.
+pub fn with_deposit_fee_account(mut ix: Instruction, fee_recipient: Pubkey, mint: Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new(
+        get_associated_token_address(&fee_recipient, &mint),
+        false,
+    ));
+    ix
+}
+
+// The staker no longer signs; the operator and its session follow the
+// instruction's accounts instead.
+fn with_staker_session(
+    mut ix: Instruction,
+    operator: Pubkey,
+    staker: Pubkey,
+    mint: Pubkey,
+) -> Instruction {
+    let session_address = staker_session_pda(staker, mint, operator);
+
+    ix.accounts[0].is_signer = false;
+    ix.accounts.extend([
+        AccountMeta::new_readonly(operator, true),
+        AccountMeta::new(session_address.0, false),
+    ]);
+    ix
+}
+
+pub fn migrate_boost_to_v2(staker: Pubkey, miner: impl Into<ManagedProofId>, mint: Pubkey) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_boost_address = delegated_boost_pda(managed_proof, staker, mint);
+    let delegated_boost_address_v2 = delegated_boost_v2_pda(managed_proof, staker, mint);
 
     Instruction {
         program_id: crate::id(),
@@ -524,9 +985,11 @@ pub fn migrate_boost_to_v2(staker: Pubkey, miner: Pubkey, mint: Pubkey) -> Instr
     }
 }
 
-pub fn close_delegate_boost_v2(staker: Pubkey, miner: Pubkey, payer: Pubkey, mint: Pubkey) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
-    let delegated_boost_address = delegated_boost_v2_pda(miner, staker, mint);
+pub fn close_delegate_boost_v2(staker: Pubkey, miner: impl Into<ManagedProofId>, payer: Pubkey, mint: Pubkey) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_boost_address = delegated_boost_v2_pda(managed_proof, staker, mint);
 
     Instruction {
         program_id: crate::id(),
@@ -543,8 +1006,10 @@ pub fn close_delegate_boost_v2(staker: Pubkey, miner: Pubkey, payer: Pubkey, min
     }
 }
 
-pub fn register_global_boost(miner: Pubkey) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
+pub fn register_global_boost(miner: impl Into<ManagedProofId>, payer: Pubkey) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
     let ore_proof_address = proof_pda(managed_proof_address.0);
     let reservation = reservation_pda(ore_proof_address.0);
 
@@ -557,13 +1022,16 @@ pub fn register_global_boost(miner: Pubkey) -> Instruction {
             AccountMeta::new(reservation.0, false),
             AccountMeta::new_readonly(system_program::id(), false),
             AccountMeta::new_readonly(GLOBAL_BOOST_ID, false),
+            AccountMeta::new(payer, true),
         ],
         data: Instructions::RegisterGlobalBoost.into(),
     }
 }
 
-pub fn rotate_global_boost(miner: Pubkey) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
+pub fn rotate_global_boost(miner: impl Into<ManagedProofId>) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
     let ore_proof_address = proof_pda(managed_proof_address.0);
     let directory = directory_pda();
     let reservation = reservation_pda(ore_proof_address.0);
@@ -583,8 +1051,10 @@ pub fn rotate_global_boost(miner: Pubkey) -> Instruction {
     }
 }
 
-pub fn update_miner_authority(miner: Pubkey, new_miner_auth: Pubkey) -> Instruction {
-    let managed_proof_address = managed_proof_pda(miner);
+pub fn update_miner_authority(miner: impl Into<ManagedProofId>, new_miner_auth: Pubkey) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
     let ore_proof_address = proof_pda(managed_proof_address.0);
 
     let accounts = vec![
@@ -602,3 +1072,489 @@ pub fn update_miner_authority(miner: Pubkey, new_miner_auth: Pubkey) -> Instruct
     }
 }
 
+
+pub fn init_managed_proof_settings(miner: impl Into<ManagedProofId>, payer: Pubkey) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let settings_address = managed_proof_settings_pda(managed_proof);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(miner, true),
+            AccountMeta::new_readonly(managed_proof_address.0, false),
+            AccountMeta::new(settings_address.0, false),
+            AccountMeta::new_readonly(sysvar::rent::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(payer, true),
+        ],
+        data: Instructions::InitManagedProofSettings.into(),
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct UpdateAllowlistArgs {
+    pub root: [u8; 32],
+    pub enabled: u8,
+}
+
+impl_to_bytes!(UpdateAllowlistArgs);
+impl_instruction_from_bytes!(UpdateAllowlistArgs);
+
+/// Set the staker allowlist root of a managed proof. Passing `None` disables
+/// the allowlist so anyone can delegate.
+pub fn update_allowlist(miner: impl Into<ManagedProofId>, root: Option<[u8; 32]>) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let settings_address = managed_proof_settings_pda(managed_proof);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(miner, true),
+            AccountMeta::new_readonly(managed_proof_address.0, false),
+            AccountMeta::new(settings_address.0, false),
+        ],
+        data: [
+            Instructions::UpdateAllowlist.to_vec(),
+            UpdateAllowlistArgs {
+                root: root.unwrap_or_default(),
+                enabled: root.is_some() as u8,
+            }
+            .to_bytes()
+            .to_vec(),
+        ]
+        .concat(),
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct UpdateDelegationLimitsArgs {
+    pub min_delegation: [u8; 8],
+    pub min_remaining_balance: [u8; 8],
+}
+
+impl_to_bytes!(UpdateDelegationLimitsArgs);
+impl_instruction_from_bytes!(UpdateDelegationLimitsArgs);
+
+pub fn update_delegation_limits(
+    miner: impl Into<ManagedProofId>,
+    min_delegation: u64,
+    min_remaining_balance: u64,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let settings_address = managed_proof_settings_pda(managed_proof);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(miner, true),
+            AccountMeta::new_readonly(managed_proof_address.0, false),
+            AccountMeta::new(settings_address.0, false),
+        ],
+        data: [
+            Instructions::UpdateDelegationLimits.to_vec(),
+            UpdateDelegationLimitsArgs {
+                min_delegation: min_delegation.to_le_bytes(),
+                min_remaining_balance: min_remaining_balance.to_le_bytes(),
+            }
+            .to_bytes()
+            .to_vec(),
+        ]
+        .concat(),
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct LockDelegateBoostArgs {
+    pub locked_until: [u8; 8],
+}
+
+impl_to_bytes!(LockDelegateBoostArgs);
+impl_instruction_from_bytes!(LockDelegateBoostArgs);
+
+pub fn lock_delegate_boost_v2(
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    mint: Pubkey,
+    locked_until: i64,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_boost_address = delegated_boost_v2_pda(managed_proof, staker, mint);
+    let settings_address = managed_proof_settings_pda(managed_proof);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(staker, true),
+            AccountMeta::new_readonly(miner, false),
+            AccountMeta::new_readonly(managed_proof_address.0, false),
+            AccountMeta::new(delegated_boost_address.0, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(settings_address.0, false),
+        ],
+        data: [
+            Instructions::LockDelegateBoostV2.to_vec(),
+            LockDelegateBoostArgs {
+                locked_until: locked_until.to_le_bytes(),
+            }
+            .to_bytes()
+            .to_vec(),
+        ]
+        .concat(),
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct UpdateLockTermsArgs {
+    pub lock_bonus_bps: [u8; 8],
+    pub min_lock_duration: [u8; 8],
+}
+
+impl_to_bytes!(UpdateLockTermsArgs);
+impl_instruction_from_bytes!(UpdateLockTermsArgs);
+
+pub fn update_lock_terms(miner: impl Into<ManagedProofId>, lock_bonus_bps: u64, min_lock_duration: i64) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let settings_address = managed_proof_settings_pda(managed_proof);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(miner, true),
+            AccountMeta::new_readonly(managed_proof_address.0, false),
+            AccountMeta::new(settings_address.0, false),
+        ],
+        data: [
+            Instructions::UpdateLockTerms.to_vec(),
+            UpdateLockTermsArgs {
+                lock_bonus_bps: lock_bonus_bps.to_le_bytes(),
+                min_lock_duration: min_lock_duration.to_le_bytes(),
+            }
+            .to_bytes()
+            .to_vec(),
+        ]
+        .concat(),
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct UpdateDepositFeeArgs {
+    pub deposit_fee_bps: [u8; 8],
+    pub fee_change_delay: [u8; 8],
+}
+
+impl_to_bytes!(UpdateDepositFeeArgs);
+impl_instruction_from_bytes!(UpdateDepositFeeArgs);
+
+/// Charge `deposit_fee_bps` of every boost deposit into the managed proof,
+/// paid to `fee_recipient`'s associated token account for the boost mint. An
+/// increase takes effect after the settings' fee change delay, which
+/// `fee_change_delay` lengthens; pass 0 to keep it.
+pub fn update_deposit_fee(
+    miner: impl Into<ManagedProofId>,
+    deposit_fee_bps: u64,
+    fee_recipient: Pubkey,
+    fee_change_delay: i64,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let settings_address = managed_proof_settings_pda(managed_proof);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(miner, true),
+            AccountMeta::new_readonly(managed_proof_address.0, false),
+            AccountMeta::new(settings_address.0, false),
+            AccountMeta::new_readonly(fee_recipient, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            Instructions::UpdateDepositFee.to_vec(),
+            UpdateDepositFeeArgs {
+                deposit_fee_bps: deposit_fee_bps.to_le_bytes(),
+                fee_change_delay: fee_change_delay.to_le_bytes(),
+            }
+            .to_bytes()
+            .to_vec(),
+        ]
+        .concat(),
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ApproveStakerSessionArgs {
+    pub expires_at: [u8; 8],
+    pub allowance: [u8; 8],
+}
+
+impl_to_bytes!(ApproveStakerSessionArgs);
+impl_instruction_from_bytes!(ApproveStakerSessionArgs);
+
+/// Let `operator` delegate up to `allowance` of the staker's `mint` to
+/// `miner`'s managed proof, and undelegate it back to the staker, until
+/// `expires_at`. `payer` pays the
+/// session's rent the first time it is approved. Only one session per staker
+/// and mint can hold the token approval; revoke the old one before approving
+/// another operator.
+pub fn approve_staker_session(
+    staker: Pubkey,
+    payer: Pubkey,
+    operator: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    mint: Pubkey,
+    expires_at: i64,
+    allowance: u64,
+) -> Instruction {
+    let managed_proof_address = managed_proof_pda(miner);
+    let session_address = staker_session_pda(staker, mint, operator);
+    let staker_token_account = get_associated_token_address(&staker, &mint);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(staker, true),
+            AccountMeta::new_readonly(operator, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(managed_proof_address.0, false),
+            AccountMeta::new(session_address.0, false),
+            AccountMeta::new(staker_token_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(payer, true),
+        ],
+        data: [
+            Instructions::ApproveStakerSession.to_vec(),
+            ApproveStakerSessionArgs {
+                expires_at: expires_at.to_le_bytes(),
+                allowance: allowance.to_le_bytes(),
+            }
+            .to_bytes()
+            .to_vec(),
+        ]
+        .concat(),
+    }
+}
+
+/// End the session, expired or not, and clear its token approval. The rent
+/// goes back to `payer`, who must be the account that paid it on approval.
+pub fn revoke_staker_session(
+    staker: Pubkey,
+    payer: Pubkey,
+    operator: Pubkey,
+    mint: Pubkey,
+) -> Instruction {
+    let session_address = staker_session_pda(staker, mint, operator);
+    let staker_token_account = get_associated_token_address(&staker, &mint);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(staker, true),
+            AccountMeta::new_readonly(operator, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(session_address.0, false),
+            AccountMeta::new(staker_token_account, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new(payer, false),
+        ],
+        data: Instructions::RevokeStakerSession.into(),
+    }
+}
+
+/// Upgrade a program account to the current layout of its type, in place.
+/// `seeds` are the keys the account's PDA is derived from, after its type
+/// seed and in seed order: the miner for a `ManagedProof`, the staker and
+/// managed proof for a `DelegatedStake`, the staker, mint and managed proof
+/// for a `DelegatedBoost` or `DelegatedBoostV2`, the managed proof for a
+/// `ManagedProofSettings` or `Distribution`, the staker, mint and operator for
+/// a `StakerSession`, and the distribution for a `ClaimBitmap`.
+pub fn upgrade_account(payer: Pubkey, account: Pubkey, seeds: &[Pubkey]) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(payer, true),
+        AccountMeta::new(account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(seeds.iter().map(|seed| AccountMeta::new_readonly(*seed, false)));
+
+    Instruction {
+        program_id: crate::id(),
+        accounts,
+        data: Instructions::UpgradeAccount.into(),
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct PostDistributionArgs {
+    pub id: [u8; 8],
+    pub root: [u8; 32],
+    pub total: [u8; 8],
+    pub leaf_count: [u8; 4],
+    pub expires_at: [u8; 8],
+}
+
+impl_to_bytes!(PostDistributionArgs);
+impl_instruction_from_bytes!(PostDistributionArgs);
+
+/// Post a merkle root of `leaf_count` allocations, built from
+/// `merkle::distribution_leaf`, that share `total` ORE out of the miner's own
+/// stake. `payer` pays the rent of the distribution and its claim bitmap.
+/// From `expires_at` the miner can close it with [`close_distribution`].
+pub fn post_distribution(
+    miner: impl Into<ManagedProofId>,
+    payer: Pubkey,
+    id: u64,
+    root: [u8; 32],
+    total: u64,
+    leaf_count: u32,
+    expires_at: i64,
+) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_stake_address = delegated_stake_pda(managed_proof, miner);
+    let distribution_address = distribution_pda(managed_proof, id);
+    let claim_bitmap_address = claim_bitmap_pda(distribution_address.0);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new(miner, true),
+            AccountMeta::new(payer, true),
+            AccountMeta::new_readonly(managed_proof_address.0, false),
+            AccountMeta::new(delegated_stake_address.0, false),
+            AccountMeta::new(distribution_address.0, false),
+            AccountMeta::new(claim_bitmap_address.0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: [
+            Instructions::PostDistribution.to_vec(),
+            PostDistributionArgs {
+                id: id.to_le_bytes(),
+                root,
+                total: total.to_le_bytes(),
+                leaf_count: leaf_count.to_le_bytes(),
+                expires_at: expires_at.to_le_bytes(),
+            }
+            .to_bytes()
+            .to_vec(),
+        ]
+        .concat(),
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ClaimDistributionArgs {
+    pub index: [u8; 4],
+    pub amount: [u8; 8],
+}
+
+impl_to_bytes!(ClaimDistributionArgs);
+impl_instruction_from_bytes!(ClaimDistributionArgs);
+
+/// Claim the staker's allocation at `index` of distribution `id` into the
+/// staker's ORE token account. Any key can sign the transaction. Fails if the
+/// proof has more nodes than fit its one byte count.
+pub fn claim_distribution(
+    staker: Pubkey,
+    miner: impl Into<ManagedProofId>,
+    id: u64,
+    index: u32,
+    amount: u64,
+    proof: &[[u8; 32]],
+) -> Result<Instruction, ProgramError> {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let ore_proof_address = proof_pda(managed_proof_address.0);
+    let distribution_address = distribution_pda(managed_proof, id);
+    let claim_bitmap_address = claim_bitmap_pda(distribution_address.0);
+    let beneficiary = get_associated_token_address(&staker, &ore_api::consts::MINT_ADDRESS);
+
+    Ok(Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(staker, false),
+            AccountMeta::new_readonly(miner, false),
+            AccountMeta::new(managed_proof_address.0, false),
+            AccountMeta::new(distribution_address.0, false),
+            AccountMeta::new(claim_bitmap_address.0, false),
+            AccountMeta::new(beneficiary, false),
+            AccountMeta::new(ore_proof_address.0, false),
+            AccountMeta::new(ore_api::consts::TREASURY_ADDRESS, false),
+            AccountMeta::new(ore_api::consts::TREASURY_TOKENS_ADDRESS, false),
+            AccountMeta::new_readonly(ore_api::id(), false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: [
+            Instructions::ClaimDistribution.to_vec(),
+            ClaimDistributionArgs {
+                index: index.to_le_bytes(),
+                amount: amount.to_le_bytes(),
+            }
+            .to_bytes()
+            .to_vec(),
+            merkle_proof_to_bytes(proof)?,
+        ]
+        .concat(),
+    })
+}
+
+/// Close expired distribution `id`, crediting what stakers left unclaimed
+/// back to the miner's stake and refunding the rent of the distribution and
+/// its claim bitmap to `payer`, the account that paid it.
+pub fn close_distribution(miner: impl Into<ManagedProofId>, payer: Pubkey, id: u64) -> Instruction {
+    let managed_proof: ManagedProofId = miner.into();
+    let miner = managed_proof.miner;
+    let managed_proof_address = managed_proof_pda(managed_proof);
+    let delegated_stake_address = delegated_stake_pda(managed_proof, miner);
+    let distribution_address = distribution_pda(managed_proof, id);
+    let claim_bitmap_address = claim_bitmap_pda(distribution_address.0);
+
+    Instruction {
+        program_id: crate::id(),
+        accounts: vec![
+            AccountMeta::new_readonly(miner, true),
+            AccountMeta::new(payer, false),
+            AccountMeta::new_readonly(managed_proof_address.0, false),
+            AccountMeta::new(delegated_stake_address.0, false),
+            AccountMeta::new(distribution_address.0, false),
+            AccountMeta::new(claim_bitmap_address.0, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: Instructions::CloseDistribution.into(),
+    }
+}
+
+// Encode a merkle proof as a node count followed by the nodes. An empty proof
+// encodes to nothing so instructions stay unchanged when no allowlist is used.
+fn merkle_proof_to_bytes(proof: &[[u8; 32]]) -> Result<Vec<u8>, ProgramError> {
+    if proof.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let count: u8 = proof
+        .len()
+        .try_into()
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    Ok([vec![count], proof.concat()].concat())
+}
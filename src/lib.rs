@@ -8,12 +8,13 @@ mod processor;
 
 pub mod consts;
 pub mod error;
+pub mod global_boost;
 pub mod instruction;
 pub mod loaders;
+pub mod merkle;
 pub mod pda;
 pub mod state;
 pub mod utils;
-pub mod global_boost;
 
 declare_id!("J6XAzG8S5KmoBM8GcCFfF8NmtzD7U3QPnbhNiYwsu9we");
 
@@ -88,6 +89,48 @@ pub fn process_instruction(
         Instructions::UpdateMiningAuthority => {
             processor::update_miner_authority::process_update_miner_authority(accounts, data)?;
         }
+        Instructions::InitManagedProofSettings => {
+            processor::init_managed_proof_settings::process_init_managed_proof_settings(accounts, data)?;
+        }
+        Instructions::UpdateAllowlist => {
+            processor::update_allowlist::process_update_allowlist(accounts, data)?;
+        }
+        Instructions::UpdateDelegationLimits => {
+            processor::update_delegation_limits::process_update_delegation_limits(accounts, data)?;
+        }
+        Instructions::LockDelegateBoostV2 => {
+            processor::lock_delegate_boost_v2::process_lock_delegate_boost_v2(accounts, data)?;
+        }
+        Instructions::UpdateLockTerms => {
+            processor::update_lock_terms::process_update_lock_terms(accounts, data)?;
+        }
+        Instructions::UpgradeAccount => {
+            processor::upgrade_account::process_upgrade_account(accounts, data)?;
+        }
+        Instructions::MineBatch => {
+            processor::mine_batch::process_mine_batch(accounts, data)?;
+        }
+        Instructions::ApproveStakerSession => {
+            processor::approve_staker_session::process_approve_staker_session(accounts, data)?;
+        }
+        Instructions::RevokeStakerSession => {
+            processor::revoke_staker_session::process_revoke_staker_session(accounts, data)?;
+        }
+        Instructions::ExitDelegateBoostV2 => {
+            processor::exit_delegate_boost_v2::process_exit_delegate_boost_v2(accounts, data)?;
+        }
+        Instructions::PostDistribution => {
+            processor::post_distribution::process_post_distribution(accounts, data)?;
+        }
+        Instructions::ClaimDistribution => {
+            processor::claim_distribution::process_claim_distribution(accounts, data)?;
+        }
+        Instructions::UpdateDepositFee => {
+            processor::update_deposit_fee::process_update_deposit_fee(accounts, data)?;
+        }
+        Instructions::CloseDistribution => {
+            processor::close_distribution::process_close_distribution(accounts, data)?;
+        }
     }
 
     Ok(())
@@ -1,152 +1,314 @@
-use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+use std::{
+    cell::{Ref, RefMut},
+    mem::size_of,
+};
+
+use bytemuck::Zeroable;
+use solana_program::{
+    account_info::AccountInfo,
+    program_error::ProgramError,
+    pubkey::{Pubkey, MAX_SEEDS},
+};
 
 use crate::{
-    state::{DelegatedBoost, DelegatedBoostV2, DelegatedStake, ManagedProof},
-    utils::AccountDeserializeV1,
+    error::OreDelegationError,
+    state::{Distribution, ManagedProof, ManagedProofSettings, StakerSession},
+    utils::{AccountDeserializeV1, ProgramAccount},
 };
 
-pub fn load_managed_proof<'a, 'info>(
+/// Load a copy of a program account after checking its owner, discriminator,
+/// PDA and writability. `seeds` are the PDA seeds that follow the type's own
+/// seed, without the bump.
+pub fn load<'a, 'info, T: ProgramAccount>(
     info: &'a AccountInfo<'info>,
-    miner: &Pubkey,
+    seeds: &[&[u8]],
+    is_writable: bool,
+) -> Result<T, ProgramError> {
+    Ok(*load_ref::<T>(info, seeds, is_writable)?)
+}
+
+/// Same checks as [`load`], returning a reference into the account data.
+pub fn load_ref<'a, 'info, T: ProgramAccount>(
+    info: &'a AccountInfo<'info>,
+    seeds: &[&[u8]],
+    is_writable: bool,
+) -> Result<Ref<'a, T>, ProgramError> {
+    check_account::<T>(info, seeds, is_writable)?;
+
+    let data = info
+        .data
+        .try_borrow()
+        .or(Err(ProgramError::AccountBorrowFailed))?;
+
+    Ok(Ref::map(data, |data| {
+        bytemuck::from_bytes::<T>(&data[8..8 + size_of::<T>()])
+    }))
+}
+
+/// Same checks as [`load`] for a writable account, returning a mutable
+/// reference into the account data.
+pub fn load_mut<'a, 'info, T: ProgramAccount>(
+    info: &'a AccountInfo<'info>,
+    seeds: &[&[u8]],
+) -> Result<RefMut<'a, T>, ProgramError> {
+    check_account::<T>(info, seeds, true)?;
+
+    let data = info
+        .data
+        .try_borrow_mut()
+        .or(Err(ProgramError::AccountBorrowFailed))?;
+
+    Ok(RefMut::map(data, |data| {
+        bytemuck::from_bytes_mut::<T>(&mut data[8..8 + size_of::<T>()])
+    }))
+}
+
+fn check_account<T: ProgramAccount>(
+    info: &AccountInfo,
+    seeds: &[&[u8]],
     is_writable: bool,
 ) -> Result<(), ProgramError> {
-    if info.owner.ne(&crate::id()) {
-        return Err(ProgramError::InvalidAccountOwner);
-    }
+    check_header::<T>(info, is_writable)?;
 
-    if info.data_is_empty() {
-        return Err(ProgramError::UninitializedAccount);
-    }
+    let bump = {
+        let data = info
+            .data
+            .try_borrow()
+            .or(Err(ProgramError::AccountBorrowFailed))?;
+        T::try_from_bytes(&data)?.bump()
+    };
 
-    let managed_proof_data = info.data.borrow();
-    let managed_proof = ManagedProof::try_from_bytes(&managed_proof_data)?;
+    check_address::<T>(info, seeds, bump)
+}
 
-    if managed_proof.miner_authority != *miner {
-        return Err(ProgramError::InvalidAccountData);
-    }
+/// Check an account of type `T` that may still use an older, shorter layout,
+/// so it can be upgraded: its owner, discriminator, writability and PDA. Every
+/// layout starts with the bump, so nothing past it is read.
+pub fn check_upgradable<T: ProgramAccount>(
+    info: &AccountInfo,
+    seeds: &[&[u8]],
+) -> Result<(), ProgramError> {
+    check_header::<T>(info, true)?;
+    let bump = legacy_bytes::<T, 1>(info)?[0];
+    check_address::<T>(info, seeds, bump)
+}
 
-    if is_writable && !info.is_writable {
-        return Err(ProgramError::InvalidAccountData);
-    }
-    Ok(())
+/// Same as [`check_upgradable`] for a managed proof of `miner`, indexed or
+/// not. The index has been stored since the first layout.
+pub fn check_upgradable_managed_proof(
+    info: &AccountInfo,
+    miner: &Pubkey,
+) -> Result<(), ProgramError> {
+    check_header::<ManagedProof>(info, true)?;
+
+    // bump, is_indexed, index
+    let [bump, is_indexed, index @ ..] = legacy_bytes::<ManagedProof, 4>(info)?;
+    let indexed_seeds: [&[u8]; 2] = [miner.as_ref(), &index];
+    let seeds: &[&[u8]] = if is_indexed != 0 {
+        &indexed_seeds
+    } else {
+        &indexed_seeds[..1]
+    };
+
+    check_address::<ManagedProof>(info, seeds, bump)
 }
 
-pub fn load_delegated_stake<'a, 'info>(
-    info: &'a AccountInfo<'info>,
-    delegate_authority: &Pubkey,
+/// Same as [`check_upgradable`] for a distribution of `managed_proof`. The id
+/// is seeded and has been stored since the first layout.
+pub fn check_upgradable_distribution(
+    info: &AccountInfo,
     managed_proof: &Pubkey,
-    is_writable: bool,
 ) -> Result<(), ProgramError> {
+    check_header::<Distribution>(info, true)?;
+
+    // bump, padding, managed_proof, id
+    let bytes = legacy_bytes::<Distribution, 48>(info)?;
+    check_address::<Distribution>(info, &[managed_proof.as_ref(), &bytes[40..]], bytes[0])
+}
+
+// Owner, initialization and writability, shared by every loader.
+fn check_header<T: ProgramAccount>(info: &AccountInfo, is_writable: bool) -> Result<(), ProgramError> {
+    let account_type = T::discriminator();
+
     if info.owner.ne(&crate::id()) {
+        solana_program::msg!("{:?} {}: not owned by this program", account_type, info.key);
         return Err(ProgramError::InvalidAccountOwner);
     }
 
     if info.data_is_empty() {
+        solana_program::msg!("{:?} {}: not initialized", account_type, info.key);
         return Err(ProgramError::UninitializedAccount);
     }
 
-    let delegated_stake_data = info.data.borrow();
-    let delegated_stake = DelegatedStake::try_from_bytes(&delegated_stake_data)?;
-
-    let delegated_stake_pda = Pubkey::create_program_address(
-        &[
-            crate::consts::DELEGATED_STAKE,
-            delegate_authority.as_ref(),
-            managed_proof.as_ref(),
-            &[delegated_stake.bump],
-        ],
-        &crate::id(),
-    )?;
+    if is_writable && !info.is_writable {
+        solana_program::msg!("{:?} {}: must be writable", account_type, info.key);
+        return Err(OreDelegationError::AccountNotWritable.into());
+    }
 
-    if *info.key != delegated_stake_pda {
-        return Err(ProgramError::InvalidAccountData);
+    Ok(())
+}
+
+// The first `N` bytes of the body of an account of any layout of `T`, after
+// checking its discriminator.
+fn legacy_bytes<T: ProgramAccount, const N: usize>(info: &AccountInfo) -> Result<[u8; N], ProgramError> {
+    let data = info
+        .data
+        .try_borrow()
+        .or(Err(ProgramError::AccountBorrowFailed))?;
+
+    if data.len() < 8 + N || data[0] != T::discriminator() as u8 {
+        solana_program::msg!("{:?} {}: wrong discriminator or too short", T::discriminator(), info.key);
+        return Err(OreDelegationError::InvalidAccountDiscriminator.into());
     }
 
-    if is_writable && !info.is_writable {
-        return Err(ProgramError::InvalidAccountData);
+    let mut bytes = [0; N];
+    bytes.copy_from_slice(&data[8..8 + N]);
+    Ok(bytes)
+}
+
+fn check_address<T: ProgramAccount>(
+    info: &AccountInfo,
+    seeds: &[&[u8]],
+    bump: u8,
+) -> Result<(), ProgramError> {
+    let bump = [bump];
+
+    // type seed, caller seeds, bump
+    if seeds.len() + 2 > MAX_SEEDS {
+        return Err(ProgramError::MaxSeedLengthExceeded);
+    }
+
+    let mut pda_seeds: [&[u8]; MAX_SEEDS] = [&[]; MAX_SEEDS];
+    pda_seeds[0] = T::seed();
+    pda_seeds[1..seeds.len() + 1].copy_from_slice(seeds);
+    pda_seeds[seeds.len() + 1] = &bump;
+
+    let pda = Pubkey::create_program_address(&pda_seeds[..seeds.len() + 2], &crate::id())?;
+
+    if *info.key != pda {
+        solana_program::msg!("{:?} {}: address does not match its seeds", T::discriminator(), info.key);
+        return Err(OreDelegationError::InvalidAccountAddress.into());
     }
 
     Ok(())
 }
 
-pub fn load_delegated_boost<'a, 'info>(
+/// Load a managed proof of `miner`, indexed or not. An indexed proof's index
+/// is stored in the account, so the account is read once and the PDA rebuilt
+/// from what it holds; an account that isn't one of the miner's proofs still
+/// fails the address check.
+pub fn load_managed_proof<'a, 'info>(
     info: &'a AccountInfo<'info>,
-    delegate_authority: &Pubkey,
-    managed_proof: &Pubkey,
-    mint: &Pubkey,
+    miner: &Pubkey,
     is_writable: bool,
-) -> Result<(), ProgramError> {
+) -> Result<ManagedProof, ProgramError> {
+    check_header::<ManagedProof>(info, is_writable)?;
+
+    let managed_proof = {
+        let data = info
+            .data
+            .try_borrow()
+            .or(Err(ProgramError::AccountBorrowFailed))?;
+        *ManagedProof::try_from_bytes(&data)?
+    };
+
+    check_address::<ManagedProof>(info, managed_proof.seeds(miner).as_slice(), managed_proof.bump)?;
+
+    Ok(managed_proof)
+}
+
+/// Load a distribution of `managed_proof` for update. Its id is seeded into
+/// the address, so like [`load_managed_proof`] it is read from the account
+/// before the PDA is checked.
+pub fn load_distribution<'a, 'info>(
+    info: &'a AccountInfo<'info>,
+    managed_proof: &Pubkey,
+) -> Result<RefMut<'a, Distribution>, ProgramError> {
     if info.owner.ne(&crate::id()) {
+        solana_program::msg!("Distribution {}: not owned by this program", info.key);
         return Err(ProgramError::InvalidAccountOwner);
     }
 
-    if info.data_is_empty() {
-        return Err(ProgramError::UninitializedAccount);
+    let id = {
+        let data = info
+            .data
+            .try_borrow()
+            .or(Err(ProgramError::AccountBorrowFailed))?;
+        Distribution::try_from_bytes(&data)?.id.to_le_bytes()
+    };
+
+    load_mut::<Distribution>(info, &[managed_proof.as_ref(), &id])
+}
+
+/// Load the session that lets `operator` act for `staker` on boosts of
+/// `mint`, after checking the operator signed, the session hasn't expired and
+/// the staker approved it for `managed_proof`.
+pub fn load_staker_session<'a, 'info>(
+    info: &'a AccountInfo<'info>,
+    staker: &Pubkey,
+    mint: &Pubkey,
+    managed_proof: &Pubkey,
+    operator: &AccountInfo<'info>,
+    now: i64,
+) -> Result<RefMut<'a, StakerSession>, ProgramError> {
+    if !operator.is_signer {
+        solana_program::msg!("operator {}: must sign", operator.key);
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let delegated_boost_data = info.data.borrow();
-    let delegated_boost = DelegatedBoost::try_from_bytes(&delegated_boost_data)?;
-
-    let delegated_boost_pda = Pubkey::create_program_address(
-        &[
-            crate::consts::DELEGATED_BOOST,
-            delegate_authority.as_ref(),
-            mint.as_ref(),
-            managed_proof.as_ref(),
-            &[delegated_boost.bump],
-        ],
-        &crate::id(),
+    let session = load_mut::<StakerSession>(
+        info,
+        &[staker.as_ref(), mint.as_ref(), operator.key.as_ref()],
     )?;
 
-    if *info.key != delegated_boost_pda {
-        return Err(ProgramError::InvalidAccountData);
+    if session.is_expired(now) {
+        solana_program::msg!("StakerSession {}: expired at {}", info.key, session.expires_at);
+        return Err(OreDelegationError::SessionExpired.into());
     }
 
-    if is_writable && !info.is_writable {
-        return Err(ProgramError::InvalidAccountData);
+    if session.managed_proof != *managed_proof {
+        solana_program::msg!("StakerSession {}: approved for {}", info.key, session.managed_proof);
+        return Err(OreDelegationError::SessionManagedProofMismatch.into());
     }
 
-    Ok(())
+    Ok(session)
 }
 
-pub fn load_delegated_boost_v2<'a, 'info>(
+// The settings account is optional for a managed proof. An uninitialized
+// account at the correct address loads as the default (all features off).
+// Its address is rebuilt from the bump the managed proof stores, and only
+// searched for on proofs opened before the bump was stored.
+pub fn load_managed_proof_settings<'a, 'info>(
     info: &'a AccountInfo<'info>,
-    delegate_authority: &Pubkey,
-    managed_proof: &Pubkey,
-    mint: &Pubkey,
+    managed_proof_key: &Pubkey,
+    managed_proof: &ManagedProof,
     is_writable: bool,
-) -> Result<DelegatedBoostV2, ProgramError> {
-    if info.owner.ne(&crate::id()) {
-        return Err(ProgramError::InvalidAccountOwner);
-    }
-
+) -> Result<ManagedProofSettings, ProgramError> {
     if info.data_is_empty() {
-        return Err(ProgramError::UninitializedAccount);
-    }
+        if is_writable && !info.is_writable {
+            solana_program::msg!("ManagedProofSettings {}: must be writable", info.key);
+            return Err(OreDelegationError::AccountNotWritable.into());
+        }
 
-    let delegated_boost_data = info.data.borrow();
-    let delegated_boost = DelegatedBoostV2::try_from_bytes(&delegated_boost_data)?;
-
-    let delegated_boost_pda = Pubkey::create_program_address(
-        &[
-            crate::consts::DELEGATED_BOOST_V2,
-            delegate_authority.as_ref(),
-            mint.as_ref(),
-            managed_proof.as_ref(),
-            &[delegated_boost.bump],
-        ],
-        &crate::id(),
-    )?;
+        let seed = [crate::consts::MANAGED_PROOF_SETTINGS, managed_proof_key.as_ref()];
+        let settings_address = if managed_proof.settings_bump != 0 {
+            Pubkey::create_program_address(
+                &[seed[0], seed[1], &[managed_proof.settings_bump]],
+                &crate::id(),
+            )?
+        } else {
+            Pubkey::find_program_address(&seed, &crate::id()).0
+        };
 
-    if *info.key != delegated_boost_pda {
-        return Err(ProgramError::InvalidAccountData);
-    }
+        if *info.key != settings_address {
+            solana_program::msg!("ManagedProofSettings {}: address does not match its seeds", info.key);
+            return Err(OreDelegationError::InvalidAccountAddress.into());
+        }
 
-    if is_writable && !info.is_writable {
-        return Err(ProgramError::InvalidAccountData);
+        return Ok(ManagedProofSettings::zeroed());
     }
 
-    Ok(*delegated_boost)
+    load::<ManagedProofSettings>(info, &[managed_proof_key.as_ref()], is_writable)
 }
 
 pub fn load_program<'a, 'info>(
@@ -154,11 +316,13 @@ pub fn load_program<'a, 'info>(
     program_id: &Pubkey,
 ) -> Result<(), ProgramError> {
     if info.key.ne(&program_id) {
+        solana_program::msg!("program {}: expected {}", info.key, program_id);
         return Err(ProgramError::IncorrectProgramId);
     }
 
     if !info.executable {
-        return Err(ProgramError::InvalidAccountData);
+        solana_program::msg!("program {}: not executable", info.key);
+        return Err(OreDelegationError::ProgramNotExecutable.into());
     }
 
     Ok(())
@@ -0,0 +1,98 @@
+use solana_program::{hash::hashv, pubkey::Pubkey};
+
+/// Domain separator for leaf hashes, so a leaf can never be passed off as an inner node.
+pub const LEAF_PREFIX: &[u8] = &[0];
+
+/// Domain separator for inner node hashes.
+pub const NODE_PREFIX: &[u8] = &[1];
+
+/// Leaf of a managed proof staker allowlist.
+pub fn allowlist_leaf(staker: &Pubkey) -> [u8; 32] {
+    hashv(&[LEAF_PREFIX, staker.as_ref()]).to_bytes()
+}
+
+/// Leaf of a distribution allocation. The index is the allocation's bit in
+/// the distribution's claim bitmap, so each allocation can only be claimed once.
+pub fn distribution_leaf(index: u32, staker: &Pubkey, amount: u64) -> [u8; 32] {
+    hashv(&[
+        LEAF_PREFIX,
+        &index.to_le_bytes(),
+        staker.as_ref(),
+        &amount.to_le_bytes(),
+    ])
+    .to_bytes()
+}
+
+/// Hash two nodes together. Pairs are sorted first so proofs do not need to
+/// carry left/right positions.
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        hashv(&[NODE_PREFIX, a, b]).to_bytes()
+    } else {
+        hashv(&[NODE_PREFIX, b, a]).to_bytes()
+    }
+}
+
+/// Verify that `leaf` is part of the tree with the given `root`.
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(&node, sibling));
+
+    computed == *root
+}
+
+/// Client side merkle tree builder. Nodes without a sibling are carried up to
+/// the next layer unchanged.
+pub struct MerkleTree {
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl MerkleTree {
+    pub fn new(leaves: Vec<[u8; 32]>) -> Self {
+        let mut layers = vec![leaves];
+
+        while layers.last().map(|layer| layer.len() > 1).unwrap_or(false) {
+            let next = layers
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| match pair {
+                    [a, b] => hash_pair(a, b),
+                    [a] => *a,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(next);
+        }
+
+        Self { layers }
+    }
+
+    /// Root of the tree, or all zeroes for an empty tree.
+    pub fn root(&self) -> [u8; 32] {
+        self.layers
+            .last()
+            .and_then(|layer| layer.first())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Proof for the leaf at `index`.
+    pub fn proof(&self, index: usize) -> Option<Vec<[u8; 32]>> {
+        if index >= self.layers[0].len() {
+            return None;
+        }
+
+        let mut proof = Vec::new();
+        let mut index = index;
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+
+        Some(proof)
+    }
+}
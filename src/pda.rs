@@ -1,13 +1,56 @@
 use solana_program::pubkey::Pubkey;
 
-pub fn managed_proof_pda(miner: Pubkey) -> (Pubkey, u8) {
-    Pubkey::find_program_address(
-        &[crate::consts::MANAGED_PROOF, miner.as_ref()],
-        &crate::id(),
-    )
+/// Identifies a managed proof: a miner's original proof, seeded by the miner
+/// alone, or one of its indexed proofs. A bare miner key converts to the
+/// original proof, so every function taking `impl Into<ManagedProofId>` also
+/// accepts a `Pubkey`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ManagedProofId {
+    pub miner: Pubkey,
+    pub index: Option<u16>,
+}
+
+impl ManagedProofId {
+    pub fn indexed(miner: Pubkey, index: u16) -> Self {
+        Self {
+            miner,
+            index: Some(index),
+        }
+    }
+}
+
+impl From<Pubkey> for ManagedProofId {
+    fn from(miner: Pubkey) -> Self {
+        Self { miner, index: None }
+    }
+}
+
+impl From<(Pubkey, u16)> for ManagedProofId {
+    fn from((miner, index): (Pubkey, u16)) -> Self {
+        Self::indexed(miner, index)
+    }
+}
+
+pub fn managed_proof_pda(miner: impl Into<ManagedProofId>) -> (Pubkey, u8) {
+    let managed_proof = miner.into();
+
+    match managed_proof.index {
+        Some(index) => Pubkey::find_program_address(
+            &[
+                crate::consts::MANAGED_PROOF,
+                managed_proof.miner.as_ref(),
+                &index.to_le_bytes(),
+            ],
+            &crate::id(),
+        ),
+        None => Pubkey::find_program_address(
+            &[crate::consts::MANAGED_PROOF, managed_proof.miner.as_ref()],
+            &crate::id(),
+        ),
+    }
 }
 
-pub fn delegated_stake_pda(miner: Pubkey, staker: Pubkey) -> (Pubkey, u8) {
+pub fn delegated_stake_pda(miner: impl Into<ManagedProofId>, staker: Pubkey) -> (Pubkey, u8) {
     let managed_proof_pda = managed_proof_pda(miner);
 
     Pubkey::find_program_address(
@@ -20,7 +63,7 @@ pub fn delegated_stake_pda(miner: Pubkey, staker: Pubkey) -> (Pubkey, u8) {
     )
 }
 
-pub fn delegated_boost_v2_pda(miner: Pubkey, staker: Pubkey, mint: Pubkey) -> (Pubkey, u8) {
+pub fn delegated_boost_v2_pda(miner: impl Into<ManagedProofId>, staker: Pubkey, mint: Pubkey) -> (Pubkey, u8) {
     let managed_proof_pda = managed_proof_pda(miner);
 
     Pubkey::find_program_address(
@@ -34,7 +77,7 @@ pub fn delegated_boost_v2_pda(miner: Pubkey, staker: Pubkey, mint: Pubkey) -> (P
     )
 }
 
-pub fn delegated_boost_pda(miner: Pubkey, staker: Pubkey, mint: Pubkey) -> (Pubkey, u8) {
+pub fn delegated_boost_pda(miner: impl Into<ManagedProofId>, staker: Pubkey, mint: Pubkey) -> (Pubkey, u8) {
     let managed_proof_pda = managed_proof_pda(miner);
 
     Pubkey::find_program_address(
@@ -47,3 +90,47 @@ pub fn delegated_boost_pda(miner: Pubkey, staker: Pubkey, mint: Pubkey) -> (Pubk
         &crate::id(),
     )
 }
+
+pub fn managed_proof_settings_pda(miner: impl Into<ManagedProofId>) -> (Pubkey, u8) {
+    let managed_proof_pda = managed_proof_pda(miner);
+
+    Pubkey::find_program_address(
+        &[
+            crate::consts::MANAGED_PROOF_SETTINGS,
+            managed_proof_pda.0.as_ref(),
+        ],
+        &crate::id(),
+    )
+}
+
+pub fn staker_session_pda(staker: Pubkey, mint: Pubkey, operator: Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            crate::consts::STAKER_SESSION,
+            staker.as_ref(),
+            mint.as_ref(),
+            operator.as_ref(),
+        ],
+        &crate::id(),
+    )
+}
+
+pub fn distribution_pda(miner: impl Into<ManagedProofId>, id: u64) -> (Pubkey, u8) {
+    let managed_proof_pda = managed_proof_pda(miner);
+
+    Pubkey::find_program_address(
+        &[
+            crate::consts::DISTRIBUTION,
+            managed_proof_pda.0.as_ref(),
+            &id.to_le_bytes(),
+        ],
+        &crate::id(),
+    )
+}
+
+pub fn claim_bitmap_pda(distribution: Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[crate::consts::CLAIM_BITMAP, distribution.as_ref()],
+        &crate::id(),
+    )
+}
@@ -0,0 +1,217 @@
+use std::mem::size_of;
+
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_option::COption,
+    program_pack::Pack, pubkey::Pubkey, rent::Rent, system_program, sysvar::Sysvar,
+};
+
+use crate::{
+    error::OreDelegationError,
+    instruction::ApproveStakerSessionArgs,
+    loaders::{check_upgradable, load_mut},
+    processor::upgrade_account::upgrade,
+    state::{ManagedProof, StakerSession},
+    utils::{write_account_header, AccountDeserializeV1},
+};
+
+/// Lets an operator key delegate and undelegate the staker's boosts of one
+/// mint, on one managed proof, until the session expires. Approving again
+/// replaces the managed proof, expiry and allowance; a session with a layout
+/// from before the managed proof is upgraded first, at the payer's expense. The session is made the SPL token delegate of the staker's
+/// token account for the allowance, so it can fund delegations without the
+/// staker signing.
+///
+/// A token account has a single SPL delegate, so only one session per staker
+/// and mint can be live at a time. Approving a session while the account is
+/// delegated to another key fails; revoke that session first. The approval
+/// is not cleared when the session expires, though an expired session can no
+/// longer move tokens; `RevokeStakerSession` clears it and works on expired
+/// sessions too. Rent is paid by the payer, the last account, which can be
+/// the staker itself, and is returned to it on revoke.
+pub fn process_approve_staker_session(
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [staker, operator, token_mint_account_info, managed_proof_account_info, session_account_info, staker_token_account_info, token_program, system_program, payer] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse args
+    let args = ApproveStakerSessionArgs::try_from_bytes(instruction_data)?;
+    let expires_at = i64::from_le_bytes(args.expires_at);
+    let allowance = u64::from_le_bytes(args.allowance);
+
+    if !staker.is_signer {
+        solana_program::msg!("staker: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !payer.is_signer {
+        solana_program::msg!("payer: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *token_program.key != spl_token::id() {
+        solana_program::msg!("token program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // the staker picks the proof, so only check it is a managed proof
+    if *managed_proof_account_info.owner != crate::id() {
+        solana_program::msg!("managed proof: not owned by this program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    ManagedProof::try_from_bytes(&managed_proof_account_info.data.borrow())?;
+
+    if *staker_token_account_info.owner != spl_token::id() {
+        solana_program::msg!("staker token account: not owned by the token program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    let staker_tokens = spl_token::state::Account::unpack(&staker_token_account_info.data.borrow())?;
+    if staker_tokens.mint != *token_mint_account_info.key {
+        solana_program::msg!("staker token account: not for mint {}", token_mint_account_info.key);
+        return Err(OreDelegationError::TokenAccountMintMismatch.into());
+    }
+
+    let session_pda = Pubkey::find_program_address(
+        &[
+            crate::consts::STAKER_SESSION,
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            operator.key.as_ref(),
+        ],
+        &crate::id(),
+    );
+
+    if session_pda.0 != *session_account_info.key {
+        solana_program::msg!("staker session: address does not match its seeds");
+        return Err(OreDelegationError::InvalidAccountAddress.into());
+    }
+
+    // approving would silently replace another key's approval
+    if let COption::Some(delegate) = staker_tokens.delegate {
+        if delegate != *session_account_info.key && staker_tokens.delegated_amount > 0 {
+            solana_program::msg!("staker token account: delegated to {}", delegate);
+            return Err(OreDelegationError::TokenAccountAlreadyDelegated.into());
+        }
+    }
+
+    if session_account_info.data_is_empty() {
+        let rent = Rent::get()?;
+
+        let space = 8 + size_of::<StakerSession>();
+
+        let cost = rent.minimum_balance(space);
+
+        if session_account_info.lamports() > 0 {
+            // cleanup any lamports that may have been sent before our program
+            // created the account
+            solana_program::program::invoke_signed(
+                &solana_program::system_instruction::transfer(
+                    session_account_info.key,
+                    payer.key,
+                    session_account_info.lamports(),
+                ),
+                &[
+                    payer.clone(),
+                    session_account_info.clone(),
+                    system_program.clone(),
+                ],
+                &[&[
+                    crate::consts::STAKER_SESSION,
+                    staker.key.as_ref(),
+                    token_mint_account_info.key.as_ref(),
+                    operator.key.as_ref(),
+                    &[session_pda.1],
+                ]],
+            )?;
+        }
+
+        solana_program::program::invoke_signed(
+            &solana_program::system_instruction::create_account(
+                payer.key,
+                session_account_info.key,
+                cost,
+                space
+                    .try_into()
+                    .expect("failed to convert space usize to u64"),
+                &crate::id(),
+            ),
+            &[
+                payer.clone(),
+                session_account_info.clone(),
+                system_program.clone(),
+            ],
+            &[&[
+                crate::consts::STAKER_SESSION,
+                staker.key.as_ref(),
+                token_mint_account_info.key.as_ref(),
+                operator.key.as_ref(),
+                &[session_pda.1],
+            ]],
+        )?;
+
+        let mut data = session_account_info.data.borrow_mut();
+
+        write_account_header::<StakerSession>(&mut data);
+
+        let session = StakerSession::try_from_bytes_mut(&mut data)?;
+        session.bump = session_pda.1;
+        session.staker = *staker.key;
+        session.operator = *operator.key;
+        session.mint = *token_mint_account_info.key;
+        session.payer = *payer.key;
+    } else if session_account_info.data_len() < 8 + size_of::<StakerSession>() {
+        check_upgradable::<StakerSession>(
+            session_account_info,
+            &[
+                staker.key.as_ref(),
+                token_mint_account_info.key.as_ref(),
+                operator.key.as_ref(),
+            ],
+        )?;
+        upgrade::<StakerSession>(payer, session_account_info, system_program)?;
+    }
+
+    {
+        let mut session = load_mut::<StakerSession>(
+            session_account_info,
+            &[
+                staker.key.as_ref(),
+                token_mint_account_info.key.as_ref(),
+                operator.key.as_ref(),
+            ],
+        )?;
+        session.expires_at = expires_at;
+        session.allowance = allowance;
+        session.managed_proof = *managed_proof_account_info.key;
+    }
+
+    // allow the session to move the allowance out of the staker's account
+    solana_program::program::invoke(
+        &spl_token::instruction::approve(
+            &spl_token::id(),
+            staker_token_account_info.key,
+            session_account_info.key,
+            staker.key,
+            &[],
+            allowance,
+        )?,
+        &[
+            staker_token_account_info.clone(),
+            session_account_info.clone(),
+            staker.clone(),
+            token_program.clone(),
+        ],
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,108 @@
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_pack::Pack,
+};
+
+use crate::{
+    error::OreDelegationError,
+    instruction::ClaimDistributionArgs,
+    loaders::{load_distribution, load_managed_proof, load_mut},
+    merkle,
+    state::ClaimBitmap,
+    utils::{parse_merkle_proof, split_instruction_data},
+};
+
+/// Pays out one allocation of a distribution to the staker's ORE token
+/// account, after checking it against the posted root and marking it claimed.
+/// Anyone can submit the claim, since the ORE can only go to the staker.
+pub fn process_claim_distribution(
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [staker, miner, managed_proof_account_info, distribution_account_info, claim_bitmap_account_info, beneficiary_token_account_info, ore_proof_account_info, treasury, treasury_tokens, ore_program, token_program] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse args
+    let (args, proof_data) = split_instruction_data::<ClaimDistributionArgs>(instruction_data)?;
+    let args = ClaimDistributionArgs::try_from_bytes(args)?;
+    let index = u32::from_le_bytes(args.index);
+    let amount = u64::from_le_bytes(args.amount);
+    let (proof, _) = parse_merkle_proof(proof_data)?;
+
+    if *ore_program.key != ore_api::id() {
+        solana_program::msg!("ore program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if *token_program.key != spl_token::id() {
+        solana_program::msg!("token program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if *beneficiary_token_account_info.owner != spl_token::id() {
+        solana_program::msg!("beneficiary token account: not owned by the token program");
+        return Err(OreDelegationError::InvalidWithdrawalDestination.into());
+    }
+    let beneficiary =
+        spl_token::state::Account::unpack(&beneficiary_token_account_info.data.borrow())?;
+    if beneficiary.owner != *staker.key || beneficiary.mint != ore_api::consts::MINT_ADDRESS {
+        solana_program::msg!("beneficiary token account: not the staker's ORE account");
+        return Err(OreDelegationError::InvalidWithdrawalDestination.into());
+    }
+
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, false)?;
+
+    {
+        let mut distribution =
+            load_distribution(distribution_account_info, managed_proof_account_info.key)?;
+        let mut claim_bitmap = load_mut::<ClaimBitmap>(
+            claim_bitmap_account_info,
+            &[distribution_account_info.key.as_ref()],
+        )?;
+
+        if index >= distribution.leaf_count
+            || !merkle::verify(
+                proof,
+                &distribution.root,
+                merkle::distribution_leaf(index, staker.key, amount),
+            )
+        {
+            return Err(OreDelegationError::InvalidDistributionProof.into());
+        }
+
+        if claim_bitmap.is_claimed(index) {
+            return Err(OreDelegationError::DistributionAlreadyClaimed.into());
+        }
+
+        // a root over more than the posted total can't pay out more than it
+        if amount > distribution.remaining() {
+            solana_program::msg!("distribution: {} remaining, claim is {}", distribution.remaining(), amount);
+            return Err(OreDelegationError::DistributionExhausted.into());
+        }
+
+        claim_bitmap.set_claimed(index);
+        distribution.claimed += amount;
+    }
+
+    // pay out of the managed proof's ORE balance
+    solana_program::program::invoke_signed(
+        &ore_api::prelude::claim(
+            *managed_proof_account_info.key,
+            *beneficiary_token_account_info.key,
+            amount,
+        ),
+        &[
+            managed_proof_account_info.clone(),
+            ore_proof_account_info.clone(),
+            beneficiary_token_account_info.clone(),
+            treasury.clone(),
+            treasury_tokens.clone(),
+            ore_program.clone(),
+        ],
+        &[managed_proof.signer_seeds(miner.key).as_slice()],
+    )?;
+
+    Ok(())
+}
@@ -4,7 +4,9 @@ use solana_program::{
 };
 
 use crate::{
-    error::OreDelegationError, loaders::{load_delegated_boost_v2, load_managed_proof}
+    error::OreDelegationError,
+    loaders::{load, load_managed_proof},
+    state::DelegatedBoostV2,
 };
 
 pub fn process_close_delegate_boost_v2(
@@ -18,7 +20,15 @@ pub fn process_close_delegate_boost_v2(
     };
 
     load_managed_proof(managed_proof_account_info, miner.key, false)?;
-    let delegate_boost_data = load_delegated_boost_v2(delegate_boost_account_info, staker.key, managed_proof_account_info.key, token_mint_account_info.key, true)?;
+    let delegate_boost_data = load::<DelegatedBoostV2>(
+        delegate_boost_account_info,
+        &[
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            managed_proof_account_info.key.as_ref(),
+        ],
+        true,
+    )?;
 
     if delegate_boost_data.amount != 0 {
         return Err(OreDelegationError::CannotCloseAccountWithBalance.into());
@@ -29,6 +39,7 @@ pub fn process_close_delegate_boost_v2(
     }
 
     if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
@@ -0,0 +1,81 @@
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, program_error::ProgramError, system_program,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    error::OreDelegationError,
+    loaders::{load, load_distribution, load_managed_proof, load_mut},
+    state::{ClaimBitmap, DelegatedStake},
+};
+
+/// Closes an expired distribution. What stakers left unclaimed goes back to
+/// the miner's own `DelegatedStake`, where `PostDistribution` took it from,
+/// and the rent of the distribution and its claim bitmap goes back to the
+/// account that paid it.
+pub fn process_close_distribution(
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [miner, payer, managed_proof_account_info, delegated_stake_account_info, distribution_account_info, claim_bitmap_account_info, system_program] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    load_managed_proof(managed_proof_account_info, miner.key, false)?;
+
+    let remaining = {
+        let distribution =
+            load_distribution(distribution_account_info, managed_proof_account_info.key)?;
+        load::<ClaimBitmap>(
+            claim_bitmap_account_info,
+            &[distribution_account_info.key.as_ref()],
+            true,
+        )?;
+
+        if !distribution.is_expired(Clock::get()?.unix_timestamp) {
+            solana_program::msg!("distribution: expires at {}", distribution.expires_at);
+            return Err(OreDelegationError::DistributionNotExpired.into());
+        }
+
+        if distribution.payer != *payer.key {
+            return Err(OreDelegationError::CloseAccountFeePayerMissmatch.into());
+        }
+
+        distribution.remaining()
+    };
+
+    // return the unclaimed ORE to the miner's own stake
+    {
+        let mut delegated_stake = load_mut::<DelegatedStake>(
+            delegated_stake_account_info,
+            &[miner.key.as_ref(), managed_proof_account_info.key.as_ref()],
+        )?;
+
+        if let Some(new_total) = delegated_stake.amount.checked_add(remaining) {
+            delegated_stake.amount = new_total;
+        } else {
+            return Err(ProgramError::ArithmeticOverflow);
+        }
+    }
+
+    for account_info in [distribution_account_info, claim_bitmap_account_info] {
+        account_info.realloc(0, true)?;
+
+        **payer.lamports.borrow_mut() += account_info.lamports();
+        **account_info.lamports.borrow_mut() = 0;
+    }
+
+    Ok(())
+}
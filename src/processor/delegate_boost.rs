@@ -6,9 +6,8 @@ use solana_program::{
 use crate::{
     error::OreDelegationError,
     instruction::DelegateBoostArgs,
-    loaders::{load_delegated_boost, load_managed_proof},
-    state::ManagedProof,
-    utils::AccountDeserializeV1,
+    loaders::{load_managed_proof, load_mut},
+    state::DelegatedBoost,
 };
 
 pub fn process_delegate_boost(
@@ -47,36 +46,35 @@ pub fn process_delegate_boost(
         return Err(ProgramError::ArithmeticOverflow);
     }
 
-    // Parse args
-    let args = DelegateBoostArgs::try_from_bytes(instruction_data)?;
+    // Parse args, the legacy boost charges no fee so its fee limit is ignored
+    let (args, _) = DelegateBoostArgs::parse(instruction_data)?;
     let amount = u64::from_le_bytes(args.amount);
 
     if !staker.is_signer {
+        solana_program::msg!("staker: must sign");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    load_managed_proof(managed_proof_account_info, miner.key, false)?;
-    load_delegated_boost(
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let mut delegated_boost = load_mut::<DelegatedBoost>(
         delegate_boost_account_info,
-        staker.key,
-        &managed_proof_account_info.key,
-        token_mint_account_info.key,
-        true,
+        &[
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            managed_proof_account_info.key.as_ref(),
+        ],
     )?;
 
     if *ore_boost_program.key != ore_boost_api::id() {
+        solana_program::msg!("ore boost program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
     if *token_program.key != spl_token::id() {
+        solana_program::msg!("token program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let managed_proof = {
-        let data = managed_proof_account_info.data.borrow();
-        ManagedProof::try_from_bytes(&data)?.clone()
-    };
-
     // transfer to miners token account
     transfer(
         staker,
@@ -103,24 +101,14 @@ pub fn process_delegate_boost(
             ore_boost_program.clone(),
             token_program.clone(),
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof.bump],
-        ]],
+        &[managed_proof.signer_seeds(miner.key).as_slice()],
     )?;
 
     // increase delegate boost balance
-    if let Ok(mut data) = delegate_boost_account_info.data.try_borrow_mut() {
-        let delegated_boost = crate::state::DelegatedBoost::try_from_bytes_mut(&mut data)?;
-
-        if let Some(new_total) = delegated_boost.amount.checked_add(amount) {
-            delegated_boost.amount = new_total;
-        } else {
-            return Err(ProgramError::ArithmeticOverflow);
-        }
+    if let Some(new_total) = delegated_boost.amount.checked_add(amount) {
+        delegated_boost.amount = new_total;
     } else {
-        return Err(ProgramError::AccountBorrowFailed);
+        return Err(ProgramError::ArithmeticOverflow);
     }
 
     Ok(())
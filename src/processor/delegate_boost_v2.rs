@@ -1,14 +1,16 @@
-use steel::transfer;
+use steel::{transfer, transfer_signed_with_bump};
 use solana_program::{
-    account_info::AccountInfo, clock::Clock, program_error::ProgramError, sysvar::Sysvar,
+    account_info::AccountInfo, clock::Clock, program_error::ProgramError, program_pack::Pack,
+    sysvar::Sysvar,
 };
 
 use crate::{
     error::OreDelegationError,
     instruction::DelegateBoostArgs,
-    loaders::{load_delegated_boost_v2, load_managed_proof},
-    state::ManagedProof,
-    utils::AccountDeserializeV1,
+    loaders::{load_managed_proof, load_managed_proof_settings, load_mut, load_staker_session},
+    merkle,
+    state::DelegatedBoostV2,
+    utils::parse_merkle_proof,
 };
 
 pub fn process_delegate_boost_v2(
@@ -34,7 +36,9 @@ pub fn process_delegate_boost_v2(
         ore_treasury_info,
         ore_treasury_tokens_info,
         ore_program,
-        token_program
+        token_program,
+        settings_account_info,
+        trailing_accounts @ ..
     ] =
         accounts
     else {
@@ -55,42 +59,140 @@ pub fn process_delegate_boost_v2(
     }
 
     // Parse args
-    let args = DelegateBoostArgs::try_from_bytes(instruction_data)?;
+    let (args, proof_data) = DelegateBoostArgs::parse(instruction_data)?;
     let amount = u64::from_le_bytes(args.amount);
 
-    if !staker.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    // The staker session accounts, if any, come before the deposit fee
+    // token account.
+    let (session_accounts, fee_accounts) = match trailing_accounts.len() {
+        1 | 3 => trailing_accounts.split_at(trailing_accounts.len() - 1),
+        _ => (trailing_accounts, &[][..]),
+    };
+
+    // An operator holding a staker session can delegate in place of the
+    // staker, up to the session's allowance.
+    let session = match session_accounts {
+        [] => {
+            if !staker.is_signer {
+                solana_program::msg!("staker: must sign");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            None
+        }
+        [operator, session_account_info] => {
+            let mut session = load_staker_session(
+                session_account_info,
+                staker.key,
+                token_mint_account_info.key,
+                managed_proof_account_info.key,
+                operator,
+                current_timestamp,
+            )?;
+            if amount > session.allowance {
+                return Err(OreDelegationError::SessionAllowanceExceeded.into());
+            }
+            session.allowance -= amount;
+            Some((operator, session_account_info, session.bump))
+        }
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
+    };
 
-    load_managed_proof(managed_proof_account_info, miner.key, false)?;
-    load_delegated_boost_v2(
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let mut delegated_boost = load_mut::<DelegatedBoostV2>(
         delegate_boost_account_info,
-        staker.key,
-        &managed_proof_account_info.key,
-        token_mint_account_info.key,
-        true,
+        &[
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            managed_proof_account_info.key.as_ref(),
+        ],
+    )?;
+    let settings = load_managed_proof_settings(
+        settings_account_info,
+        managed_proof_account_info.key,
+        &managed_proof,
+        false,
     )?;
 
+    // The proof is only read when there is an allowlist to check it against
+    if settings.is_allowlist_enabled() {
+        let (proof, _) = parse_merkle_proof(proof_data)?;
+        if !merkle::verify(proof, &settings.allowlist_root, merkle::allowlist_leaf(staker.key)) {
+            return Err(OreDelegationError::StakerNotAllowlisted.into());
+        }
+    }
+
+    // A fee raised since the staker signed aborts the deposit
+    let deposit_fee_bps = settings.deposit_fee_bps_at(current_timestamp);
+    let max_deposit_fee_bps = u64::from_le_bytes(args.max_deposit_fee_bps);
+    if deposit_fee_bps > max_deposit_fee_bps {
+        solana_program::msg!("deposit fee: {} bps, staker accepted at most {}", deposit_fee_bps, max_deposit_fee_bps);
+        return Err(OreDelegationError::DepositFeeAboveAccepted.into());
+    }
+
+    // The staker never saw this fee when approving the session, so an
+    // operator can only deposit while there is none.
+    if session.is_some() && deposit_fee_bps > 0 {
+        solana_program::msg!("deposit fee: {} bps, sessions only deposit without a fee", deposit_fee_bps);
+        return Err(OreDelegationError::DepositFeeAboveAccepted.into());
+    }
+
+    // Only the amount left after the deposit fee is delegated
+    let fee = settings.deposit_fee(amount, current_timestamp);
+    let net_amount = amount - fee;
+
+    if delegated_boost.amount == 0 && net_amount < settings.min_delegation {
+        return Err(OreDelegationError::DelegationBelowMinimum.into());
+    }
+
     if *ore_boost_program.key != ore_boost_api::id() {
+        solana_program::msg!("ore boost program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
     if *token_program.key != spl_token::id() {
+        solana_program::msg!("token program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let managed_proof = {
-        let data = managed_proof_account_info.data.borrow();
-        ManagedProof::try_from_bytes(&data)?.clone()
-    };
+    // pay the deposit fee to the recipient's token account
+    if fee > 0 {
+        let [fee_token_account_info] = fee_accounts else {
+            solana_program::msg!("deposit fee token account: missing");
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        if *fee_token_account_info.owner != spl_token::id() {
+            solana_program::msg!("deposit fee token account: not owned by the token program");
+            return Err(OreDelegationError::InvalidDepositFeeAccount.into());
+        }
+        let fee_token_account =
+            spl_token::state::Account::unpack(&fee_token_account_info.data.borrow())?;
+        if fee_token_account.owner != settings.deposit_fee_recipient
+            || fee_token_account.mint != *token_mint_account_info.key
+        {
+            solana_program::msg!("deposit fee token account: not the fee recipient's account for this mint");
+            return Err(OreDelegationError::InvalidDepositFeeAccount.into());
+        }
+
+        transfer_from_staker(
+            staker,
+            session,
+            staker_token_account_info,
+            fee_token_account_info,
+            token_mint_account_info,
+            token_program,
+            fee,
+        )?;
+    }
 
     // transfer to miners token account
-    transfer(
+    transfer_from_staker(
         staker,
+        session,
         staker_token_account_info,
         managed_proof_account_token_account_info,
+        token_mint_account_info,
         token_program,
-        amount,
+        net_amount,
     )?;
 
     // deposit into ore boost program
@@ -98,14 +200,8 @@ pub fn process_delegate_boost_v2(
         &ore_boost_api::sdk::deposit(
             *managed_proof_account_info.key,
             *token_mint_account_info.key,
-            amount,
+            net_amount,
         ),
-            // let [
-        // signer_info, boost_info, config_info, deposits_info, mint_info, proof_info,
-        // rewards_info, sender_info, stake_info, treasury_info, treasury_tokens_info,
-        // ore_program, token_program
-    // ] =
-
         &[
             managed_proof_account_info.clone(),
             boost_account_info.clone(),
@@ -121,25 +217,52 @@ pub fn process_delegate_boost_v2(
             ore_program.clone(),
             token_program.clone(),
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof.bump],
-        ]],
+        &[managed_proof.signer_seeds(miner.key).as_slice()],
     )?;
 
     // increase delegate boost balance
-    if let Ok(mut data) = delegate_boost_account_info.data.try_borrow_mut() {
-        let delegated_boost = crate::state::DelegatedBoostV2::try_from_bytes_mut(&mut data)?;
-
-        if let Some(new_total) = delegated_boost.amount.checked_add(amount) {
-            delegated_boost.amount = new_total;
-        } else {
-            return Err(ProgramError::ArithmeticOverflow);
-        }
+    if let Some(new_total) = delegated_boost.amount.checked_add(net_amount) {
+        delegated_boost.amount = new_total;
     } else {
-        return Err(ProgramError::AccountBorrowFailed);
+        return Err(ProgramError::ArithmeticOverflow);
     }
 
     Ok(())
 }
+
+/// Moves `amount` out of the staker's token account, signed by the staker or
+/// by the operator's staker session.
+fn transfer_from_staker<'info>(
+    staker: &AccountInfo<'info>,
+    session: Option<(&AccountInfo<'info>, &AccountInfo<'info>, u8)>,
+    staker_token_account_info: &AccountInfo<'info>,
+    destination_info: &AccountInfo<'info>,
+    token_mint_account_info: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+) -> Result<(), ProgramError> {
+    if let Some((operator, session_account_info, bump)) = session {
+        transfer_signed_with_bump(
+            session_account_info,
+            staker_token_account_info,
+            destination_info,
+            token_program,
+            amount,
+            &[
+                crate::consts::STAKER_SESSION,
+                staker.key.as_ref(),
+                token_mint_account_info.key.as_ref(),
+                operator.key.as_ref(),
+            ],
+            bump,
+        )
+    } else {
+        transfer(
+            staker,
+            staker_token_account_info,
+            destination_info,
+            token_program,
+            amount,
+        )
+    }
+}
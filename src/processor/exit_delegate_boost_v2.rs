@@ -0,0 +1,135 @@
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, program_error::ProgramError, sysvar::Sysvar,
+};
+
+use crate::{
+    error::OreDelegationError,
+    loaders::{load, load_managed_proof},
+    state::DelegatedBoostV2,
+};
+
+/// Withdraws the staker's full boost delegation back to the staker, then
+/// closes the `DelegatedBoostV2` and refunds its rent to the fee payer that
+/// opened it.
+pub fn process_exit_delegate_boost_v2(
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [
+        staker,
+        miner,
+        payer,
+        managed_proof_account_info,
+        managed_proof_account_token_account_info,
+        delegate_boost_account_info,
+        boost_account_info,
+        boost_config_info,
+        deposits_info,
+        token_mint_account_info,
+        config_proof_info,
+        rewards_info,
+        sender_info,
+        staker_token_account_info,
+        stake_account_info,
+        ore_boost_program,
+        ore_treasury_info,
+        ore_treasury_tokens_info,
+        ore_program,
+        token_program
+    ] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !staker.is_signer {
+        solana_program::msg!("staker: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let delegated_boost = load::<DelegatedBoostV2>(
+        delegate_boost_account_info,
+        &[
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            managed_proof_account_info.key.as_ref(),
+        ],
+        true,
+    )?;
+
+    let clock = Clock::get()?;
+    if delegated_boost.is_locked(clock.unix_timestamp) {
+        return Err(OreDelegationError::DelegationLocked.into());
+    }
+
+    if delegated_boost.fee_payer != *payer.key {
+        return Err(OreDelegationError::CloseAccountFeePayerMissmatch.into());
+    }
+
+    if *ore_boost_program.key != ore_boost_api::id() {
+        solana_program::msg!("ore boost program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if *token_program.key != spl_token::id() {
+        solana_program::msg!("token program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let amount = delegated_boost.amount;
+
+    if amount > 0 {
+        // withdraw everything from boost program
+        let signer_seeds = managed_proof.signer_seeds(miner.key);
+        solana_program::program::invoke_signed(
+            &ore_boost_api::sdk::withdraw(
+                *managed_proof_account_info.key,
+                *token_mint_account_info.key,
+                amount,
+            ),
+            &[
+                managed_proof_account_info.clone(),
+                boost_account_info.clone(),
+                boost_config_info.clone(),
+                deposits_info.clone(),
+                token_mint_account_info.clone(),
+                config_proof_info.clone(),
+                rewards_info.clone(),
+                sender_info.clone(),
+                stake_account_info.clone(),
+                ore_treasury_info.clone(),
+                ore_treasury_tokens_info.clone(),
+                ore_program.clone(),
+                token_program.clone(),
+            ],
+            &[signer_seeds.as_slice()],
+        )?;
+
+        // transfer to stakers token account
+        solana_program::program::invoke_signed(
+            &spl_token::instruction::transfer(
+                &spl_token::id(),
+                managed_proof_account_token_account_info.key,
+                staker_token_account_info.key,
+                managed_proof_account_info.key,
+                &[],
+                amount,
+            )?,
+            &[
+                managed_proof_account_token_account_info.clone(),
+                staker_token_account_info.clone(),
+                managed_proof_account_info.clone(),
+                token_program.clone(),
+            ],
+            &[signer_seeds.as_slice()],
+        )?;
+    }
+
+    delegate_boost_account_info.realloc(0, true)?;
+
+    **payer.lamports.borrow_mut() += delegate_boost_account_info.lamports();
+    **delegate_boost_account_info.lamports.borrow_mut() = 0;
+
+    Ok(())
+}
@@ -8,7 +8,7 @@ use solana_program::{
 use crate::{
     loaders::load_managed_proof,
     state::DelegatedBoost,
-    utils::{AccountDeserializeV1, Discriminator},
+    utils::{write_account_header, AccountDeserializeV1},
 };
 
 pub fn process_init_delegate_boost(
@@ -21,17 +21,25 @@ pub fn process_init_delegate_boost(
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
+    if !payer.is_signer {
+        solana_program::msg!("payer: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
     load_managed_proof(managed_proof_account_info, miner.key, false)?;
 
     if !delegate_boost_account_info.data_is_empty() {
+        solana_program::msg!("delegate boost: already initialized");
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
     if *rent_sysvar.key != solana_program::sysvar::rent::id() {
+        solana_program::msg!("rent sysvar: incorrect sysvar");
         return Err(ProgramError::UnsupportedSysvar);
     }
 
     if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
@@ -101,7 +109,7 @@ pub fn process_init_delegate_boost(
 
     // Set the DelegatedBoost initial data
     if let Ok(mut data) = delegate_boost_account_info.data.try_borrow_mut() {
-        data[0] = DelegatedBoost::discriminator() as u8;
+        write_account_header::<DelegatedBoost>(&mut data);
 
         let delegated_boost = crate::state::DelegatedBoost::try_from_bytes_mut(&mut data)?;
         delegated_boost.bump = delegated_boost_pda.1;
@@ -6,32 +6,55 @@ use solana_program::{
 };
 
 use crate::{
-    loaders::load_managed_proof,
+    error::OreDelegationError,
+    loaders::{load_managed_proof, load_managed_proof_settings},
+    merkle,
     state::DelegatedBoostV2,
-    utils::{AccountDeserializeV1, Discriminator},
+    utils::{parse_merkle_proof, write_account_header, AccountDeserializeV1},
 };
 
 pub fn process_init_delegate_boost_v2(
     accounts: &[AccountInfo],
-    _instruction_data: &[u8],
+    instruction_data: &[u8],
 ) -> Result<(), ProgramError> {
-    let [staker, miner, payer, managed_proof_account_info, delegate_boost_account_info, token_mint_account_info, rent_sysvar, system_program] =
+    let [staker, miner, payer, managed_proof_account_info, delegate_boost_account_info, token_mint_account_info, rent_sysvar, system_program, settings_account_info] =
         accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    if !payer.is_signer {
+        solana_program::msg!("payer: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let settings = load_managed_proof_settings(
+        settings_account_info,
+        managed_proof_account_info.key,
+        &managed_proof,
+        false,
+    )?;
+
+    if settings.is_allowlist_enabled() {
+        let (proof, _) = parse_merkle_proof(instruction_data)?;
+        if !merkle::verify(proof, &settings.allowlist_root, merkle::allowlist_leaf(staker.key)) {
+            return Err(OreDelegationError::StakerNotAllowlisted.into());
+        }
+    }
 
     if !delegate_boost_account_info.data_is_empty() {
+        solana_program::msg!("delegate boost: already initialized");
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
     if *rent_sysvar.key != solana_program::sysvar::rent::id() {
+        solana_program::msg!("rent sysvar: incorrect sysvar");
         return Err(ProgramError::UnsupportedSysvar);
     }
 
     if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
@@ -101,7 +124,7 @@ pub fn process_init_delegate_boost_v2(
 
     // Set the DelegatedBoost initial data
     if let Ok(mut data) = delegate_boost_account_info.data.try_borrow_mut() {
-        data[0] = DelegatedBoostV2::discriminator() as u8;
+        write_account_header::<DelegatedBoostV2>(&mut data);
 
         let delegated_boost = crate::state::DelegatedBoostV2::try_from_bytes_mut(&mut data)?;
         delegated_boost.bump = delegated_boost_pda.1;
@@ -110,6 +133,8 @@ pub fn process_init_delegate_boost_v2(
         delegated_boost.authority = *staker.key;
         delegated_boost.amount = 0;
         delegated_boost.fee_payer = *payer.key;
+        delegated_boost.locked_until = 0;
+        delegated_boost.lock_bonus_bps = 0;
     } else {
         return Err(ProgramError::AccountBorrowFailed);
     }
@@ -8,7 +8,7 @@ use solana_program::{
 use crate::{
     loaders::load_managed_proof,
     state::DelegatedStake,
-    utils::{AccountDeserializeV1, Discriminator},
+    utils::{write_account_header, AccountDeserializeV1},
 };
 
 pub fn process_init_delegate_stake(
@@ -21,17 +21,25 @@ pub fn process_init_delegate_stake(
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
+    if !payer.is_signer {
+        solana_program::msg!("payer: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
     load_managed_proof(managed_proof_account_info, miner.key, false)?;
 
     if !delegate_stake_account_info.data_is_empty() {
+        solana_program::msg!("delegate stake: already initialized");
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
     if *rent_sysvar.key != solana_program::sysvar::rent::id() {
+        solana_program::msg!("rent sysvar: incorrect sysvar");
         return Err(ProgramError::UnsupportedSysvar);
     }
 
     if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
@@ -98,11 +106,12 @@ pub fn process_init_delegate_stake(
 
     // Set the DelegatedStake initial data
     if let Ok(mut data) = delegate_stake_account_info.data.try_borrow_mut() {
-        data[0] = DelegatedStake::discriminator() as u8;
+        write_account_header::<DelegatedStake>(&mut data);
 
         let delegated_stake = crate::state::DelegatedStake::try_from_bytes_mut(&mut data)?;
         delegated_stake.bump = delegated_stake_pda.1;
         delegated_stake.amount = 0;
+        delegated_stake.payer = *payer.key;
     } else {
         return Err(ProgramError::AccountBorrowFailed);
     }
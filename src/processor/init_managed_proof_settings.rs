@@ -0,0 +1,137 @@
+use std::mem::size_of;
+
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, rent::Rent,
+    system_program, sysvar::Sysvar,
+};
+
+use crate::{
+    consts::MIN_FEE_CHANGE_DELAY,
+    error::OreDelegationError,
+    loaders::load_managed_proof,
+    state::ManagedProofSettings,
+    utils::{write_account_header, AccountDeserializeV1},
+};
+
+/// Creates the settings account of a managed proof. Rent is paid by the
+/// payer, the last account, which can be the miner itself.
+pub fn process_init_managed_proof_settings(
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [miner, managed_proof_account_info, settings_account_info, rent_sysvar, system_program, payer] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !payer.is_signer {
+        solana_program::msg!("payer: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_managed_proof(managed_proof_account_info, miner.key, false)?;
+
+    if !settings_account_info.data_is_empty() {
+        solana_program::msg!("settings: already initialized");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    if *rent_sysvar.key != solana_program::sysvar::rent::id() {
+        solana_program::msg!("rent sysvar: incorrect sysvar");
+        return Err(ProgramError::UnsupportedSysvar);
+    }
+
+    if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let settings_pda = Pubkey::find_program_address(
+        &[
+            crate::consts::MANAGED_PROOF_SETTINGS,
+            managed_proof_account_info.key.as_ref(),
+        ],
+        &crate::id(),
+    );
+
+    if settings_pda.0 != *settings_account_info.key {
+        solana_program::msg!("settings: address does not match its seeds");
+        return Err(OreDelegationError::InvalidAccountAddress.into());
+    }
+
+    let rent = Rent::get()?;
+
+    let space = 8 + size_of::<ManagedProofSettings>();
+
+    let cost = rent.minimum_balance(space);
+
+    if settings_account_info.lamports() > 0 {
+        // cleanup any lamports that may have been sent before our program
+        // created the account
+        solana_program::program::invoke_signed(
+            &solana_program::system_instruction::transfer(
+                settings_account_info.key,
+                payer.key,
+                settings_account_info.lamports(),
+            ),
+            &[
+                payer.clone(),
+                settings_account_info.clone(),
+                system_program.clone(),
+            ],
+            &[&[
+                crate::consts::MANAGED_PROOF_SETTINGS,
+                managed_proof_account_info.key.as_ref(),
+                &[settings_pda.1],
+            ]],
+        )?;
+    }
+
+    solana_program::program::invoke_signed(
+        &solana_program::system_instruction::create_account(
+            payer.key,
+            settings_account_info.key,
+            cost,
+            space
+                .try_into()
+                .expect("failed to convert space usize to u64"),
+            &crate::id(),
+        ),
+        &[
+            payer.clone(),
+            settings_account_info.clone(),
+            system_program.clone(),
+        ],
+        &[&[
+            crate::consts::MANAGED_PROOF_SETTINGS,
+            managed_proof_account_info.key.as_ref(),
+            &[settings_pda.1],
+        ]],
+    )?;
+
+    // Set the ManagedProofSettings initial data
+    if let Ok(mut data) = settings_account_info.data.try_borrow_mut() {
+        write_account_header::<ManagedProofSettings>(&mut data);
+
+        let settings = ManagedProofSettings::try_from_bytes_mut(&mut data)?;
+        settings.bump = settings_pda.1;
+        settings.managed_proof = *managed_proof_account_info.key;
+        settings.allowlist_enabled = 0;
+        settings.allowlist_root = [0; 32];
+        settings.min_delegation = 0;
+        settings.min_remaining_balance = 0;
+        settings.lock_bonus_bps = 0;
+        settings.min_lock_duration = 0;
+        settings.fee_change_delay = MIN_FEE_CHANGE_DELAY;
+    } else {
+        return Err(ProgramError::AccountBorrowFailed);
+    }
+
+    Ok(())
+}
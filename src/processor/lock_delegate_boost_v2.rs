@@ -0,0 +1,68 @@
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, program_error::ProgramError, sysvar::Sysvar,
+};
+
+use crate::{
+    error::OreDelegationError,
+    instruction::LockDelegateBoostArgs,
+    loaders::{load_managed_proof, load_managed_proof_settings, load_mut},
+    state::DelegatedBoostV2,
+};
+
+pub fn process_lock_delegate_boost_v2(
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [staker, miner, managed_proof_account_info, delegate_boost_account_info, token_mint_account_info, settings_account_info] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse args
+    let args = LockDelegateBoostArgs::try_from_bytes(instruction_data)?;
+    let locked_until = i64::from_le_bytes(args.locked_until);
+
+    if !staker.is_signer {
+        solana_program::msg!("staker: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let mut delegated_boost = load_mut::<DelegatedBoostV2>(
+        delegate_boost_account_info,
+        &[
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            managed_proof_account_info.key.as_ref(),
+        ],
+    )?;
+    let settings = load_managed_proof_settings(
+        settings_account_info,
+        managed_proof_account_info.key,
+        &managed_proof,
+        false,
+    )?;
+
+    let clock = Clock::get()?;
+
+    // locks can only be extended
+    if locked_until < delegated_boost.locked_until {
+        return Err(OreDelegationError::InvalidLockDuration.into());
+    }
+
+    let duration = if let Some(duration) = locked_until.checked_sub(clock.unix_timestamp) {
+        duration
+    } else {
+        return Err(ProgramError::ArithmeticOverflow);
+    };
+
+    if duration <= 0 || duration < settings.min_lock_duration {
+        return Err(OreDelegationError::InvalidLockDuration.into());
+    }
+
+    delegated_boost.locked_until = locked_until;
+    delegated_boost.lock_bonus_bps = settings.lock_bonus_bps;
+
+    Ok(())
+}
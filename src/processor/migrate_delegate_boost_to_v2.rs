@@ -3,7 +3,8 @@ use solana_program::{
 };
 
 use crate::{
-    loaders::{load_delegated_boost, load_delegated_boost_v2, load_managed_proof}, utils::AccountDeserializeV1
+    loaders::{load_managed_proof, load_mut},
+    state::{DelegatedBoost, DelegatedBoostV2},
 };
 
 pub fn process_migrate_delegate_boost_v2(
@@ -17,52 +18,41 @@ pub fn process_migrate_delegate_boost_v2(
     };
 
     load_managed_proof(managed_proof_account_info, miner.key, false)?;
-    load_delegated_boost(
+    let mut delegated_boost = load_mut::<DelegatedBoost>(
         delegate_boost_account_info,
-        staker.key,
-        managed_proof_account_info.key,
-        token_mint_account_info.key,
-        true,
+        &[
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            managed_proof_account_info.key.as_ref(),
+        ],
     )?;
-    load_delegated_boost_v2(
+    let mut delegated_boost_v2 = load_mut::<DelegatedBoostV2>(
         delegate_boost_v2_account_info,
-        staker.key,
-        managed_proof_account_info.key,
-        token_mint_account_info.key,
-        true,
+        &[
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            managed_proof_account_info.key.as_ref(),
+        ],
     )?;
 
     // decrease from delegate boost v1
-    let transfer_amount;
-    if let Ok(mut data) = delegate_boost_account_info.data.try_borrow_mut() {
-        let delegated_boost = crate::state::DelegatedBoost::try_from_bytes_mut(&mut data)?;
-
-        if delegated_boost.amount == 0 {
-            return Err(ProgramError::InsufficientFunds);
-        }
+    if delegated_boost.amount == 0 {
+        return Err(ProgramError::InsufficientFunds);
+    }
 
-        transfer_amount = delegated_boost.amount;
+    let transfer_amount = delegated_boost.amount;
 
-        if let Some(new_total) = delegated_boost.amount.checked_sub(transfer_amount) {
-            delegated_boost.amount = new_total;
-        } else {
-            return Err(ProgramError::ArithmeticOverflow);
-        }
+    if let Some(new_total) = delegated_boost.amount.checked_sub(transfer_amount) {
+        delegated_boost.amount = new_total;
     } else {
-        return Err(ProgramError::AccountBorrowFailed);
+        return Err(ProgramError::ArithmeticOverflow);
     }
 
     // increase delegate v2 boost balance
-    if let Ok(mut data) = delegate_boost_v2_account_info.data.try_borrow_mut() {
-        let delegated_boost = crate::state::DelegatedBoostV2::try_from_bytes_mut(&mut data)?;
-
-        if let Some(new_total) = delegated_boost.amount.checked_add(transfer_amount) {
-            delegated_boost.amount = new_total;
-        } else {
-            return Err(ProgramError::ArithmeticOverflow);
-        }
+    if let Some(new_total) = delegated_boost_v2.amount.checked_add(transfer_amount) {
+        delegated_boost_v2.amount = new_total;
     } else {
-        return Err(ProgramError::AccountBorrowFailed);
+        return Err(ProgramError::ArithmeticOverflow);
     }
 
     Ok(())
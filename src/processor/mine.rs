@@ -1,17 +1,28 @@
+use std::mem::size_of;
+
 use steel::AccountDeserialize;
-use solana_program::{account_info::AccountInfo, program_error::ProgramError, system_program};
+use solana_program::{
+    account_info::AccountInfo,
+    clock::Clock,
+    instruction::{AccountMeta, Instruction},
+    program_error::ProgramError,
+    system_program,
+    sysvar::Sysvar,
+};
 
 use crate::{
+    error::OreDelegationError,
+    global_boost::{rotate, Reservation, ReservationStatus, GLOBAL_BOOST_ID},
     instruction::MineArgs,
-    loaders::{load_delegated_stake, load_managed_proof},
-    state::ManagedProof,
+    loaders::{check_upgradable, check_upgradable_managed_proof, load_managed_proof, load_mut},
+    processor::upgrade_account::upgrade,
+    state::{DelegatedStake, ManagedProof},
     utils::AccountDeserializeV1,
 };
 
 pub fn process_mine(accounts: &[AccountInfo], instruction_data: &[u8]) -> Result<(), ProgramError> {
-    let (required_accounts, boost_accounts) = accounts.split_at(10);
-    let [miner, managed_proof_account_info, bus_info, config_info, ore_proof_account_info, delegated_stake_account_info, slot_hashes_sysvar, instructions_sysvar, ore_program, system_program] =
-        required_accounts
+    let [miner, managed_proof_account_info, bus_info, config_info, ore_proof_account_info, delegated_stake_account_info, slot_hashes_sysvar, instructions_sysvar, ore_program, system_program, boost_accounts @ ..] =
+        accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -19,80 +30,122 @@ pub fn process_mine(accounts: &[AccountInfo], instruction_data: &[u8]) -> Result
     // Parse args
     let args = MineArgs::try_from_bytes(instruction_data)?;
 
-    if !miner.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
-    load_managed_proof(managed_proof_account_info, miner.key, true)?;
-    load_delegated_stake(
-        delegated_stake_account_info,
-        miner.key,
-        managed_proof_account_info.key,
-        true,
-    )?;
-
     if *ore_program.key != ore_api::id() {
+        solana_program::msg!("ore program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
     if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let balance_before = if let Ok(data) = ore_proof_account_info.data.try_borrow() {
-        let ore_proof = ore_api::state::Proof::try_from_bytes(&data)?;
-        ore_proof.balance
-    } else {
-        return Err(ProgramError::AccountBorrowFailed);
-    };
+    check_boost_accounts(boost_accounts)?;
 
-    let managed_proof = {
-        let data = managed_proof_account_info.data.borrow();
-        ManagedProof::try_from_bytes(&data)?.clone()
-    };
+    mine_managed_proof(
+        miner,
+        managed_proof_account_info,
+        bus_info,
+        config_info,
+        ore_proof_account_info,
+        delegated_stake_account_info,
+        slot_hashes_sysvar,
+        instructions_sysvar,
+        system_program,
+        boost_accounts,
+        accounts,
+        args,
+    )
+}
+
+/// Submits a solution for the miner's managed proof, credits the reward to the
+/// miner's `DelegatedStake` and records it in the managed proof's statistics.
+/// A managed proof or delegated stake with an older layout is checked and
+/// upgraded first, at the miner's expense. `accounts` must hold every account the ORE mine
+/// instruction references, including the ORE program.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn mine_managed_proof<'info>(
+    miner: &AccountInfo<'info>,
+    managed_proof_account_info: &AccountInfo<'info>,
+    bus_info: &AccountInfo<'info>,
+    config_info: &AccountInfo<'info>,
+    ore_proof_account_info: &AccountInfo<'info>,
+    delegated_stake_account_info: &AccountInfo<'info>,
+    slot_hashes_sysvar: &AccountInfo<'info>,
+    instructions_sysvar: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    boost_accounts: &[AccountInfo<'info>],
+    accounts: &[AccountInfo<'info>],
+    args: &MineArgs,
+) -> Result<(), ProgramError> {
+    if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
 
-    // CPI to submit the solution
-    let solution = drillx::Solution::new(args.digest, args.nonce);
+    if managed_proof_account_info.data_len() < 8 + size_of::<ManagedProof>() {
+        check_upgradable_managed_proof(managed_proof_account_info, miner.key)?;
+        upgrade::<ManagedProof>(miner, managed_proof_account_info, system_program)?;
+    }
 
-    let [boost_config_info, boost_proof_info] = boost_accounts else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
+    if delegated_stake_account_info.data_len() < 8 + size_of::<DelegatedStake>() {
+        check_upgradable::<DelegatedStake>(
+            delegated_stake_account_info,
+            &[miner.key.as_ref(), managed_proof_account_info.key.as_ref()],
+        )?;
+        upgrade::<DelegatedStake>(miner, delegated_stake_account_info, system_program)?;
+    }
 
-    let mine_accounts = 
-        vec![
-            managed_proof_account_info.clone(),
-            bus_info.clone(),
-            config_info.clone(),
-            ore_proof_account_info.clone(),
-            instructions_sysvar.clone(),
-            slot_hashes_sysvar.clone(),
-            boost_config_info.clone(),
-            boost_proof_info.clone(),
-            ore_program.clone(),
-        ];
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, true)?;
+    let mut delegated_stake = load_mut::<DelegatedStake>(
+        delegated_stake_account_info,
+        &[miner.key.as_ref(), managed_proof_account_info.key.as_ref()],
+    )?;
+
+    // Validate the ORE proof once; after the CPI only its balance is reread.
+    if *ore_proof_account_info.owner != ore_api::id() {
+        solana_program::msg!("ore proof: not owned by the ore program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+    let balance_before = ore_api::state::Proof::try_from_bytes(
+        &ore_proof_account_info
+            .try_borrow_data()
+            .or(Err(ProgramError::AccountBorrowFailed))?,
+    )?
+    .balance;
 
+    let signer_seeds = managed_proof.signer_seeds(miner.key);
+    let boost_accounts = rotate_global_boost_if_needed(
+        managed_proof_account_info,
+        ore_proof_account_info,
+        boost_accounts,
+        accounts,
+        signer_seeds.as_slice(),
+    )?;
+
+    // CPI to submit the solution. Every account the ORE instruction needs is
+    // already in `accounts`, so they are passed through as is.
     solana_program::program::invoke_signed(
-        &ore_api::sdk::mine(
-            *managed_proof_account_info.key,
-            *managed_proof_account_info.key,
-            *bus_info.key,
-            solution,
-            *boost_config_info.key
+        &mine_instruction(
+            managed_proof_account_info,
+            bus_info,
+            config_info,
+            ore_proof_account_info,
+            instructions_sysvar,
+            slot_hashes_sysvar,
+            boost_accounts,
+            args,
         ),
-        &mine_accounts,
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof.bump],
-        ]],
+        accounts,
+        &[signer_seeds.as_slice()],
     )?;
 
-    let balance_after = if let Ok(data) = ore_proof_account_info.data.try_borrow() {
-        let ore_proof = ore_api::state::Proof::try_from_bytes(&data)?;
-        ore_proof.balance
-    } else {
-        return Err(ProgramError::AccountBorrowFailed);
-    };
+    let balance_after = ore_api::state::Proof::try_from_bytes(
+        &ore_proof_account_info
+            .try_borrow_data()
+            .or(Err(ProgramError::AccountBorrowFailed))?,
+    )?
+    .balance;
 
     let miner_rewards_earned = if let Some(difference) = balance_after.checked_sub(balance_before) {
         difference
@@ -101,17 +154,155 @@ pub fn process_mine(accounts: &[AccountInfo], instruction_data: &[u8]) -> Result
     };
 
     // Update the Miners DelegatedStake amount
-    if let Ok(mut data) = delegated_stake_account_info.data.try_borrow_mut() {
-        let delegated_stake = crate::state::DelegatedStake::try_from_bytes_mut(&mut data)?;
-
-        if let Some(new_total) = delegated_stake.amount.checked_add(miner_rewards_earned) {
-            delegated_stake.amount = new_total;
-        } else {
-            return Err(ProgramError::ArithmeticOverflow);
-        }
+    if let Some(new_total) = delegated_stake.amount.checked_add(miner_rewards_earned) {
+        delegated_stake.amount = new_total;
     } else {
-        return Err(ProgramError::AccountBorrowFailed);
+        return Err(ProgramError::ArithmeticOverflow);
     }
 
+    // Record the submission, the account was validated by the load above
+    let difficulty = drillx::Solution::new(args.digest, args.nonce)
+        .to_hash()
+        .difficulty();
+    let now = Clock::get()?.unix_timestamp;
+    ManagedProof::try_from_bytes_mut(&mut managed_proof_account_info.try_borrow_mut_data()?)?
+        .record_mine(miner_rewards_earned, difficulty, now);
+
     Ok(())
 }
+
+/// Rotates the managed proof's global boost reservation first when the boost
+/// accounts include the directory, treasury tokens and global boost program
+/// and the reservation can rotate, even if it already holds the supplied
+/// boost. Fails with `RotatedToAnotherBoost` if the rotation moved it to
+/// another boost, and with `ReservedForAnotherBoost` if it holds another boost
+/// and can't rotate yet, rather than mining without one. Returns the boost
+/// accounts to forward to ORE.
+fn rotate_global_boost_if_needed<'a, 'info>(
+    managed_proof_account_info: &AccountInfo<'info>,
+    ore_proof_account_info: &AccountInfo<'info>,
+    boost_accounts: &'a [AccountInfo<'info>],
+    accounts: &[AccountInfo<'info>],
+    signer_seeds: &[&[u8]],
+) -> Result<&'a [AccountInfo<'info>], ProgramError> {
+    let [boost_info, _boost_proof_info, reservation_info, _directory_info, _treasury_tokens_info, ore_global_boost_program] =
+        boost_accounts
+    else {
+        return Ok(boost_accounts);
+    };
+
+    if *ore_global_boost_program.key != GLOBAL_BOOST_ID {
+        solana_program::msg!("ore global boost program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let now = Clock::get()?.unix_timestamp;
+    let status = reservation_status(reservation_info, ore_proof_account_info)?;
+    if status.can_rotate(now) {
+        solana_program::program::invoke_signed(
+            &rotate(*managed_proof_account_info.key, *ore_proof_account_info.key),
+            accounts,
+            &[signer_seeds],
+        )?;
+        let status = reservation_status(reservation_info, ore_proof_account_info)?;
+        if status.boost != Some(*boost_info.key) {
+            solana_program::msg!("reservation: rotated to another boost");
+            return Err(OreDelegationError::RotatedToAnotherBoost.into());
+        }
+    } else if status.boost != Some(*boost_info.key) {
+        solana_program::msg!("reservation: reserved for another boost");
+        return Err(OreDelegationError::ReservedForAnotherBoost.into());
+    }
+
+    Ok(&boost_accounts[..3])
+}
+
+/// The status of the ORE proof's reservation.
+fn reservation_status(
+    reservation_info: &AccountInfo,
+    ore_proof_account_info: &AccountInfo,
+) -> Result<ReservationStatus, ProgramError> {
+    let reservation_data = reservation_info.try_borrow_data()?;
+    let reservation = Reservation::try_from_bytes(&reservation_data)?;
+    if reservation.authority != *ore_proof_account_info.key {
+        solana_program::msg!("reservation: not held by the ore proof");
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(reservation.status())
+}
+
+/// Checks the optional boost accounts that follow the `Mine` accounts. They
+/// can be absent, the ORE boost config and its proof, or a global boost
+/// reservation set of boost, boost proof and reservation. The reservation set
+/// can be followed by the directory, treasury tokens and global boost program
+/// to rotate a due reservation before mining.
+pub(crate) fn check_boost_accounts(boost_accounts: &[AccountInfo]) -> Result<(), ProgramError> {
+    match boost_accounts {
+        [] => Ok(()),
+        [boost_config_info, boost_proof_info] => {
+            if *boost_config_info.owner != ore_boost_api::id()
+                || *boost_proof_info.owner != ore_api::id()
+            {
+                solana_program::msg!("boost config or boost proof: wrong owner");
+                return Err(ProgramError::InvalidAccountOwner);
+            }
+            Ok(())
+        }
+        [boost_info, boost_proof_info, reservation_info] => {
+            if *boost_info.owner != GLOBAL_BOOST_ID
+                || *boost_proof_info.owner != ore_api::id()
+                || *reservation_info.owner != GLOBAL_BOOST_ID
+            {
+                solana_program::msg!("global boost, boost proof or reservation: wrong owner");
+                return Err(ProgramError::InvalidAccountOwner);
+            }
+            Ok(())
+        }
+        [global_boost_accounts @ .., _directory_info, _treasury_tokens_info, _ore_global_boost_program]
+            if global_boost_accounts.len() == 3 =>
+        {
+            check_boost_accounts(global_boost_accounts)
+        }
+        _ => Err(ProgramError::NotEnoughAccountKeys),
+    }
+}
+
+/// Builds the ORE mine instruction for a managed proof, forwarding whichever
+/// boost accounts were supplied. With no boost or the ORE boost config pair it
+/// matches `ore_api::sdk::mine`; the mock ORE program in the tests checks it.
+#[allow(clippy::too_many_arguments)]
+fn mine_instruction(
+    managed_proof_account_info: &AccountInfo,
+    bus_info: &AccountInfo,
+    config_info: &AccountInfo,
+    ore_proof_account_info: &AccountInfo,
+    instructions_sysvar: &AccountInfo,
+    slot_hashes_sysvar: &AccountInfo,
+    boost_accounts: &[AccountInfo],
+    args: &MineArgs,
+) -> Instruction {
+    let mut accounts = Vec::with_capacity(6 + boost_accounts.len());
+    accounts.extend_from_slice(&[
+        AccountMeta::new(*managed_proof_account_info.key, true),
+        AccountMeta::new(*bus_info.key, false),
+        AccountMeta::new_readonly(*config_info.key, false),
+        AccountMeta::new(*ore_proof_account_info.key, false),
+        AccountMeta::new_readonly(*instructions_sysvar.key, false),
+        AccountMeta::new_readonly(*slot_hashes_sysvar.key, false),
+    ]);
+    accounts.extend(boost_accounts.iter().map(|info| AccountMeta {
+        pubkey: *info.key,
+        is_signer: false,
+        is_writable: info.is_writable,
+    }));
+
+    Instruction {
+        program_id: ore_api::id(),
+        accounts,
+        data: ore_api::instruction::Mine {
+            digest: args.digest,
+            nonce: args.nonce,
+        }
+        .to_bytes(),
+    }
+}
@@ -0,0 +1,75 @@
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, system_program};
+
+use crate::{
+    error::OreDelegationError,
+    instruction::MineArgs,
+    processor::mine::{check_boost_accounts, mine_managed_proof},
+};
+
+/// Accounts per managed proof in a batch: miner, managed proof, ORE proof,
+/// bus and the miner's delegated stake.
+const BATCH_GROUP_LEN: usize = 5;
+
+pub fn process_mine_batch(accounts: &[AccountInfo], instruction_data: &[u8]) -> Result<(), ProgramError> {
+    let [config_info, slot_hashes_sysvar, instructions_sysvar, ore_program, system_program, remaining_accounts @ ..] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse args, one solution per managed proof
+    let solutions = bytemuck::try_cast_slice::<u8, MineArgs>(instruction_data)
+        .or(Err(ProgramError::InvalidInstructionData))?;
+    if solutions.is_empty() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    if *ore_program.key != ore_api::id() {
+        solana_program::msg!("ore program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    let groups_len = solutions.len() * BATCH_GROUP_LEN;
+    if remaining_accounts.len() < groups_len {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
+    let (groups, boost_accounts) = remaining_accounts.split_at(groups_len);
+    check_boost_accounts(boost_accounts)?;
+
+    // Every proof in the batch shares the boost accounts, which only works
+    // for the ORE boost config. A reservation belongs to a single proof.
+    if boost_accounts.len() > 2 {
+        solana_program::msg!("boost accounts: global boost reservations can't be batched");
+        return Err(OreDelegationError::GlobalBoostInBatch.into());
+    }
+
+    for (group, args) in groups.chunks_exact(BATCH_GROUP_LEN).zip(solutions) {
+        let [miner, managed_proof_account_info, ore_proof_account_info, bus_info, delegated_stake_account_info] =
+            group
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        mine_managed_proof(
+            miner,
+            managed_proof_account_info,
+            bus_info,
+            config_info,
+            ore_proof_account_info,
+            delegated_stake_account_info,
+            slot_hashes_sysvar,
+            instructions_sysvar,
+            system_program,
+            boost_accounts,
+            accounts,
+            args,
+        )?;
+    }
+
+    Ok(())
+}
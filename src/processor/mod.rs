@@ -6,6 +6,7 @@ pub mod init_delegate_boost_v2;
 pub mod init_delegate_stake;
 pub mod migrate_delegate_boost_to_v2;
 pub mod mine;
+pub mod mine_batch;
 pub mod open_managed_proof;
 pub mod open_managed_proof_boost;
 pub mod undelegate_boost;
@@ -15,3 +16,16 @@ pub mod close_delegate_boost_v2;
 pub mod register_global_boost;
 pub mod rotate_global_boost;
 pub mod update_miner_authority;
+pub mod init_managed_proof_settings;
+pub mod update_allowlist;
+pub mod update_delegation_limits;
+pub mod lock_delegate_boost_v2;
+pub mod update_lock_terms;
+pub mod upgrade_account;
+pub mod approve_staker_session;
+pub mod revoke_staker_session;
+pub mod exit_delegate_boost_v2;
+pub mod post_distribution;
+pub mod claim_distribution;
+pub mod update_deposit_fee;
+pub mod close_distribution;
@@ -1,59 +1,99 @@
 use std::mem::size_of;
 
+use bytemuck::Zeroable;
 use solana_program::{
-    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, rent::Rent,
-    system_program, sysvar::Sysvar,
+    account_info::AccountInfo, program_error::ProgramError, rent::Rent, system_program,
+    sysvar::Sysvar,
 };
 
 use crate::{
+    error::OreDelegationError,
+    instruction::OpenManagedProofArgs,
+    pda::{managed_proof_pda, managed_proof_settings_pda, ManagedProofId},
     state::ManagedProof,
-    utils::{AccountDeserializeV1, Discriminator},
+    utils::{write_account_header, AccountDeserializeV1},
 };
 
+/// Opens a managed proof for the miner. Without instruction data this is the
+/// miner's original proof; with `OpenManagedProofArgs` it is the proof at the
+/// given index, so one miner key can run several proofs. Rent is paid by the
+/// payer, the last account, which can be the miner itself.
 pub fn process_open_managed_proof(
     accounts: &[AccountInfo],
-    _instruction_data: &[u8],
+    instruction_data: &[u8],
 ) -> Result<(), ProgramError> {
-    let [miner, managed_proof_account_info, ore_proof_account_info, slothashes_sysvar, rent_sysvar, ore_program, system_program] =
+    let [miner, managed_proof_account_info, ore_proof_account_info, slothashes_sysvar, rent_sysvar, ore_program, system_program, payer] =
         accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !payer.is_signer {
+        solana_program::msg!("payer: must sign");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
     if !managed_proof_account_info.is_writable {
-        return Err(ProgramError::InvalidAccountData);
+        solana_program::msg!("managed proof: must be writable");
+        return Err(OreDelegationError::AccountNotWritable.into());
     }
 
     if !managed_proof_account_info.data_is_empty() {
+        solana_program::msg!("managed proof: already initialized");
         return Err(ProgramError::AccountAlreadyInitialized);
     }
 
     if *ore_program.key != ore_api::id() {
+        solana_program::msg!("ore program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
     if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let managed_proof_account_pda = Pubkey::find_program_address(
-        &[crate::consts::MANAGED_PROOF, miner.key.as_ref()],
-        &crate::id(),
-    );
+    let index = if instruction_data.is_empty() {
+        None
+    } else {
+        let args = OpenManagedProofArgs::try_from_bytes(instruction_data)?;
+        Some(u16::from_le_bytes(args.index))
+    };
+
+    let managed_proof_id = ManagedProofId {
+        miner: *miner.key,
+        index,
+    };
+    let managed_proof_account_pda = managed_proof_pda(managed_proof_id);
+
+    if *managed_proof_account_info.key != managed_proof_account_pda.0 {
+        solana_program::msg!("managed proof: address does not match its seeds");
+        return Err(OreDelegationError::InvalidAccountAddress.into());
+    }
+
+    let mut managed_proof = ManagedProof::zeroed();
+    managed_proof.bump = managed_proof_account_pda.1;
+    managed_proof.settings_bump = managed_proof_settings_pda(managed_proof_id).1;
+    managed_proof.is_indexed = index.is_some() as u8;
+    managed_proof.index = index.unwrap_or_default();
+    managed_proof.miner_authority = *miner.key;
+    managed_proof.payer = *payer.key;
+    let signer_seeds = managed_proof.signer_seeds(miner.key);
 
     // CPI to create the proof account
     solana_program::program::invoke_signed(
         &ore_api::prelude::open(
             managed_proof_account_pda.0,
             managed_proof_account_pda.0,
-            *miner.key,
+            *payer.key,
         ),
         &[
-            miner.clone(),
+            payer.clone(),
             managed_proof_account_info.clone(),
             ore_proof_account_info.clone(),
             slothashes_sysvar.clone(),
@@ -61,11 +101,7 @@ pub fn process_open_managed_proof(
             ore_program.clone(),
             system_program.clone(),
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof_account_pda.1],
-        ]],
+        &[signer_seeds.as_slice()],
     )?;
 
     // Set the ManangedProof account data
@@ -81,25 +117,21 @@ pub fn process_open_managed_proof(
         solana_program::program::invoke_signed(
             &solana_program::system_instruction::transfer(
                 managed_proof_account_info.key,
-                miner.key,
+                payer.key,
                 managed_proof_account_info.lamports(),
             ),
             &[
-                miner.clone(),
+                payer.clone(),
                 managed_proof_account_info.clone(),
                 system_program.clone(),
             ],
-            &[&[
-                crate::consts::MANAGED_PROOF,
-                miner.key.as_ref(),
-                &[managed_proof_account_pda.1],
-            ]],
+            &[signer_seeds.as_slice()],
         )?;
     }
 
     solana_program::program::invoke_signed(
         &solana_program::system_instruction::create_account(
-            miner.key,
+            payer.key,
             managed_proof_account_info.key,
             cost,
             space
@@ -108,25 +140,18 @@ pub fn process_open_managed_proof(
             &crate::id(),
         ),
         &[
-            miner.clone(),
+            payer.clone(),
             managed_proof_account_info.clone(),
             system_program.clone(),
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof_account_pda.1],
-        ]],
+        &[signer_seeds.as_slice()],
     )?;
 
     let mut data = managed_proof_account_info.data.borrow_mut();
 
-    data[0] = ManagedProof::discriminator() as u8;
-
-    let parsed_data = ManagedProof::try_from_bytes_mut(&mut data)?;
+    write_account_header::<ManagedProof>(&mut data);
 
-    parsed_data.bump = managed_proof_account_pda.1;
-    parsed_data.miner_authority = *miner.key;
+    *ManagedProof::try_from_bytes_mut(&mut data)? = managed_proof;
 
     Ok(())
 }
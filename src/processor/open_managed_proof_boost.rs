@@ -1,51 +1,53 @@
-use solana_program::{
-    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, system_program
-};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, system_program};
 
+use crate::loaders::load_managed_proof;
+
+/// Opens the managed proof's stake account in the boost for `mint`. Rent is
+/// paid by the payer, the last account, which can be the miner itself.
 pub fn process_open_managed_proof_boost(
     accounts: &[AccountInfo],
     _instruction_data: &[u8],
 ) -> Result<(), ProgramError> {
-    let [miner, managed_proof_account_info, boost_account_info, token_mint_account_info, stake_boost_account_info, system_program, ore_boost_program] =
+    let [miner, managed_proof_account_info, boost_account_info, token_mint_account_info, stake_boost_account_info, system_program, ore_boost_program, payer] =
         accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !payer.is_signer {
+        solana_program::msg!("payer: must sign");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
     if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let managed_proof_account_pda = Pubkey::find_program_address(
-        &[crate::consts::MANAGED_PROOF, miner.key.as_ref()],
-        &crate::id(),
-    );
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, false)?;
 
     // CPI to create the proof account
     solana_program::program::invoke_signed(
         &ore_boost_api::sdk::open(
-            managed_proof_account_pda.0,
-            *miner.key,
+            *managed_proof_account_info.key,
+            *payer.key,
             *token_mint_account_info.key,
         ),
         &[
             managed_proof_account_info.clone(),
-            miner.clone(),
+            payer.clone(),
             boost_account_info.clone(),
             token_mint_account_info.clone(),
             stake_boost_account_info.clone(),
             system_program.clone(),
             ore_boost_program.clone(),
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof_account_pda.1],
-        ]],
+        &[managed_proof.signer_seeds(miner.key).as_slice()],
     )?;
 
     Ok(())
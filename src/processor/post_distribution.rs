@@ -0,0 +1,203 @@
+use std::mem::size_of;
+
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, program_error::ProgramError, pubkey::Pubkey,
+    rent::Rent, system_program, sysvar::Sysvar,
+};
+
+use crate::{
+    consts::{CLAIM_BITMAP, DISTRIBUTION, MAX_DISTRIBUTION_CLAIMS},
+    error::OreDelegationError,
+    instruction::PostDistributionArgs,
+    loaders::{load_managed_proof, load_mut},
+    state::{ClaimBitmap, DelegatedStake, Distribution},
+    utils::{write_account_header, AccountDeserializeV1, Discriminator, LayoutVersion},
+};
+
+/// Posts a merkle root of `(staker, amount)` allocations for a managed proof.
+/// The total is taken out of the miner's own `DelegatedStake`, so the ORE
+/// stays in the managed proof's ORE proof until stakers claim it with
+/// `ClaimDistribution`, or the miner closes it with `CloseDistribution` once
+/// it expires.
+pub fn process_post_distribution(
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [miner, payer, managed_proof_account_info, delegated_stake_account_info, distribution_account_info, claim_bitmap_account_info, system_program] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse args
+    let args = PostDistributionArgs::try_from_bytes(instruction_data)?;
+    let id = u64::from_le_bytes(args.id);
+    let total = u64::from_le_bytes(args.total);
+    let leaf_count = u32::from_le_bytes(args.leaf_count);
+    let expires_at = i64::from_le_bytes(args.expires_at);
+
+    if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !payer.is_signer {
+        solana_program::msg!("payer: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if leaf_count == 0 {
+        solana_program::msg!("distribution: has no allocations");
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    if leaf_count > MAX_DISTRIBUTION_CLAIMS {
+        solana_program::msg!("distribution: {} allocations, at most {}", leaf_count, MAX_DISTRIBUTION_CLAIMS);
+        return Err(OreDelegationError::TooManyDistributionClaims.into());
+    }
+
+    if expires_at <= Clock::get()?.unix_timestamp {
+        solana_program::msg!("distribution: expires at {}, already passed", expires_at);
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    load_managed_proof(managed_proof_account_info, miner.key, false)?;
+
+    // fund the distribution from the miner's own stake
+    {
+        let mut delegated_stake = load_mut::<DelegatedStake>(
+            delegated_stake_account_info,
+            &[miner.key.as_ref(), managed_proof_account_info.key.as_ref()],
+        )?;
+
+        if total > delegated_stake.amount {
+            solana_program::msg!("delegated stake: balance {} is less than {}", delegated_stake.amount, total);
+            return Err(ProgramError::InsufficientFunds);
+        }
+
+        delegated_stake.amount -= total;
+    }
+
+    let distribution_pda = Pubkey::find_program_address(
+        &[
+            DISTRIBUTION,
+            managed_proof_account_info.key.as_ref(),
+            &id.to_le_bytes(),
+        ],
+        &crate::id(),
+    );
+
+    if distribution_pda.0 != *distribution_account_info.key {
+        solana_program::msg!("distribution: address does not match its seeds");
+        return Err(OreDelegationError::InvalidAccountAddress.into());
+    }
+
+    let claim_bitmap_pda = Pubkey::find_program_address(
+        &[CLAIM_BITMAP, distribution_account_info.key.as_ref()],
+        &crate::id(),
+    );
+
+    if claim_bitmap_pda.0 != *claim_bitmap_account_info.key {
+        solana_program::msg!("claim bitmap: address does not match its seeds");
+        return Err(OreDelegationError::InvalidAccountAddress.into());
+    }
+
+    create_program_account::<Distribution>(
+        payer,
+        distribution_account_info,
+        system_program,
+        &[
+            DISTRIBUTION,
+            managed_proof_account_info.key.as_ref(),
+            &id.to_le_bytes(),
+            &[distribution_pda.1],
+        ],
+    )?;
+
+    create_program_account::<ClaimBitmap>(
+        payer,
+        claim_bitmap_account_info,
+        system_program,
+        &[
+            CLAIM_BITMAP,
+            distribution_account_info.key.as_ref(),
+            &[claim_bitmap_pda.1],
+        ],
+    )?;
+
+    {
+        let mut data = distribution_account_info.data.borrow_mut();
+        let distribution = Distribution::try_from_bytes_mut(&mut data)?;
+        distribution.bump = distribution_pda.1;
+        distribution.managed_proof = *managed_proof_account_info.key;
+        distribution.id = id;
+        distribution.root = args.root;
+        distribution.total = total;
+        distribution.leaf_count = leaf_count;
+        distribution.payer = *payer.key;
+        distribution.expires_at = expires_at;
+    }
+
+    let mut data = claim_bitmap_account_info.data.borrow_mut();
+    let claim_bitmap = ClaimBitmap::try_from_bytes_mut(&mut data)?;
+    claim_bitmap.bump = claim_bitmap_pda.1;
+    claim_bitmap.distribution = *distribution_account_info.key;
+
+    Ok(())
+}
+
+/// Create a program account at a PDA and write its header.
+fn create_program_account<'info, T: Discriminator + LayoutVersion>(
+    payer: &AccountInfo<'info>,
+    account_info: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+) -> Result<(), ProgramError> {
+    if !account_info.data_is_empty() {
+        solana_program::msg!("{:?}: already initialized", T::discriminator());
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let rent = Rent::get()?;
+
+    let space = 8 + size_of::<T>();
+
+    let cost = rent.minimum_balance(space);
+
+    if account_info.lamports() > 0 {
+        // cleanup any lamports that may have been sent before our program
+        // created the account
+        solana_program::program::invoke_signed(
+            &solana_program::system_instruction::transfer(
+                account_info.key,
+                payer.key,
+                account_info.lamports(),
+            ),
+            &[payer.clone(), account_info.clone(), system_program.clone()],
+            &[signer_seeds],
+        )?;
+    }
+
+    solana_program::program::invoke_signed(
+        &solana_program::system_instruction::create_account(
+            payer.key,
+            account_info.key,
+            cost,
+            space
+                .try_into()
+                .expect("failed to convert space usize to u64"),
+            &crate::id(),
+        ),
+        &[payer.clone(), account_info.clone(), system_program.clone()],
+        &[signer_seeds],
+    )?;
+
+    write_account_header::<T>(&mut account_info.data.borrow_mut());
+
+    Ok(())
+}
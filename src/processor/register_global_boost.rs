@@ -1,59 +1,57 @@
-use solana_program::{
-    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, system_program
-};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError, system_program};
 
-use crate::global_boost::register;
+use crate::{global_boost::register, loaders::load_managed_proof};
 
+/// Registers the managed proof's ORE proof with the global boost program. The
+/// reservation's rent is paid by the payer, the last account, which can be
+/// the miner itself.
 pub fn process_register_global_boost(
     accounts: &[AccountInfo],
     _instruction_data: &[u8],
 ) -> Result<(), ProgramError> {
-    let [miner, managed_proof_account_info, managed_proof, reservation, system_program, ore_global_boost_program] =
+    let [miner, managed_proof_account_info, managed_proof, reservation, system_program, ore_global_boost_program, payer] =
         accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
     if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !payer.is_signer {
+        solana_program::msg!("payer: must sign");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
     if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let managed_proof_account_pda = Pubkey::find_program_address(
-        &[crate::consts::MANAGED_PROOF, miner.key.as_ref()],
-        &crate::id(),
-    );
-
-    if managed_proof_account_pda.0 != *managed_proof.key {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let managed_proof_data = load_managed_proof(managed_proof, miner.key, false)?;
      
     if *ore_global_boost_program.key != crate::global_boost::GLOBAL_BOOST_ID {
+        solana_program::msg!("ore global boost program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
     // CPI to register the proof account
     solana_program::program::invoke_signed(
         &register(
-            managed_proof_account_pda.0,
-            *miner.key,
+            *managed_proof.key,
+            *payer.key,
             *managed_proof_account_info.key,
         ),
         &[
             managed_proof.clone(),
-            miner.clone(),
+            payer.clone(),
             managed_proof_account_info.clone(),
             reservation.clone(),
             system_program.clone(),
             ore_global_boost_program.clone(),
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof_account_pda.1],
-        ]],
+        &[managed_proof_data.signer_seeds(miner.key).as_slice()],
     )?;
 
     Ok(())
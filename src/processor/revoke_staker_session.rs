@@ -0,0 +1,74 @@
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, program_option::COption,
+    program_pack::Pack,
+};
+
+use crate::{error::OreDelegationError, loaders::load, state::StakerSession};
+
+/// Ends a staker session, before or after it expires. The session's SPL token
+/// approval on the staker's token account, which outlives the session's
+/// expiry, is revoked and its rent returned to the account that paid it.
+pub fn process_revoke_staker_session(
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [staker, operator, token_mint_account_info, session_account_info, staker_token_account_info, token_program, payer] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !staker.is_signer {
+        solana_program::msg!("staker: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let session = load::<StakerSession>(
+        session_account_info,
+        &[
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            operator.key.as_ref(),
+        ],
+        true,
+    )?;
+
+    if session.payer != *payer.key {
+        return Err(OreDelegationError::CloseAccountFeePayerMissmatch.into());
+    }
+
+    if *token_program.key != spl_token::id() {
+        solana_program::msg!("token program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if *staker_token_account_info.owner != spl_token::id() {
+        solana_program::msg!("staker token account: not owned by the token program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    // only clear the approval if it still belongs to this session
+    let staker_tokens = spl_token::state::Account::unpack(&staker_token_account_info.data.borrow())?;
+    if staker_tokens.delegate == COption::Some(*session_account_info.key) {
+        solana_program::program::invoke(
+            &spl_token::instruction::revoke(
+                &spl_token::id(),
+                staker_token_account_info.key,
+                staker.key,
+                &[],
+            )?,
+            &[
+                staker_token_account_info.clone(),
+                staker.clone(),
+                token_program.clone(),
+            ],
+        )?;
+    }
+
+    session_account_info.realloc(0, true)?;
+
+    **payer.lamports.borrow_mut() += session_account_info.lamports();
+    **session_account_info.lamports.borrow_mut() = 0;
+
+    Ok(())
+}
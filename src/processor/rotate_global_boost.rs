@@ -1,8 +1,6 @@
-use solana_program::{
-    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey
-};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError};
 
-use crate::global_boost::rotate;
+use crate::{global_boost::rotate, loaders::load_managed_proof};
 
 pub fn process_rotate_global_boost(
     accounts: &[AccountInfo],
@@ -15,26 +13,21 @@ pub fn process_rotate_global_boost(
     };
 
     if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    let managed_proof_account_pda = Pubkey::find_program_address(
-        &[crate::consts::MANAGED_PROOF, miner.key.as_ref()],
-        &crate::id(),
-    );
-
-    if managed_proof_account_pda.0 != *managed_proof.key {
-        return Err(ProgramError::InvalidAccountData);
-    }
+    let managed_proof_data = load_managed_proof(managed_proof, miner.key, false)?;
      
     if *ore_global_boost_program.key != crate::global_boost::GLOBAL_BOOST_ID {
+        solana_program::msg!("ore global boost program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
     // CPI to register the proof account
     solana_program::program::invoke_signed(
         &rotate(
-            managed_proof_account_pda.0,
+            *managed_proof.key,
             *managed_proof_account_info.key,
         ),
         &[
@@ -45,11 +38,7 @@ pub fn process_rotate_global_boost(
             treasury_tokens_address.clone(),
             ore_global_boost_program.clone(),
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof_account_pda.1],
-        ]],
+        &[managed_proof_data.signer_seeds(miner.key).as_slice()],
     )?;
 
     Ok(())
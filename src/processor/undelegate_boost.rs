@@ -1,11 +1,9 @@
 use solana_program::{account_info::AccountInfo, program_error::ProgramError};
-use steel::{transfer_signed, transfer_signed_with_bump};
 
 use crate::{
     instruction::UndelegateBoostArgs,
-    loaders::{load_delegated_boost, load_managed_proof},
-    state::ManagedProof,
-    utils::AccountDeserializeV1,
+    loaders::{load_managed_proof, load_mut},
+    state::DelegatedBoost,
 };
 
 pub fn process_undelegate_boost(
@@ -36,49 +34,44 @@ pub fn process_undelegate_boost(
     let amount = u64::from_le_bytes(args.amount);
 
     if !staker.is_signer {
+        solana_program::msg!("staker: must sign");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    load_managed_proof(managed_proof_account_info, miner.key, false)?;
-    load_delegated_boost(
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let mut delegated_boost = load_mut::<DelegatedBoost>(
         delegate_boost_account_info,
-        staker.key,
-        managed_proof_account_info.key,
-        token_mint_account_info.key,
-        true,
+        &[
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            managed_proof_account_info.key.as_ref(),
+        ],
     )?;
 
     if *ore_boost_program.key != ore_boost_api::id() {
+        solana_program::msg!("ore boost program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
     if *token_program.key != spl_token::id() {
+        solana_program::msg!("token program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let managed_proof = {
-        let data = managed_proof_account_info.data.borrow();
-        ManagedProof::try_from_bytes(&data)?.clone()
-    };
-
     // decrease delegate boost balance
-    if let Ok(mut data) = delegate_boost_account_info.data.try_borrow_mut() {
-        let delegated_boost = crate::state::DelegatedBoost::try_from_bytes_mut(&mut data)?;
-
-        if amount > delegated_boost.amount {
-            return Err(ProgramError::InsufficientFunds);
-        }
+    if amount > delegated_boost.amount {
+        solana_program::msg!("delegated boost: balance {} is less than {}", delegated_boost.amount, amount);
+        return Err(ProgramError::InsufficientFunds);
+    }
 
-        if let Some(new_total) = delegated_boost.amount.checked_sub(amount) {
-            delegated_boost.amount = new_total;
-        } else {
-            return Err(ProgramError::ArithmeticOverflow);
-        }
+    if let Some(new_total) = delegated_boost.amount.checked_sub(amount) {
+        delegated_boost.amount = new_total;
     } else {
-        return Err(ProgramError::AccountBorrowFailed);
+        return Err(ProgramError::ArithmeticOverflow);
     }
 
-    // withdraw from boost program 
+    // withdraw from boost program
+    let signer_seeds = managed_proof.signer_seeds(miner.key);
     solana_program::program::invoke_signed(
         &ore_boost_api::sdk::withdraw(
             *managed_proof_account_info.key,
@@ -95,31 +88,26 @@ pub fn process_undelegate_boost(
             ore_boost_program.clone(),
             token_program.clone(),
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof.bump],
-        ]],
+        &[signer_seeds.as_slice()],
     )?;
 
-    let bump = managed_proof.bump;
-    let seeds: Vec<&[u8]> = vec![
-        crate::consts::MANAGED_PROOF,
-        miner.key.as_ref(),
-    ];
-
-    let seeds: &[&[u8]] = &seeds;
-
-
     // transfer to stakers token account
-    transfer_signed_with_bump(
-        managed_proof_account_info,
-        managed_proof_account_token_account_info,
-        staker_token_account_info,
-        token_program,
-        amount,
-        seeds,
-        bump
+    solana_program::program::invoke_signed(
+        &spl_token::instruction::transfer(
+            &spl_token::id(),
+            managed_proof_account_token_account_info.key,
+            staker_token_account_info.key,
+            managed_proof_account_info.key,
+            &[],
+            amount,
+        )?,
+        &[
+            managed_proof_account_token_account_info.clone(),
+            staker_token_account_info.clone(),
+            managed_proof_account_info.clone(),
+            token_program.clone(),
+        ],
+        &[signer_seeds.as_slice()],
     )?;
 
     Ok(())
@@ -1,11 +1,13 @@
-use solana_program::{account_info::AccountInfo, program_error::ProgramError};
-use steel::{transfer_signed, transfer_signed_with_bump};
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, program_error::ProgramError, program_pack::Pack,
+    sysvar::Sysvar,
+};
 
 use crate::{
+    error::OreDelegationError,
     instruction::UndelegateBoostArgs,
-    loaders::{load_delegated_boost_v2, load_managed_proof},
-    state::ManagedProof,
-    utils::AccountDeserializeV1,
+    loaders::{load_managed_proof, load_managed_proof_settings, load_mut, load_staker_session},
+    state::DelegatedBoostV2,
 };
 
 pub fn process_undelegate_boost_v2(
@@ -31,7 +33,9 @@ pub fn process_undelegate_boost_v2(
         ore_treasury_info,
         ore_treasury_tokens_info,
         ore_program,
-        token_program
+        token_program,
+        settings_account_info,
+        session_accounts @ ..
     ] =
         accounts
     else {
@@ -40,52 +44,92 @@ pub fn process_undelegate_boost_v2(
 
     // Parse args
     let args = UndelegateBoostArgs::try_from_bytes(instruction_data)?;
-    let amount = u64::from_le_bytes(args.amount);
-
-    if !staker.is_signer {
-        return Err(ProgramError::MissingRequiredSignature);
+    let mut amount = u64::from_le_bytes(args.amount);
+
+    let clock = Clock::get()?;
+
+    // An operator holding a staker session can undelegate in place of the
+    // staker, but only into a token account the staker owns.
+    match session_accounts {
+        [] => {
+            if !staker.is_signer {
+                solana_program::msg!("staker: must sign");
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+        }
+        [operator, session_account_info] => {
+            load_staker_session(
+                session_account_info,
+                staker.key,
+                token_mint_account_info.key,
+                managed_proof_account_info.key,
+                operator,
+                clock.unix_timestamp,
+            )?;
+
+            if *staker_token_account_info.owner != spl_token::id() {
+                return Err(OreDelegationError::InvalidWithdrawalDestination.into());
+            }
+            let destination =
+                spl_token::state::Account::unpack(&staker_token_account_info.data.borrow())?;
+            if destination.owner != *staker.key || destination.mint != *token_mint_account_info.key {
+                return Err(OreDelegationError::InvalidWithdrawalDestination.into());
+            }
+        }
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
     }
 
-    load_managed_proof(managed_proof_account_info, miner.key, false)?;
-    load_delegated_boost_v2(
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let mut delegated_boost = load_mut::<DelegatedBoostV2>(
         delegate_boost_account_info,
-        staker.key,
+        &[
+            staker.key.as_ref(),
+            token_mint_account_info.key.as_ref(),
+            managed_proof_account_info.key.as_ref(),
+        ],
+    )?;
+
+    if delegated_boost.is_locked(clock.unix_timestamp) {
+        return Err(OreDelegationError::DelegationLocked.into());
+    }
+
+    let settings = load_managed_proof_settings(
+        settings_account_info,
         managed_proof_account_info.key,
-        token_mint_account_info.key,
-        true,
+        &managed_proof,
+        false,
     )?;
 
     if *ore_boost_program.key != ore_boost_api::id() {
+        solana_program::msg!("ore boost program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
     if *token_program.key != spl_token::id() {
+        solana_program::msg!("token program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let managed_proof = {
-        let data = managed_proof_account_info.data.borrow();
-        ManagedProof::try_from_bytes(&data)?.clone()
-    };
-
     // decrease delegate boost balance
-    if let Ok(mut data) = delegate_boost_account_info.data.try_borrow_mut() {
-        let delegated_boost = crate::state::DelegatedBoostV2::try_from_bytes_mut(&mut data)?;
+    if amount > delegated_boost.amount {
+        solana_program::msg!("delegated boost: balance {} is less than {}", delegated_boost.amount, amount);
+        return Err(ProgramError::InsufficientFunds);
+    }
 
-        if amount > delegated_boost.amount {
-            return Err(ProgramError::InsufficientFunds);
-        }
+    // withdraw everything rather than leave dust behind
+    let remaining = delegated_boost.amount - amount;
+    if remaining > 0 && remaining < settings.min_remaining_balance {
+        amount = delegated_boost.amount;
+    }
 
-        if let Some(new_total) = delegated_boost.amount.checked_sub(amount) {
-            delegated_boost.amount = new_total;
-        } else {
-            return Err(ProgramError::ArithmeticOverflow);
-        }
+    if let Some(new_total) = delegated_boost.amount.checked_sub(amount) {
+        delegated_boost.amount = new_total;
     } else {
-        return Err(ProgramError::AccountBorrowFailed);
+        return Err(ProgramError::ArithmeticOverflow);
     }
 
-    // withdraw from boost program 
+    // withdraw from boost program
+    let signer_seeds = managed_proof.signer_seeds(miner.key);
     solana_program::program::invoke_signed(
         &ore_boost_api::sdk::withdraw(
             *managed_proof_account_info.key,
@@ -107,30 +151,26 @@ pub fn process_undelegate_boost_v2(
             ore_program.clone(),
             token_program.clone(),
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof.bump],
-        ]],
+        &[signer_seeds.as_slice()],
     )?;
 
-    let bump = managed_proof.bump;
-    let seeds: Vec<&[u8]> = vec![
-        crate::consts::MANAGED_PROOF,
-        miner.key.as_ref(),
-    ];
-
-    let seeds: &[&[u8]] = &seeds;
-
     // transfer to stakers token account
-    transfer_signed_with_bump(
-        managed_proof_account_info,
-        managed_proof_account_token_account_info,
-        staker_token_account_info,
-        token_program,
-        amount,
-        seeds,
-        bump
+    solana_program::program::invoke_signed(
+        &spl_token::instruction::transfer(
+            &spl_token::id(),
+            managed_proof_account_token_account_info.key,
+            staker_token_account_info.key,
+            managed_proof_account_info.key,
+            &[],
+            amount,
+        )?,
+        &[
+            managed_proof_account_token_account_info.clone(),
+            staker_token_account_info.clone(),
+            managed_proof_account_info.clone(),
+            token_program.clone(),
+        ],
+        &[signer_seeds.as_slice()],
     )?;
 
     Ok(())
@@ -2,9 +2,8 @@ use solana_program::{account_info::AccountInfo, program_error::ProgramError};
 
 use crate::{
     instruction::DelegateStakeArgs,
-    loaders::{load_delegated_stake, load_managed_proof},
-    state::ManagedProof,
-    utils::AccountDeserializeV1,
+    loaders::{load_managed_proof, load_mut},
+    state::DelegatedStake,
 };
 
 pub fn process_undelegate_stake(
@@ -22,45 +21,36 @@ pub fn process_undelegate_stake(
     let amount = u64::from_le_bytes(args.amount);
 
     if !staker.is_signer {
+        solana_program::msg!("staker: must sign");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    load_managed_proof(managed_proof_account_info, miner.key, false)?;
-    load_delegated_stake(
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let mut delegated_stake = load_mut::<DelegatedStake>(
         delegated_stake_account_info,
-        staker.key,
-        &managed_proof_account_info.key,
-        true,
+        &[staker.key.as_ref(), managed_proof_account_info.key.as_ref()],
     )?;
 
     if *ore_program.key != ore_api::id() {
+        solana_program::msg!("ore program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
     if *token_program.key != spl_token::id() {
+        solana_program::msg!("token program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let managed_proof = {
-        let data = managed_proof_account_info.data.borrow();
-        ManagedProof::try_from_bytes(&data)?.clone()
-    };
-
     // decrease delegate stake balance
-    if let Ok(mut data) = delegated_stake_account_info.data.try_borrow_mut() {
-        let delegated_stake = crate::state::DelegatedStake::try_from_bytes_mut(&mut data)?;
-
-        if amount > delegated_stake.amount {
-            return Err(ProgramError::InsufficientFunds);
-        }
+    if amount > delegated_stake.amount {
+        solana_program::msg!("delegated stake: balance {} is less than {}", delegated_stake.amount, amount);
+        return Err(ProgramError::InsufficientFunds);
+    }
 
-        if let Some(new_total) = delegated_stake.amount.checked_sub(amount) {
-            delegated_stake.amount = new_total;
-        } else {
-            return Err(ProgramError::ArithmeticOverflow);
-        }
+    if let Some(new_total) = delegated_stake.amount.checked_sub(amount) {
+        delegated_stake.amount = new_total;
     } else {
-        return Err(ProgramError::AccountBorrowFailed);
+        return Err(ProgramError::ArithmeticOverflow);
     }
 
     // stake to ore program
@@ -78,11 +68,7 @@ pub fn process_undelegate_stake(
             treasury_tokens.clone(),
             ore_program.clone(),
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof.bump],
-        ]],
+        &[managed_proof.signer_seeds(miner.key).as_slice()],
     )?;
 
     Ok(())
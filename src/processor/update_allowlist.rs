@@ -0,0 +1,35 @@
+use solana_program::{account_info::AccountInfo, program_error::ProgramError};
+
+use crate::{
+    instruction::UpdateAllowlistArgs,
+    loaders::{load_managed_proof, load_mut},
+    state::ManagedProofSettings,
+};
+
+pub fn process_update_allowlist(
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [miner, managed_proof_account_info, settings_account_info] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse args
+    let args = UpdateAllowlistArgs::try_from_bytes(instruction_data)?;
+
+    if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let mut settings = load_mut::<ManagedProofSettings>(
+        settings_account_info,
+        &[managed_proof_account_info.key.as_ref()],
+    )?;
+
+    settings.allowlist_enabled = (args.enabled != 0) as u8;
+    settings.allowlist_root = args.root;
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use solana_program::{account_info::AccountInfo, program_error::ProgramError};
+
+use crate::{
+    instruction::UpdateDelegationLimitsArgs,
+    loaders::{load_managed_proof, load_mut},
+    state::ManagedProofSettings,
+};
+
+pub fn process_update_delegation_limits(
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [miner, managed_proof_account_info, settings_account_info] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse args
+    let args = UpdateDelegationLimitsArgs::try_from_bytes(instruction_data)?;
+    let min_delegation = u64::from_le_bytes(args.min_delegation);
+    let min_remaining_balance = u64::from_le_bytes(args.min_remaining_balance);
+
+    if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let mut settings = load_mut::<ManagedProofSettings>(
+        settings_account_info,
+        &[managed_proof_account_info.key.as_ref()],
+    )?;
+
+    settings.min_delegation = min_delegation;
+    settings.min_remaining_balance = min_remaining_balance;
+
+    Ok(())
+}
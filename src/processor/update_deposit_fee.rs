@@ -0,0 +1,82 @@
+use std::mem::size_of;
+
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, program_error::ProgramError, system_program,
+    sysvar::Sysvar,
+};
+
+use crate::{
+    consts::{MAX_DEPOSIT_FEE_BPS, MIN_FEE_CHANGE_DELAY},
+    error::OreDelegationError,
+    instruction::UpdateDepositFeeArgs,
+    loaders::{check_upgradable, load_managed_proof, load_mut},
+    processor::upgrade_account::upgrade,
+    state::ManagedProofSettings,
+};
+
+/// Sets the fee charged on boost deposits and who receives it. A lower fee
+/// applies right away, a higher one only after the settings' fee change
+/// delay, so delegators get notice of it. The recipient changes right away:
+/// it decides who is paid, not what a delegator pays, so it needs no notice.
+/// The delay can be lengthened but never shortened. Settings with a layout from before the deposit fee are
+/// checked and upgraded first, at the miner's expense.
+pub fn process_update_deposit_fee(
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [miner, managed_proof_account_info, settings_account_info, fee_recipient, system_program] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse args
+    let args = UpdateDepositFeeArgs::try_from_bytes(instruction_data)?;
+    let deposit_fee_bps = u64::from_le_bytes(args.deposit_fee_bps);
+    let fee_change_delay = i64::from_le_bytes(args.fee_change_delay);
+
+    if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if deposit_fee_bps > MAX_DEPOSIT_FEE_BPS {
+        solana_program::msg!("deposit fee: {} bps, at most {}", deposit_fee_bps, MAX_DEPOSIT_FEE_BPS);
+        return Err(OreDelegationError::DepositFeeTooHigh.into());
+    }
+
+    load_managed_proof(managed_proof_account_info, miner.key, false)?;
+
+    if settings_account_info.data_len() < 8 + size_of::<ManagedProofSettings>() {
+        check_upgradable::<ManagedProofSettings>(
+            settings_account_info,
+            &[managed_proof_account_info.key.as_ref()],
+        )?;
+        upgrade::<ManagedProofSettings>(miner, settings_account_info, system_program)?;
+    }
+
+    let mut settings = load_mut::<ManagedProofSettings>(
+        settings_account_info,
+        &[managed_proof_account_info.key.as_ref()],
+    )?;
+
+    // 0 keeps the current delay
+    let current_delay = settings.fee_change_delay.max(MIN_FEE_CHANGE_DELAY);
+    if fee_change_delay != 0 && fee_change_delay < current_delay {
+        solana_program::msg!("fee change delay: {} seconds, at least {}", fee_change_delay, current_delay);
+        return Err(OreDelegationError::FeeChangeDelayTooShort.into());
+    }
+    settings.fee_change_delay = current_delay.max(fee_change_delay);
+
+    // An increase is scheduled with the delay it was set with
+    let now = Clock::get()?.unix_timestamp;
+    settings.schedule_deposit_fee(deposit_fee_bps, now);
+    settings.deposit_fee_recipient = *fee_recipient.key;
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use solana_program::{account_info::AccountInfo, program_error::ProgramError};
+
+use crate::{
+    instruction::UpdateLockTermsArgs,
+    loaders::{load_managed_proof, load_mut},
+    state::ManagedProofSettings,
+};
+
+pub fn process_update_lock_terms(
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [miner, managed_proof_account_info, settings_account_info] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse args
+    let args = UpdateLockTermsArgs::try_from_bytes(instruction_data)?;
+    let lock_bonus_bps = u64::from_le_bytes(args.lock_bonus_bps);
+    let min_lock_duration = i64::from_le_bytes(args.min_lock_duration);
+
+    if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if min_lock_duration < 0 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    load_managed_proof(managed_proof_account_info, miner.key, false)?;
+    let mut settings = load_mut::<ManagedProofSettings>(
+        settings_account_info,
+        &[managed_proof_account_info.key.as_ref()],
+    )?;
+
+    // Existing locks keep the bonus they were granted
+    settings.lock_bonus_bps = lock_bonus_bps;
+    settings.min_lock_duration = min_lock_duration;
+
+    Ok(())
+}
@@ -1,11 +1,6 @@
-use steel::{AccountDeserialize as _, Pubkey};
-use solana_program::{account_info::AccountInfo, program_error::ProgramError, system_program};
+use solana_program::{account_info::AccountInfo, program_error::ProgramError};
 
-use crate::{
-    loaders::{load_delegated_stake, load_managed_proof},
-    state::ManagedProof,
-    utils::AccountDeserializeV1,
-};
+use crate::loaders::load_managed_proof;
 
 pub fn process_update_miner_authority(accounts: &[AccountInfo], instruction_data: &[u8]) -> Result<(), ProgramError> {
     let [miner, managed_proof_account_info, new_miner_info, ore_proof_account_info, ore_program] =
@@ -15,19 +10,16 @@ pub fn process_update_miner_authority(accounts: &[AccountInfo], instruction_data
     };
 
     if !miner.is_signer {
+        solana_program::msg!("miner: must sign");
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    load_managed_proof(managed_proof_account_info, miner.key, true)?;
+    let managed_proof = load_managed_proof(managed_proof_account_info, miner.key, true)?;
     if *ore_program.key != ore_api::id() {
+        solana_program::msg!("ore program: incorrect program id");
         return Err(ProgramError::IncorrectProgramId);
     }
 
-    let managed_proof_account_pda = Pubkey::find_program_address(
-        &[crate::consts::MANAGED_PROOF, miner.key.as_ref()],
-        &crate::id(),
-    );
-
     // Update the Miners Authority
     solana_program::program::invoke_signed(
         &ore_api::sdk::update(
@@ -39,11 +31,7 @@ pub fn process_update_miner_authority(accounts: &[AccountInfo], instruction_data
             new_miner_info.clone(),
             ore_proof_account_info.clone()
         ],
-        &[&[
-            crate::consts::MANAGED_PROOF,
-            miner.key.as_ref(),
-            &[managed_proof_account_pda.1],
-        ]],
+        &[managed_proof.signer_seeds(miner.key).as_slice()],
     )?;
 
     Ok(())
@@ -0,0 +1,186 @@
+use std::mem::size_of;
+
+use bytemuck::Pod;
+use solana_program::{
+    account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey, rent::Rent,
+    system_program, sysvar::Sysvar,
+};
+
+use crate::{
+    error::OreDelegationError,
+    loaders::{check_upgradable, check_upgradable_distribution, check_upgradable_managed_proof},
+    state::{
+        ClaimBitmap, DelegatedBoost, DelegatedBoostV2, DelegatedStake, Distribution, ManagedProof,
+        ManagedProofSettings, StakerSession,
+    },
+    utils::{AccountDiscriminator, LayoutVersion},
+};
+
+/// Upgrade any program account to the current layout of its type. The account
+/// keeps its address, is reallocated to the new size with rent topped up by
+/// the payer, and the new fields are filled with their defaults. The accounts
+/// after the system program are the keys the account's PDA is derived from,
+/// in seed order, and the address is checked against them first.
+pub fn process_upgrade_account(
+    accounts: &[AccountInfo],
+    _instruction_data: &[u8],
+) -> Result<(), ProgramError> {
+    let [payer, account_info, system_program, seed_accounts @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !payer.is_signer {
+        solana_program::msg!("payer: must sign");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if *system_program.key != system_program::id() {
+        solana_program::msg!("system program: incorrect program id");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    if account_info.owner.ne(&crate::id()) {
+        solana_program::msg!("account: not owned by this program");
+        return Err(ProgramError::InvalidAccountOwner);
+    }
+
+    if !account_info.is_writable {
+        solana_program::msg!("account: must be writable");
+        return Err(OreDelegationError::AccountNotWritable.into());
+    }
+
+    if account_info.data_len() < 8 {
+        solana_program::msg!("account: not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+
+    let discriminator = account_info.data.borrow()[0];
+    let discriminator = AccountDiscriminator::try_from(discriminator).or_else(|_| {
+        solana_program::msg!("account: unknown discriminator {}", discriminator);
+        Err(OreDelegationError::InvalidAccountDiscriminator)
+    })?;
+
+    match discriminator {
+        AccountDiscriminator::ManagedProof => {
+            let [miner] = seed_keys(seed_accounts)?;
+            check_upgradable_managed_proof(account_info, miner)?;
+            upgrade::<ManagedProof>(payer, account_info, system_program)
+        }
+        AccountDiscriminator::DelegatedStake => {
+            let [staker, managed_proof] = seed_keys(seed_accounts)?;
+            check_upgradable::<DelegatedStake>(
+                account_info,
+                &[staker.as_ref(), managed_proof.as_ref()],
+            )?;
+            upgrade::<DelegatedStake>(payer, account_info, system_program)
+        }
+        AccountDiscriminator::DelegatedBoost => {
+            let [staker, mint, managed_proof] = seed_keys(seed_accounts)?;
+            check_upgradable::<DelegatedBoost>(
+                account_info,
+                &[staker.as_ref(), mint.as_ref(), managed_proof.as_ref()],
+            )?;
+            upgrade::<DelegatedBoost>(payer, account_info, system_program)
+        }
+        AccountDiscriminator::DelegatedBoostV2 => {
+            let [staker, mint, managed_proof] = seed_keys(seed_accounts)?;
+            check_upgradable::<DelegatedBoostV2>(
+                account_info,
+                &[staker.as_ref(), mint.as_ref(), managed_proof.as_ref()],
+            )?;
+            upgrade::<DelegatedBoostV2>(payer, account_info, system_program)
+        }
+        AccountDiscriminator::ManagedProofSettings => {
+            let [managed_proof] = seed_keys(seed_accounts)?;
+            check_upgradable::<ManagedProofSettings>(account_info, &[managed_proof.as_ref()])?;
+            upgrade::<ManagedProofSettings>(payer, account_info, system_program)
+        }
+        AccountDiscriminator::StakerSession => {
+            let [staker, mint, operator] = seed_keys(seed_accounts)?;
+            check_upgradable::<StakerSession>(
+                account_info,
+                &[staker.as_ref(), mint.as_ref(), operator.as_ref()],
+            )?;
+            upgrade::<StakerSession>(payer, account_info, system_program)
+        }
+        AccountDiscriminator::Distribution => {
+            let [managed_proof] = seed_keys(seed_accounts)?;
+            check_upgradable_distribution(account_info, managed_proof)?;
+            upgrade::<Distribution>(payer, account_info, system_program)
+        }
+        AccountDiscriminator::ClaimBitmap => {
+            let [distribution] = seed_keys(seed_accounts)?;
+            check_upgradable::<ClaimBitmap>(account_info, &[distribution.as_ref()])?;
+            upgrade::<ClaimBitmap>(payer, account_info, system_program)
+        }
+    }
+}
+
+/// The keys of the `N` seed accounts an account type is derived from.
+fn seed_keys<'a, const N: usize>(
+    seed_accounts: &'a [AccountInfo],
+) -> Result<[&'a Pubkey; N], ProgramError> {
+    if seed_accounts.len() != N {
+        solana_program::msg!("seed accounts: expected {}, got {}", N, seed_accounts.len());
+        return Err(if seed_accounts.len() < N {
+            ProgramError::NotEnoughAccountKeys
+        } else {
+            ProgramError::InvalidArgument
+        });
+    }
+
+    Ok(std::array::from_fn(|i| seed_accounts[i].key))
+}
+
+/// Reallocate `account_info` to the current layout of `T`. Callers check the
+/// account is a `T` at the right address first.
+pub(crate) fn upgrade<'info, T: Pod + LayoutVersion>(
+    payer: &AccountInfo<'info>,
+    account_info: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<(), ProgramError> {
+    let space = 8 + size_of::<T>();
+    let current_len = account_info.data_len();
+    if current_len < 8 {
+        solana_program::msg!("account: not initialized");
+        return Err(ProgramError::UninitializedAccount);
+    }
+    let from_version = account_info.data.borrow()[1];
+
+    if current_len > space
+        || (current_len == space && from_version >= T::layout_version())
+    {
+        return Err(OreDelegationError::AccountAlreadyUpgraded.into());
+    }
+
+    // top up rent for the larger account
+    let cost = Rent::get()?.minimum_balance(space);
+    let lamports = account_info.lamports();
+    if lamports < cost {
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                payer.key,
+                account_info.key,
+                cost - lamports,
+            ),
+            &[payer.clone(), account_info.clone(), system_program.clone()],
+        )?;
+    }
+
+    // new bytes are zeroed by the realloc
+    if current_len < space {
+        account_info.realloc(space, true)?;
+    }
+
+    if let Ok(mut data) = account_info.data.try_borrow_mut() {
+        data[1] = T::layout_version();
+
+        let account = bytemuck::try_from_bytes_mut::<T>(&mut data[8..])
+            .or(Err(OreDelegationError::InvalidAccountLayout))?;
+        account.upgrade_defaults(from_version);
+    } else {
+        return Err(ProgramError::AccountBorrowFailed);
+    }
+
+    Ok(())
+}
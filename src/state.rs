@@ -1,9 +1,14 @@
 use bytemuck::{Pod, Zeroable};
-use solana_program::{account_info::AccountInfo, program_error::ProgramError, pubkey::Pubkey};
+use solana_program::pubkey::Pubkey;
 
 use crate::{
-    impl_account_from_account_info, impl_account_from_bytes, impl_to_bytes,
-    utils::{AccountDeserializeV1, AccountDiscriminator, Discriminator},
+    consts::{
+        CLAIM_BITMAP, DELEGATED_BOOST, DELEGATED_BOOST_V2, DELEGATED_STAKE, DISTRIBUTION,
+        MANAGED_PROOF, MANAGED_PROOF_SETTINGS, MIN_FEE_CHANGE_DELAY, REWARD_RATE_SMOOTHING,
+        STAKER_SESSION,
+    },
+    impl_account_from_bytes, impl_program_account, impl_to_bytes,
+    utils::{AccountDiscriminator, Discriminator, LayoutVersion},
 };
 
 // ManagedProof
@@ -11,8 +16,94 @@ use crate::{
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
 pub struct ManagedProof {
     pub bump: u8,
-    _pad: [u8; 7],
+    /// 1 when the proof was opened with an index and its address is seeded by
+    /// `[MANAGED_PROOF, miner, index]` instead of `[MANAGED_PROOF, miner]`.
+    pub is_indexed: u8,
+    pub index: u16,
+    /// Bump of the proof's `ManagedProofSettings` address, so a proof without
+    /// settings can have that address checked without searching for it. 0 on
+    /// proofs opened before it was stored.
+    pub settings_bump: u8,
+    _pad: [u8; 3],
     pub miner_authority: Pubkey,
+    /// Solutions submitted through this program.
+    pub total_submissions: u64,
+    /// ORE credited to the miner's `DelegatedStake` by mining.
+    pub total_rewards: u64,
+    /// Unix timestamp of the last submission.
+    pub last_mine_at: i64,
+    /// Moving average of the ORE earned per minute between submissions.
+    pub reward_rate: u64,
+    /// Highest difficulty submitted.
+    pub best_difficulty: u32,
+    _stats_pad: [u8; 4],
+    /// Account that paid the rent and is refunded if the proof is closed.
+    pub payer: Pubkey,
+}
+
+impl ManagedProof {
+    /// The proof's index, `None` for a miner's original, index-less proof.
+    pub fn index(&self) -> Option<u16> {
+        (self.is_indexed != 0).then_some(self.index)
+    }
+
+    /// The PDA seeds that follow the type seed, without the bump. The index is
+    /// seeded as little endian bytes, matching `pda::managed_proof_pda`.
+    pub fn seeds<'a>(&'a self, miner: &'a Pubkey) -> ManagedProofSeeds<'a> {
+        let mut seeds = ManagedProofSeeds::default();
+        seeds.push(miner.as_ref());
+        if self.is_indexed != 0 {
+            seeds.push(bytemuck::bytes_of(&self.index));
+        }
+        seeds
+    }
+
+    /// The seeds to sign for the managed proof with.
+    pub fn signer_seeds<'a>(&'a self, miner: &'a Pubkey) -> ManagedProofSeeds<'a> {
+        let mut seeds = ManagedProofSeeds::default();
+        seeds.push(MANAGED_PROOF);
+        seeds.push(miner.as_ref());
+        if self.is_indexed != 0 {
+            seeds.push(bytemuck::bytes_of(&self.index));
+        }
+        seeds.push(std::slice::from_ref(&self.bump));
+        seeds
+    }
+
+    /// Record a submission that earned `reward` at `now`.
+    pub fn record_mine(&mut self, reward: u64, difficulty: u32, now: i64) {
+        self.total_submissions = self.total_submissions.saturating_add(1);
+        self.total_rewards = self.total_rewards.saturating_add(reward);
+        self.best_difficulty = self.best_difficulty.max(difficulty);
+
+        // The first submission has no interval to measure a rate over.
+        if self.last_mine_at > 0 {
+            let elapsed = now.saturating_sub(self.last_mine_at).max(1) as u128;
+            let sample = (reward as u128 * 60 / elapsed).min(u64::MAX as u128) as u64;
+            self.reward_rate = self.reward_rate - self.reward_rate / REWARD_RATE_SMOOTHING
+                + sample / REWARD_RATE_SMOOTHING;
+        }
+        self.last_mine_at = now;
+    }
+}
+
+/// A managed proof's seeds, held on the stack: the type seed, miner, index
+/// and bump at most.
+#[derive(Clone, Copy, Default)]
+pub struct ManagedProofSeeds<'a> {
+    seeds: [&'a [u8]; 4],
+    len: usize,
+}
+
+impl<'a> ManagedProofSeeds<'a> {
+    fn push(&mut self, seed: &'a [u8]) {
+        self.seeds[self.len] = seed;
+        self.len += 1;
+    }
+
+    pub fn as_slice(&self) -> &[&'a [u8]] {
+        &self.seeds[..self.len]
+    }
 }
 
 impl Discriminator for ManagedProof {
@@ -21,9 +112,24 @@ impl Discriminator for ManagedProof {
     }
 }
 
+impl LayoutVersion for ManagedProof {
+    // 1: added mining statistics
+    // 2: added payer
+    fn layout_version() -> u8 {
+        2
+    }
+
+    fn upgrade_defaults(&mut self, from_version: u8) {
+        // proofs were always opened and paid for by the miner
+        if from_version < 2 {
+            self.payer = self.miner_authority;
+        }
+    }
+}
+
 impl_to_bytes!(ManagedProof);
 impl_account_from_bytes!(ManagedProof);
-impl_account_from_account_info!(ManagedProof);
+impl_program_account!(ManagedProof, MANAGED_PROOF);
 
 // DelegatedStake
 #[repr(C)]
@@ -32,6 +138,10 @@ pub struct DelegatedStake {
     pub bump: u8,
     _pad: [u8; 7],
     pub amount: u64,
+    /// Account that paid the rent and is refunded if the stake is closed.
+    /// Zero for accounts opened before the payer was recorded, whose payer is
+    /// unknown.
+    pub payer: Pubkey,
 }
 
 impl Discriminator for DelegatedStake {
@@ -40,9 +150,16 @@ impl Discriminator for DelegatedStake {
     }
 }
 
+impl LayoutVersion for DelegatedStake {
+    // 1: added payer
+    fn layout_version() -> u8 {
+        1
+    }
+}
+
 impl_to_bytes!(DelegatedStake);
 impl_account_from_bytes!(DelegatedStake);
-impl_account_from_account_info!(DelegatedStake);
+impl_program_account!(DelegatedStake, DELEGATED_STAKE);
 
 // DelegatedBoost
 #[repr(C)]
@@ -60,9 +177,15 @@ impl Discriminator for DelegatedBoost {
     }
 }
 
+impl LayoutVersion for DelegatedBoost {
+    fn layout_version() -> u8 {
+        0
+    }
+}
+
 impl_to_bytes!(DelegatedBoost);
 impl_account_from_bytes!(DelegatedBoost);
-impl_account_from_account_info!(DelegatedBoost);
+impl_program_account!(DelegatedBoost, DELEGATED_BOOST);
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, Pod, Zeroable)]
@@ -74,6 +197,31 @@ pub struct DelegatedBoostV2 {
     pub mint: Pubkey,
     pub amount: u64,
     pub fee_payer: Pubkey,
+    /// Unix timestamp until which the position cannot be undelegated.
+    pub locked_until: i64,
+    /// Extra reward weight earned while the lock is active, in basis points.
+    /// See `reward_weight_bps`.
+    pub lock_bonus_bps: u64,
+}
+
+impl DelegatedBoostV2 {
+    pub fn is_locked(&self, now: i64) -> bool {
+        now < self.locked_until
+    }
+
+    /// Reward weight of the position at `now`, in basis points.
+    ///
+    /// The weight is advisory: boost rewards accrue to the managed proof as a
+    /// whole and the program never scales a position by it. Miners that
+    /// honour lock bonuses pay them out of band, for example through a
+    /// distribution built with the same weights.
+    pub fn reward_weight_bps(&self, now: i64) -> u64 {
+        if self.is_locked(now) {
+            crate::consts::BASE_REWARD_WEIGHT_BPS.saturating_add(self.lock_bonus_bps)
+        } else {
+            crate::consts::BASE_REWARD_WEIGHT_BPS
+        }
+    }
 }
 
 impl Discriminator for DelegatedBoostV2 {
@@ -82,6 +230,246 @@ impl Discriminator for DelegatedBoostV2 {
     }
 }
 
+impl LayoutVersion for DelegatedBoostV2 {
+    // 1: added locked_until and lock_bonus_bps
+    fn layout_version() -> u8 {
+        1
+    }
+}
+
 impl_to_bytes!(DelegatedBoostV2);
 impl_account_from_bytes!(DelegatedBoostV2);
-impl_account_from_account_info!(DelegatedBoostV2);
+impl_program_account!(DelegatedBoostV2, DELEGATED_BOOST_V2);
+
+// ManagedProofSettings
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct ManagedProofSettings {
+    pub bump: u8,
+    pub allowlist_enabled: u8,
+    _pad: [u8; 6],
+    pub managed_proof: Pubkey,
+    pub allowlist_root: [u8; 32],
+    /// Smallest amount a boost delegation can be opened with.
+    pub min_delegation: u64,
+    /// Smallest balance a partial boost undelegation can leave behind. A
+    /// withdrawal that would leave less withdraws the full balance instead.
+    pub min_remaining_balance: u64,
+    /// Extra reward weight granted to boost delegations that lock, in basis
+    /// points. Advisory only, see `DelegatedBoostV2::reward_weight_bps`.
+    pub lock_bonus_bps: u64,
+    /// Shortest lock, in seconds, that earns the lock bonus.
+    pub min_lock_duration: i64,
+    /// Share of each boost deposit paid to the fee recipient, in basis points.
+    pub deposit_fee_bps: u64,
+    /// Owner of the token accounts deposit fees are paid to. Unlike the fee,
+    /// changes to it apply right away.
+    pub deposit_fee_recipient: Pubkey,
+    /// Deposit fee scheduled to replace `deposit_fee_bps`.
+    pub pending_deposit_fee_bps: u64,
+    /// When the pending deposit fee takes effect, 0 if none is scheduled.
+    pub pending_deposit_fee_at: i64,
+    /// Notice, in seconds, a fee increase is given before it takes effect.
+    pub fee_change_delay: i64,
+}
+
+impl ManagedProofSettings {
+    pub fn is_allowlist_enabled(&self) -> bool {
+        self.allowlist_enabled != 0
+    }
+
+    /// The deposit fee in effect at `now`, the pending fee once it is due.
+    pub fn deposit_fee_bps_at(&self, now: i64) -> u64 {
+        if self.pending_deposit_fee_at != 0 && now >= self.pending_deposit_fee_at {
+            self.pending_deposit_fee_bps
+        } else {
+            self.deposit_fee_bps
+        }
+    }
+
+    /// The fee taken from a boost deposit of `amount` at `now`, rounded down.
+    pub fn deposit_fee(&self, amount: u64, now: i64) -> u64 {
+        (amount as u128 * self.deposit_fee_bps_at(now) as u128 / 10_000) as u64
+    }
+
+    /// Change the deposit fee. A decrease applies right away and cancels any
+    /// pending change; an increase is scheduled `fee_change_delay` from `now`.
+    pub fn schedule_deposit_fee(&mut self, deposit_fee_bps: u64, now: i64) {
+        self.deposit_fee_bps = self.deposit_fee_bps_at(now);
+
+        if deposit_fee_bps <= self.deposit_fee_bps {
+            self.deposit_fee_bps = deposit_fee_bps;
+            self.pending_deposit_fee_bps = 0;
+            self.pending_deposit_fee_at = 0;
+        } else {
+            self.pending_deposit_fee_bps = deposit_fee_bps;
+            self.pending_deposit_fee_at = now.saturating_add(self.fee_change_delay);
+        }
+    }
+}
+
+impl Discriminator for ManagedProofSettings {
+    fn discriminator() -> AccountDiscriminator {
+        AccountDiscriminator::ManagedProofSettings
+    }
+}
+
+impl LayoutVersion for ManagedProofSettings {
+    fn layout_version() -> u8 {
+        2
+    }
+
+    fn upgrade_defaults(&mut self, from_version: u8) {
+        if from_version < 2 {
+            self.fee_change_delay = MIN_FEE_CHANGE_DELAY;
+        }
+    }
+}
+
+impl_to_bytes!(ManagedProofSettings);
+impl_account_from_bytes!(ManagedProofSettings);
+impl_program_account!(ManagedProofSettings, MANAGED_PROOF_SETTINGS);
+
+// StakerSession
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct StakerSession {
+    pub bump: u8,
+    _pad: [u8; 7],
+    pub staker: Pubkey,
+    /// Key allowed to delegate and undelegate the staker's boosts of `mint`.
+    pub operator: Pubkey,
+    pub mint: Pubkey,
+    /// Unix timestamp from which the session can no longer be used.
+    pub expires_at: i64,
+    /// Amount the operator can still delegate on the staker's behalf.
+    pub allowance: u64,
+    /// The only managed proof the operator can delegate to or undelegate
+    /// from, chosen by the staker at approval.
+    pub managed_proof: Pubkey,
+    /// Paid the session's rent and gets it back when the session is revoked.
+    pub payer: Pubkey,
+}
+
+impl StakerSession {
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+impl Discriminator for StakerSession {
+    fn discriminator() -> AccountDiscriminator {
+        AccountDiscriminator::StakerSession
+    }
+}
+
+impl LayoutVersion for StakerSession {
+    fn layout_version() -> u8 {
+        2
+    }
+
+    fn upgrade_defaults(&mut self, from_version: u8) {
+        // sessions from before the payer was recorded refunded the staker
+        if from_version < 2 {
+            self.payer = self.staker;
+        }
+    }
+}
+
+impl_to_bytes!(StakerSession);
+impl_account_from_bytes!(StakerSession);
+impl_program_account!(StakerSession, STAKER_SESSION);
+
+// Distribution
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct Distribution {
+    pub bump: u8,
+    _pad: [u8; 7],
+    pub managed_proof: Pubkey,
+    /// Chosen by the miner, so a managed proof can run several distributions.
+    pub id: u64,
+    /// Root of the tree of `merkle::distribution_leaf` allocations.
+    pub root: [u8; 32],
+    /// ORE set aside from the miner's stake for the allocations.
+    pub total: u64,
+    pub claimed: u64,
+    /// Number of allocations in the tree; leaf indexes are below this.
+    pub leaf_count: u32,
+    _count_pad: [u8; 4],
+    /// Account that paid the rent of the distribution and its claim bitmap.
+    pub payer: Pubkey,
+    /// Unix timestamp from which the miner can close the distribution and
+    /// take back what is left unclaimed.
+    pub expires_at: i64,
+}
+
+impl Distribution {
+    pub fn remaining(&self) -> u64 {
+        self.total.saturating_sub(self.claimed)
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        now >= self.expires_at
+    }
+}
+
+impl Discriminator for Distribution {
+    fn discriminator() -> AccountDiscriminator {
+        AccountDiscriminator::Distribution
+    }
+}
+
+impl LayoutVersion for Distribution {
+    fn layout_version() -> u8 {
+        1
+    }
+
+    fn upgrade_defaults(&mut self, from_version: u8) {
+        // distributions posted without an expiry stay claimable for good
+        if from_version < 1 {
+            self.expires_at = i64::MAX;
+        }
+    }
+}
+
+impl_to_bytes!(Distribution);
+impl_account_from_bytes!(Distribution);
+impl_program_account!(Distribution, DISTRIBUTION);
+
+// ClaimBitmap
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct ClaimBitmap {
+    pub bump: u8,
+    _pad: [u8; 7],
+    pub distribution: Pubkey,
+    /// One bit per allocation of the distribution, set once it is claimed.
+    pub bits: [u8; 1024],
+}
+
+impl ClaimBitmap {
+    pub fn is_claimed(&self, index: u32) -> bool {
+        self.bits[index as usize / 8] & (1 << (index % 8)) != 0
+    }
+
+    pub fn set_claimed(&mut self, index: u32) {
+        self.bits[index as usize / 8] |= 1 << (index % 8);
+    }
+}
+
+impl Discriminator for ClaimBitmap {
+    fn discriminator() -> AccountDiscriminator {
+        AccountDiscriminator::ClaimBitmap
+    }
+}
+
+impl LayoutVersion for ClaimBitmap {
+    fn layout_version() -> u8 {
+        0
+    }
+}
+
+impl_to_bytes!(ClaimBitmap);
+impl_account_from_bytes!(ClaimBitmap);
+impl_program_account!(ClaimBitmap, CLAIM_BITMAP);
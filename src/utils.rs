@@ -1,4 +1,7 @@
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use std::mem::size_of;
+
+use bytemuck::Pod;
 use solana_program::program_error::ProgramError;
 
 #[repr(u8)]
@@ -8,17 +11,76 @@ pub enum AccountDiscriminator {
     DelegatedStake = 101,
     DelegatedBoost = 102,
     DelegatedBoostV2 = 103,
+    ManagedProofSettings = 104,
+    StakerSession = 105,
+    Distribution = 106,
+    ClaimBitmap = 107,
 }
 
 pub trait Discriminator {
     fn discriminator() -> AccountDiscriminator;
 }
 
+/// Accounts only ever grow by appending fields, so data written with an older
+/// layout is a prefix of the current one. The layout version is stored in
+/// byte 1 of the 8 byte account header, after the discriminator.
+pub trait LayoutVersion {
+    fn layout_version() -> u8;
+
+    /// Set defaults for fields added after `from_version`. Fields added by an
+    /// upgrade start out zeroed, so this only needs to handle non-zero defaults.
+    fn upgrade_defaults(&mut self, _from_version: u8) {}
+}
+
+/// Write the discriminator and layout version of a new account.
+pub fn write_account_header<T: Discriminator + LayoutVersion>(data: &mut [u8]) {
+    data[0] = T::discriminator() as u8;
+    data[1] = T::layout_version();
+}
+
 pub trait AccountDeserializeV1 {
     fn try_from_bytes(data: &[u8]) -> Result<&Self, ProgramError>;
     fn try_from_bytes_mut(data: &mut [u8]) -> Result<&mut Self, ProgramError>;
 }
 
+/// An account stored at a PDA of this program. The PDA is derived from
+/// `seed()`, then the seeds given to `loaders::load`, then `bump()`.
+pub trait ProgramAccount: Pod + Discriminator + AccountDeserializeV1 {
+    fn seed() -> &'static [u8];
+    fn bump(&self) -> u8;
+}
+
+/// Split instruction data into the bytes of a fixed size args struct and any
+/// trailing bytes that follow it.
+pub fn split_instruction_data<T>(data: &[u8]) -> Result<(&[u8], &[u8]), ProgramError> {
+    if data.len() < size_of::<T>() {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok(data.split_at(size_of::<T>()))
+}
+
+/// Parse a merkle proof encoded as a one byte node count followed by the
+/// 32 byte nodes. Empty data is an empty proof. Returns the proof and any
+/// bytes that follow it.
+pub fn parse_merkle_proof(data: &[u8]) -> Result<(&[[u8; 32]], &[u8]), ProgramError> {
+    let Some((count, rest)) = data.split_first() else {
+        let empty: &[[u8; 32]] = &[];
+        return Ok((empty, data));
+    };
+
+    let len = (*count as usize) * 32;
+    if rest.len() < len {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let (nodes, rest) = rest.split_at(len);
+    let proof = bytemuck::try_cast_slice::<u8, [u8; 32]>(nodes)
+        .or(Err(ProgramError::InvalidInstructionData))?;
+
+    Ok((proof, rest))
+}
+
 #[macro_export]
 macro_rules! impl_to_bytes {
     ($struct_name:ident) => {
@@ -37,47 +99,47 @@ macro_rules! impl_account_from_bytes {
             fn try_from_bytes(
                 data: &[u8],
             ) -> Result<&Self, solana_program::program_error::ProgramError> {
-                if (Self::discriminator() as u8).ne(&data[0]) {
-                    return Err(solana_program::program_error::ProgramError::InvalidAccountData);
+                if data.first() != Some(&(Self::discriminator() as u8)) {
+                    solana_program::msg!("{:?}: wrong account discriminator", Self::discriminator());
+                    return Err(crate::error::OreDelegationError::InvalidAccountDiscriminator.into());
+                }
+                if data.len() < 8 + std::mem::size_of::<Self>() {
+                    solana_program::msg!("{:?}: account needs upgrade", Self::discriminator());
+                    return Err(crate::error::OreDelegationError::AccountNeedsUpgrade.into());
                 }
                 bytemuck::try_from_bytes::<Self>(&data[8..]).or(Err(
-                    solana_program::program_error::ProgramError::InvalidAccountData,
+                    crate::error::OreDelegationError::InvalidAccountLayout.into(),
                 ))
             }
             fn try_from_bytes_mut(
                 data: &mut [u8],
             ) -> Result<&mut Self, solana_program::program_error::ProgramError> {
-                if (Self::discriminator() as u8).ne(&data[0]) {
-                    return Err(solana_program::program_error::ProgramError::InvalidAccountData);
+                if data.first() != Some(&(Self::discriminator() as u8)) {
+                    solana_program::msg!("{:?}: wrong account discriminator", Self::discriminator());
+                    return Err(crate::error::OreDelegationError::InvalidAccountDiscriminator.into());
+                }
+                if data.len() < 8 + std::mem::size_of::<Self>() {
+                    solana_program::msg!("{:?}: account needs upgrade", Self::discriminator());
+                    return Err(crate::error::OreDelegationError::AccountNeedsUpgrade.into());
                 }
                 bytemuck::try_from_bytes_mut::<Self>(&mut data[8..]).or(Err(
-                    solana_program::program_error::ProgramError::InvalidAccountData,
+                    crate::error::OreDelegationError::InvalidAccountLayout.into(),
                 ))
             }
         }
     };
 }
 
-// Load and verify the account is initialized and has the correct program id
 #[macro_export]
-macro_rules! impl_account_from_account_info {
-    ($struct_name:ident) => {
-        impl $struct_name {
-            pub fn from_account_info(account_info: &AccountInfo) -> Result<Self, ProgramError> {
-                if *account_info.owner != crate::id() {
-                    return Err(ProgramError::IncorrectProgramId);
-                }
-
-                if account_info.data_is_empty() {
-                    return Err(ProgramError::UninitializedAccount);
-                }
+macro_rules! impl_program_account {
+    ($struct_name:ident, $seed:expr) => {
+        impl crate::utils::ProgramAccount for $struct_name {
+            fn seed() -> &'static [u8] {
+                $seed
+            }
 
-                if let Ok(account) = account_info.data.try_borrow() {
-                    let parsed_data = $struct_name::try_from_bytes(&account)?;
-                    return Ok(parsed_data.clone());
-                } else {
-                    return Err(ProgramError::AccountBorrowFailed);
-                };
+            fn bump(&self) -> u8 {
+                self.bump
             }
         }
     };
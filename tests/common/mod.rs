@@ -0,0 +1,203 @@
+//! Helpers shared by the tests that run this program against the native mocks.
+
+#![allow(dead_code)]
+
+use ore_api::consts::{BUS_ADDRESSES, MINT_ADDRESS};
+use ore_miner_delegation::{
+    error::OreDelegationError,
+    pda::{
+        delegated_boost_v2_pda, delegated_stake_pda, managed_proof_pda, managed_proof_settings_pda,
+    },
+    state::{DelegatedBoostV2, DelegatedStake, ManagedProofSettings},
+    utils::AccountDeserializeV1,
+};
+use solana_program::{clock::Clock, program_pack::Pack, pubkey::Pubkey};
+use solana_program_test::{BanksClientError, ProgramTestContext};
+use solana_sdk::{
+    account::Account,
+    instruction::Instruction,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+
+use crate::mocks;
+
+pub const STAKER_ORE: u64 = 5_000_000_000;
+
+pub async fn setup() -> (ProgramTestContext, Keypair, Keypair) {
+    let miner = Keypair::new();
+    let staker = Keypair::new();
+
+    let mut program_test = mocks::program_test();
+    for wallet in [&miner, &staker] {
+        program_test.add_account(
+            wallet.pubkey(),
+            Account {
+                lamports: 1_000_000_000,
+                ..Account::default()
+            },
+        );
+    }
+    program_test.add_account(
+        get_associated_token_address(&staker.pubkey(), &MINT_ADDRESS),
+        mocks::token_account(staker.pubkey(), MINT_ADDRESS, STAKER_ORE),
+    );
+
+    let mut context = program_test.start_with_context().await;
+
+    // Start at the top of the hour, when every stake window is open.
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp -= clock.unix_timestamp % 3600;
+    context.set_sysvar::<Clock>(&clock);
+
+    (context, miner, staker)
+}
+
+pub async fn open_managed_proof(context: &mut ProgramTestContext, miner: &Keypair) {
+    process(
+        context,
+        &[
+            ore_miner_delegation::instruction::open_managed_proof(miner.pubkey(), miner.pubkey()),
+            ore_miner_delegation::instruction::init_delegate_stake(
+                miner.pubkey(),
+                miner.pubkey(),
+                miner.pubkey(),
+            ),
+        ],
+        miner,
+    )
+    .await
+    .expect("open managed proof should succeed");
+}
+
+pub async fn open_managed_proof_boost(context: &mut ProgramTestContext, miner: &Keypair) {
+    process(
+        context,
+        &[
+            ore_miner_delegation::instruction::open_managed_proof_boost(
+                miner.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+            ),
+            create_associated_token_account_idempotent(
+                &miner.pubkey(),
+                &managed_proof_pda(miner.pubkey()).0,
+                &MINT_ADDRESS,
+                &spl_token::id(),
+            ),
+        ],
+        miner,
+    )
+    .await
+    .expect("open managed proof boost should succeed");
+}
+
+pub async fn set_deposit_fee(
+    context: &mut ProgramTestContext,
+    miner: &Keypair,
+    deposit_fee_bps: u64,
+    fee_recipient: Pubkey,
+) {
+    open_managed_proof_boost(context, miner).await;
+    process(
+        context,
+        &[
+            create_associated_token_account_idempotent(
+                &miner.pubkey(),
+                &fee_recipient,
+                &MINT_ADDRESS,
+                &spl_token::id(),
+            ),
+            ore_miner_delegation::instruction::init_managed_proof_settings(
+                miner.pubkey(),
+                miner.pubkey(),
+            ),
+            ore_miner_delegation::instruction::update_deposit_fee(
+                miner.pubkey(),
+                deposit_fee_bps,
+                fee_recipient,
+                0,
+            ),
+        ],
+        miner,
+    )
+    .await
+    .expect("setting a deposit fee should succeed");
+}
+
+pub fn mine(miner: Pubkey) -> Instruction {
+    ore_miner_delegation::instruction::mine_with_boost(
+        miner,
+        BUS_ADDRESSES[0],
+        drillx::Solution::new([0; 16], [0; 8]),
+    )
+}
+
+pub async fn process(
+    context: &mut ProgramTestContext,
+    ixs: &[Instruction],
+    signer: &Keypair,
+) -> Result<(), BanksClientError> {
+    let blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .expect("should get latest blockhash");
+    context.last_blockhash = blockhash;
+    let tx = Transaction::new_signed_with_payer(ixs, Some(&signer.pubkey()), &[signer], blockhash);
+    context
+        .banks_client
+        .process_transaction(tx)
+        .await
+}
+
+pub async fn get_account(context: &mut ProgramTestContext, address: Pubkey) -> Account {
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .expect("account should exist")
+}
+
+pub async fn token_balance(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    let account = get_account(context, address).await;
+    spl_token::state::Account::unpack(&account.data).unwrap().amount
+}
+
+pub async fn delegated_stake(
+    context: &mut ProgramTestContext,
+    miner: Pubkey,
+    staker: Pubkey,
+) -> DelegatedStake {
+    let account = get_account(context, delegated_stake_pda(miner, staker).0).await;
+    *DelegatedStake::try_from_bytes(&account.data).unwrap()
+}
+
+pub async fn managed_proof_settings(context: &mut ProgramTestContext, miner: &Keypair) -> ManagedProofSettings {
+    let account = get_account(context, managed_proof_settings_pda(miner.pubkey()).0).await;
+    *ManagedProofSettings::try_from_bytes(&account.data).unwrap()
+}
+
+pub async fn delegated_boost(
+    context: &mut ProgramTestContext,
+    miner: &Keypair,
+    staker: &Keypair,
+) -> DelegatedBoostV2 {
+    let address = delegated_boost_v2_pda(miner.pubkey(), staker.pubkey(), MINT_ADDRESS).0;
+    let account = get_account(context, address).await;
+    *DelegatedBoostV2::try_from_bytes(&account.data).unwrap()
+}
+
+pub fn delegation_error(err: BanksClientError) -> Option<OreDelegationError> {
+    match err {
+        BanksClientError::TransactionError(TransactionError::InstructionError(_, err)) => {
+            OreDelegationError::from_instruction_error(&err)
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,600 @@
+//! Compute unit ceilings, one test per instruction.
+//!
+//! These run this program from its SBF build against the native mocks, so only
+//! this program's own work is metered. `cargo test-sbf` builds the program and
+//! turns on the `test-sbf` feature they are compiled under; CI runs them that
+//! way on every push:
+//!
+//! ```sh
+//! cargo test-sbf --test compute_units
+//! ```
+#![cfg(feature = "test-sbf")]
+
+mod mocks;
+
+use ore_api::consts::{BUS_ADDRESSES, MINT_ADDRESS};
+use ore_miner_delegation::{
+    instruction::{self, MineBatchEntry},
+    merkle::{distribution_leaf, MerkleTree},
+    pda::managed_proof_pda,
+};
+use solana_program::{clock::Clock, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::Account, instruction::Instruction, signature::Keypair, signer::Signer,
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+
+// Upper bounds; lower them as the instructions get cheaper.
+const OPEN_MANAGED_PROOF_CU: u64 = 30_000;
+const INIT_DELEGATE_STAKE_CU: u64 = 20_000;
+const MINE_CU: u64 = 12_000;
+const MINE_BATCH_CU: u64 = 15_000;
+const UNDELEGATE_STAKE_CU: u64 = 12_000;
+const UPDATE_MINER_AUTHORITY_CU: u64 = 8_000;
+const OPEN_MANAGED_PROOF_BOOST_CU: u64 = 10_000;
+const INIT_DELEGATE_BOOST_V2_CU: u64 = 25_000;
+const DELEGATE_BOOST_V2_CU: u64 = 20_000;
+const UNDELEGATE_BOOST_V2_CU: u64 = 20_000;
+const CLOSE_DELEGATE_BOOST_V2_CU: u64 = 8_000;
+const EXIT_DELEGATE_BOOST_V2_CU: u64 = 25_000;
+const APPROVE_STAKER_SESSION_CU: u64 = 25_000;
+const DELEGATE_BOOST_V2_WITH_SESSION_CU: u64 = 25_000;
+const UNDELEGATE_BOOST_V2_WITH_SESSION_CU: u64 = 25_000;
+const REVOKE_STAKER_SESSION_CU: u64 = 10_000;
+const POST_DISTRIBUTION_CU: u64 = 30_000;
+const CLAIM_DISTRIBUTION_CU: u64 = 25_000;
+const CLOSE_DISTRIBUTION_CU: u64 = 12_000;
+const REGISTER_GLOBAL_BOOST_CU: u64 = 8_000;
+const ROTATE_GLOBAL_BOOST_CU: u64 = 8_000;
+
+#[tokio::test]
+pub async fn test_open_managed_proof_compute_units() {
+    let mut bench = Bench::new().await;
+    let miner = bench.miner.pubkey();
+    bench
+        .assert_budget(
+            "open_managed_proof",
+            instruction::open_managed_proof(miner, miner),
+            OPEN_MANAGED_PROOF_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_init_delegate_stake_compute_units() {
+    let mut bench = Bench::new().await;
+    let miner = bench.miner.pubkey();
+    bench.send_as_miner(instruction::open_managed_proof(miner, miner)).await;
+    bench
+        .assert_budget(
+            "init_delegate_stake",
+            instruction::init_delegate_stake(miner, miner, miner),
+            INIT_DELEGATE_STAKE_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_mine_compute_units() {
+    let mut bench = Bench::new().await;
+    let miner = bench.miner.pubkey();
+    bench.open_managed_proof().await;
+    bench
+        .assert_budget(
+            "mine",
+            instruction::mine_with_boost(miner, BUS_ADDRESSES[0], solution()),
+            MINE_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_mine_batch_compute_units() {
+    let mut bench = Bench::new().await;
+    bench.open_managed_proof().await;
+    let entries = [MineBatchEntry {
+        miner: bench.miner.pubkey().into(),
+        bus: BUS_ADDRESSES[0],
+        solution: solution(),
+    }];
+    bench
+        .assert_budget(
+            "mine_batch",
+            instruction::mine_batch_with_boost(&entries),
+            MINE_BATCH_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_undelegate_stake_compute_units() {
+    let mut bench = Bench::new().await;
+    let miner = bench.miner.pubkey();
+    bench.open_managed_proof().await;
+    bench
+        .send_as_miner(instruction::mine_with_boost(miner, BUS_ADDRESSES[0], solution()))
+        .await;
+    bench
+        .send_as_miner(create_associated_token_account_idempotent(
+            &miner,
+            &miner,
+            &MINT_ADDRESS,
+            &spl_token::id(),
+        ))
+        .await;
+    let miner_tokens = get_associated_token_address(&miner, &MINT_ADDRESS);
+    bench
+        .assert_budget(
+            "undelegate_stake",
+            instruction::undelegate_stake(miner, miner, miner_tokens, 1),
+            UNDELEGATE_STAKE_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_update_miner_authority_compute_units() {
+    let mut bench = Bench::new().await;
+    let miner = bench.miner.pubkey();
+    bench.open_managed_proof().await;
+    bench
+        .assert_budget(
+            "update_miner_authority",
+            instruction::update_miner_authority(miner, Pubkey::new_unique()),
+            UPDATE_MINER_AUTHORITY_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_open_managed_proof_boost_compute_units() {
+    let mut bench = Bench::new().await;
+    let miner = bench.miner.pubkey();
+    bench.open_managed_proof().await;
+    bench
+        .assert_budget(
+            "open_managed_proof_boost",
+            instruction::open_managed_proof_boost(miner, miner, MINT_ADDRESS),
+            OPEN_MANAGED_PROOF_BOOST_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_init_delegate_boost_v2_compute_units() {
+    let mut bench = Bench::new().await;
+    let (staker, miner) = (bench.staker.pubkey(), bench.miner.pubkey());
+    bench.open_managed_proof().await;
+    bench.open_managed_proof_boost().await;
+    bench
+        .assert_budget_as_staker(
+            "init_delegate_boost_v2",
+            instruction::init_delegate_boost_v2(staker, miner, staker, MINT_ADDRESS),
+            INIT_DELEGATE_BOOST_V2_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_delegate_boost_v2_compute_units() {
+    let mut bench = Bench::new().await;
+    let (staker, miner) = (bench.staker.pubkey(), bench.miner.pubkey());
+    bench.open_delegate_boost_v2().await;
+    bench
+        .assert_budget_as_staker(
+            "delegate_boost_v2",
+            instruction::delegate_boost_v2(staker, miner, MINT_ADDRESS, 1_000, None),
+            DELEGATE_BOOST_V2_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_undelegate_boost_v2_compute_units() {
+    let mut bench = Bench::new().await;
+    let (staker, miner) = (bench.staker.pubkey(), bench.miner.pubkey());
+    bench.open_delegate_boost_v2().await;
+    bench
+        .send_as_staker(instruction::delegate_boost_v2(staker, miner, MINT_ADDRESS, 1_000, None))
+        .await;
+    bench
+        .assert_budget_as_staker(
+            "undelegate_boost_v2",
+            instruction::undelegate_boost_v2(staker, miner, MINT_ADDRESS, 1_000),
+            UNDELEGATE_BOOST_V2_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_close_delegate_boost_v2_compute_units() {
+    let mut bench = Bench::new().await;
+    let (staker, miner) = (bench.staker.pubkey(), bench.miner.pubkey());
+    bench.open_delegate_boost_v2().await;
+    bench
+        .assert_budget_as_staker(
+            "close_delegate_boost_v2",
+            instruction::close_delegate_boost_v2(staker, miner, staker, MINT_ADDRESS),
+            CLOSE_DELEGATE_BOOST_V2_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_exit_delegate_boost_v2_compute_units() {
+    let mut bench = Bench::new().await;
+    let (staker, miner) = (bench.staker.pubkey(), bench.miner.pubkey());
+    bench.open_delegate_boost_v2().await;
+    bench
+        .send_as_staker(instruction::delegate_boost_v2(staker, miner, MINT_ADDRESS, 1_000, None))
+        .await;
+    bench
+        .assert_budget_as_staker(
+            "exit_delegate_boost_v2",
+            instruction::exit_delegate_boost_v2(staker, miner, staker, MINT_ADDRESS),
+            EXIT_DELEGATE_BOOST_V2_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_approve_staker_session_compute_units() {
+    let mut bench = Bench::new().await;
+    bench.open_managed_proof().await;
+    let ix = bench.approve_staker_session();
+    bench
+        .assert_budget_as_staker("approve_staker_session", ix, APPROVE_STAKER_SESSION_CU)
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_delegate_boost_v2_with_session_compute_units() {
+    let mut bench = Bench::new().await;
+    let (staker, miner) = (bench.staker.pubkey(), bench.miner.pubkey());
+    bench.open_delegate_boost_v2().await;
+    let approve = bench.approve_staker_session();
+    bench.send_as_staker(approve).await;
+    let ix = instruction::delegate_boost_v2_with_session(
+        bench.operator.pubkey(),
+        staker,
+        miner,
+        MINT_ADDRESS,
+        1_000,
+    );
+    bench
+        .assert_budget_as_operator(
+            "delegate_boost_v2_with_session",
+            ix,
+            DELEGATE_BOOST_V2_WITH_SESSION_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_undelegate_boost_v2_with_session_compute_units() {
+    let mut bench = Bench::new().await;
+    let (staker, miner) = (bench.staker.pubkey(), bench.miner.pubkey());
+    bench.open_delegate_boost_v2().await;
+    let approve = bench.approve_staker_session();
+    bench.send_as_staker(approve).await;
+    bench
+        .send_as_staker(instruction::delegate_boost_v2(staker, miner, MINT_ADDRESS, 1_000, None))
+        .await;
+    let ix = instruction::undelegate_boost_v2_with_session(
+        bench.operator.pubkey(),
+        staker,
+        miner,
+        MINT_ADDRESS,
+        1_000,
+    );
+    bench
+        .assert_budget_as_operator(
+            "undelegate_boost_v2_with_session",
+            ix,
+            UNDELEGATE_BOOST_V2_WITH_SESSION_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_revoke_staker_session_compute_units() {
+    let mut bench = Bench::new().await;
+    let staker = bench.staker.pubkey();
+    bench.open_managed_proof().await;
+    let approve = bench.approve_staker_session();
+    bench.send_as_staker(approve).await;
+    let ix =
+        instruction::revoke_staker_session(staker, staker, bench.operator.pubkey(), MINT_ADDRESS);
+    bench
+        .assert_budget_as_staker("revoke_staker_session", ix, REVOKE_STAKER_SESSION_CU)
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_post_distribution_compute_units() {
+    let mut bench = Bench::new().await;
+    bench.open_managed_proof().await;
+    let ix = bench.post_distribution().await;
+    bench
+        .assert_budget("post_distribution", ix, POST_DISTRIBUTION_CU)
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_claim_distribution_compute_units() {
+    let mut bench = Bench::new().await;
+    let (staker, miner) = (bench.staker.pubkey(), bench.miner.pubkey());
+    bench.open_managed_proof().await;
+    let post = bench.post_distribution().await;
+    bench.send_as_miner(post).await;
+
+    let tree = distribution_tree(staker, miner);
+    let ix = instruction::claim_distribution(staker, miner, 1, 0, 600, &tree.proof(0).unwrap())
+        .unwrap();
+    bench
+        .assert_budget("claim_distribution", ix, CLAIM_DISTRIBUTION_CU)
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_close_distribution_compute_units() {
+    let mut bench = Bench::new().await;
+    let miner = bench.miner.pubkey();
+    bench.open_managed_proof().await;
+    let post = bench.post_distribution().await;
+    bench.send_as_miner(post).await;
+
+    let mut clock = bench.context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp += DISTRIBUTION_DURATION;
+    bench.context.set_sysvar::<Clock>(&clock);
+
+    bench
+        .assert_budget(
+            "close_distribution",
+            instruction::close_distribution(miner, miner, 1),
+            CLOSE_DISTRIBUTION_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_register_global_boost_compute_units() {
+    let mut bench = Bench::new().await;
+    let miner = bench.miner.pubkey();
+    bench.open_managed_proof().await;
+    bench
+        .assert_budget(
+            "register_global_boost",
+            instruction::register_global_boost(miner, miner),
+            REGISTER_GLOBAL_BOOST_CU,
+        )
+        .await;
+}
+
+#[tokio::test]
+pub async fn test_rotate_global_boost_compute_units() {
+    let mut bench = Bench::new().await;
+    let miner = bench.miner.pubkey();
+    bench.open_managed_proof().await;
+    bench
+        .send_as_miner(instruction::register_global_boost(miner, miner))
+        .await;
+    bench
+        .assert_budget(
+            "rotate_global_boost",
+            instruction::rotate_global_boost(miner),
+            ROTATE_GLOBAL_BOOST_CU,
+        )
+        .await;
+}
+
+/// How long the distributions posted here stay claimable.
+const DISTRIBUTION_DURATION: i64 = 3_600;
+
+fn solution() -> drillx::Solution {
+    drillx::Solution::new([0; 16], [0; 8])
+}
+
+/// Pays the staker 600 and the miner 400 of a 1_000 distribution.
+fn distribution_tree(staker: Pubkey, miner: Pubkey) -> MerkleTree {
+    MerkleTree::new(vec![
+        distribution_leaf(0, &staker, 600),
+        distribution_leaf(1, &miner, 400),
+    ])
+}
+
+/// A context running this program from its SBF build, with a funded miner,
+/// staker and operator, and the clock at the top of the hour.
+struct Bench {
+    context: ProgramTestContext,
+    miner: Keypair,
+    staker: Keypair,
+    operator: Keypair,
+}
+
+impl Bench {
+    async fn new() -> Self {
+        let miner = Keypair::new();
+        let staker = Keypair::new();
+        let operator = Keypair::new();
+
+        let mut program_test = mocks::sbf_program_test();
+        for wallet in [&miner, &staker, &operator] {
+            program_test.add_account(
+                wallet.pubkey(),
+                Account {
+                    lamports: 1_000_000_000,
+                    ..Account::default()
+                },
+            );
+        }
+        program_test.add_account(
+            get_associated_token_address(&staker.pubkey(), &MINT_ADDRESS),
+            mocks::token_account(staker.pubkey(), MINT_ADDRESS, 1_000_000_000),
+        );
+        let mut context = program_test.start_with_context().await;
+
+        // Start at the top of the hour, when every stake window is open.
+        let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+        clock.unix_timestamp -= clock.unix_timestamp % 3600;
+        context.set_sysvar::<Clock>(&clock);
+
+        Self {
+            context,
+            miner,
+            staker,
+            operator,
+        }
+    }
+
+    async fn open_managed_proof(&mut self) {
+        let miner = self.miner.pubkey();
+        self.send_as_miner(instruction::open_managed_proof(miner, miner))
+            .await;
+        self.send_as_miner(instruction::init_delegate_stake(miner, miner, miner))
+            .await;
+    }
+
+    /// Opens the managed proof's boost and an empty position of the staker.
+    async fn open_delegate_boost_v2(&mut self) {
+        let (staker, miner) = (self.staker.pubkey(), self.miner.pubkey());
+        self.open_managed_proof().await;
+        self.open_managed_proof_boost().await;
+        self.send_as_staker(instruction::init_delegate_boost_v2(
+            staker,
+            miner,
+            staker,
+            MINT_ADDRESS,
+        ))
+        .await;
+    }
+
+    async fn open_managed_proof_boost(&mut self) {
+        let miner = self.miner.pubkey();
+        self.send_as_miner(instruction::open_managed_proof_boost(miner, miner, MINT_ADDRESS))
+            .await;
+        self.send_as_miner(create_associated_token_account_idempotent(
+            &miner,
+            &managed_proof_pda(miner).0,
+            &MINT_ADDRESS,
+            &spl_token::id(),
+        ))
+        .await;
+    }
+
+    fn approve_staker_session(&self) -> Instruction {
+        let staker = self.staker.pubkey();
+        instruction::approve_staker_session(
+            staker,
+            staker,
+            self.operator.pubkey(),
+            self.miner.pubkey(),
+            MINT_ADDRESS,
+            i64::MAX,
+            1_000_000_000,
+        )
+    }
+
+    /// Mines once so the miner's stake can fund it, and returns the
+    /// instruction posting [`distribution_tree`] as distribution 1.
+    async fn post_distribution(&mut self) -> Instruction {
+        let (staker, miner) = (self.staker.pubkey(), self.miner.pubkey());
+        self.send_as_miner(instruction::mine_with_boost(miner, BUS_ADDRESSES[0], solution()))
+            .await;
+
+        let now = self
+            .context
+            .banks_client
+            .get_sysvar::<Clock>()
+            .await
+            .unwrap()
+            .unix_timestamp;
+        instruction::post_distribution(
+            miner,
+            miner,
+            1,
+            distribution_tree(staker, miner).root(),
+            1_000,
+            2,
+            now + DISTRIBUTION_DURATION,
+        )
+    }
+
+    async fn assert_budget(&mut self, name: &str, ix: Instruction, budget: u64) {
+        assert_budget(&mut self.context, name, ix, &self.miner, budget).await;
+    }
+
+    async fn assert_budget_as_staker(&mut self, name: &str, ix: Instruction, budget: u64) {
+        assert_budget(&mut self.context, name, ix, &self.staker, budget).await;
+    }
+
+    async fn assert_budget_as_operator(&mut self, name: &str, ix: Instruction, budget: u64) {
+        assert_budget(&mut self.context, name, ix, &self.operator, budget).await;
+    }
+
+    async fn send_as_miner(&mut self, ix: Instruction) {
+        send(&mut self.context, ix, &self.miner).await;
+    }
+
+    async fn send_as_staker(&mut self, ix: Instruction) {
+        send(&mut self.context, ix, &self.staker).await;
+    }
+}
+
+/// Simulates `ix` to meter it against `budget`, then processes it.
+async fn assert_budget(
+    context: &mut ProgramTestContext,
+    name: &str,
+    ix: Instruction,
+    signer: &Keypair,
+    budget: u64,
+) {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix.clone()],
+        Some(&signer.pubkey()),
+        &[signer],
+        context.last_blockhash,
+    );
+    let simulation = context
+        .banks_client
+        .simulate_transaction(tx)
+        .await
+        .unwrap();
+    let units_consumed = simulation
+        .simulation_details
+        .expect("simulation should report details")
+        .units_consumed;
+    assert!(
+        matches!(simulation.result, Some(Ok(()))),
+        "{} failed: {:?}",
+        name,
+        simulation.result
+    );
+    assert!(
+        units_consumed <= budget,
+        "{} used {} compute units, budget is {}",
+        name,
+        units_consumed,
+        budget
+    );
+
+    send(context, ix, signer).await;
+}
+
+async fn send(context: &mut ProgramTestContext, ix: Instruction, signer: &Keypair) {
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&signer.pubkey()),
+        &[signer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .expect("process_transaction should be ok");
+}
@@ -0,0 +1,642 @@
+mod common;
+mod mocks;
+
+use common::{
+    delegated_boost, delegation_error, get_account, managed_proof_settings, open_managed_proof,
+    open_managed_proof_boost, process, setup, token_balance, STAKER_ORE,
+};
+use ore_api::consts::MINT_ADDRESS;
+use ore_boost_api::state::{boost_pda, stake_pda};
+use ore_miner_delegation::{
+    consts::BASE_REWARD_WEIGHT_BPS,
+    error::OreDelegationError,
+    merkle::{allowlist_leaf, MerkleTree},
+    pda::{delegated_boost_pda, delegated_boost_v2_pda, managed_proof_pda},
+    state::DelegatedBoost,
+    utils::AccountDeserializeV1,
+};
+use solana_program::{clock::Clock, program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::BanksClientError;
+use solana_sdk::{instruction::InstructionError, signer::Signer, transaction::TransactionError};
+use spl_associated_token_account::{
+    get_associated_token_address,
+    instruction::create_associated_token_account_idempotent,
+};
+
+#[tokio::test]
+pub async fn test_migrate_legacy_boost_delegation() {
+    let (mut context, miner, staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+    open_managed_proof_boost(&mut context, &miner).await;
+
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::init_delegate_boost(
+                staker.pubkey(),
+                miner.pubkey(),
+                staker.pubkey(),
+                MINT_ADDRESS,
+            ),
+            ore_miner_delegation::instruction::delegate_boost(
+                staker.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+                2_000_000_000,
+            ),
+            ore_miner_delegation::instruction::undelegate_boost(
+                staker.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+                500_000_000,
+            ),
+        ],
+        &staker,
+    )
+    .await
+    .expect("legacy delegation should succeed");
+
+    let legacy_address = delegated_boost_pda(miner.pubkey(), staker.pubkey(), MINT_ADDRESS).0;
+    let legacy = get_account(&mut context, legacy_address).await;
+    assert_eq!(DelegatedBoost::try_from_bytes(&legacy.data).unwrap().amount, 1_500_000_000);
+    let staker_tokens = get_associated_token_address(&staker.pubkey(), &MINT_ADDRESS);
+    assert_eq!(
+        token_balance(&mut context, staker_tokens).await,
+        STAKER_ORE - 1_500_000_000
+    );
+
+    let migrate = ore_miner_delegation::instruction::migrate_boost_to_v2(
+        staker.pubkey(),
+        miner.pubkey(),
+        MINT_ADDRESS,
+    );
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::init_delegate_boost_v2(
+                staker.pubkey(),
+                miner.pubkey(),
+                staker.pubkey(),
+                MINT_ADDRESS,
+            ),
+            migrate.clone(),
+        ],
+        &staker,
+    )
+    .await
+    .expect("migrate should succeed");
+
+    let legacy = get_account(&mut context, legacy_address).await;
+    assert_eq!(DelegatedBoost::try_from_bytes(&legacy.data).unwrap().amount, 0);
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 1_500_000_000);
+
+    // There is nothing left to migrate.
+    let err = process(&mut context, &[migrate], &staker).await.unwrap_err();
+    assert!(matches!(
+        err,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::InsufficientFunds
+        ))
+    ));
+
+    // The migrated position withdraws and closes like any other.
+    let close = ore_miner_delegation::instruction::close_delegate_boost_v2(
+        staker.pubkey(),
+        miner.pubkey(),
+        staker.pubkey(),
+        MINT_ADDRESS,
+    );
+    let err = process(&mut context, &[close.clone()], &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::CannotCloseAccountWithBalance));
+
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::undelegate_boost_v2(
+                staker.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+                1_500_000_000,
+            ),
+            close,
+        ],
+        &staker,
+    )
+    .await
+    .expect("undelegate and close should succeed");
+    assert_eq!(token_balance(&mut context, staker_tokens).await, STAKER_ORE);
+    let position = delegated_boost_v2_pda(miner.pubkey(), staker.pubkey(), MINT_ADDRESS).0;
+    assert!(context
+        .banks_client
+        .get_account(position)
+        .await
+        .unwrap()
+        .is_none());
+}
+
+#[tokio::test]
+pub async fn test_delegate_and_undelegate_boost_v2() {
+    let (mut context, miner, staker) = setup().await;
+    let managed_proof = managed_proof_pda(miner.pubkey()).0;
+    open_managed_proof(&mut context, &miner).await;
+
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::open_managed_proof_boost(
+                miner.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+            ),
+            create_associated_token_account_idempotent(
+                &miner.pubkey(),
+                &managed_proof,
+                &MINT_ADDRESS,
+                &spl_token::id(),
+            ),
+        ],
+        &miner,
+    )
+    .await
+    .expect("open managed proof boost should succeed");
+
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::init_delegate_boost_v2(
+                staker.pubkey(),
+                miner.pubkey(),
+                staker.pubkey(),
+                MINT_ADDRESS,
+            ),
+            ore_miner_delegation::instruction::delegate_boost_v2(
+                staker.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+                3_000_000_000,
+                None,
+            ),
+        ],
+        &staker,
+    )
+    .await
+    .expect("delegate boost should succeed");
+
+    let staker_tokens = get_associated_token_address(&staker.pubkey(), &MINT_ADDRESS);
+    let boost_address = boost_pda(MINT_ADDRESS).0;
+    let stake_address = stake_pda(managed_proof, boost_address).0;
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 3_000_000_000);
+    assert_eq!(
+        mocks::read_stake(&get_account(&mut context, stake_address).await.data).balance,
+        3_000_000_000
+    );
+    assert_eq!(
+        token_balance(&mut context, staker_tokens).await,
+        STAKER_ORE - 3_000_000_000
+    );
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::undelegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+        )],
+        &staker,
+    )
+    .await
+    .expect("undelegate boost should succeed");
+
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 2_000_000_000);
+    assert_eq!(
+        mocks::read_stake(&get_account(&mut context, stake_address).await.data).balance,
+        2_000_000_000
+    );
+    assert_eq!(
+        token_balance(&mut context, staker_tokens).await,
+        STAKER_ORE - 2_000_000_000
+    );
+
+    // A failed boost withdrawal must not debit the position.
+    mocks::fail_next(managed_proof, ProgramError::Custom(7));
+    assert!(process(
+        &mut context,
+        &[ore_miner_delegation::instruction::undelegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+        )],
+        &staker,
+    )
+    .await
+    .is_err());
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 2_000_000_000);
+}
+
+#[tokio::test]
+pub async fn test_delegate_boost_v2_allowlist() {
+    let (mut context, miner, staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+    open_managed_proof_boost(&mut context, &miner).await;
+
+    let tree = MerkleTree::new(vec![
+        allowlist_leaf(&Pubkey::new_unique()),
+        allowlist_leaf(&staker.pubkey()),
+    ]);
+    let proof = tree.proof(1).unwrap();
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::init_managed_proof_settings(
+                miner.pubkey(),
+                miner.pubkey(),
+            ),
+            ore_miner_delegation::instruction::update_allowlist(miner.pubkey(), Some(tree.root())),
+        ],
+        &miner,
+    )
+    .await
+    .expect("enabling the allowlist should succeed");
+
+    // Without a proof the staker is turned away.
+    let err = process(
+        &mut context,
+        &[ore_miner_delegation::instruction::init_delegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            staker.pubkey(),
+            MINT_ADDRESS,
+        )],
+        &staker,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::StakerNotAllowlisted));
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::init_delegate_boost_v2_with_proof(
+            staker.pubkey(),
+            miner.pubkey(),
+            staker.pubkey(),
+            MINT_ADDRESS,
+            &proof,
+        )
+        .unwrap()],
+        &staker,
+    )
+    .await
+    .expect("init delegate boost with a proof should succeed");
+
+    let err = process(
+        &mut context,
+        &[ore_miner_delegation::instruction::delegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+            None,
+        )],
+        &staker,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::StakerNotAllowlisted));
+
+    // Another staker's proof doesn't verify for this one.
+    let err = process(
+        &mut context,
+        &[ore_miner_delegation::instruction::delegate_boost_v2_with_proof(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+            None,
+            &tree.proof(0).unwrap(),
+        )
+        .unwrap()],
+        &staker,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::StakerNotAllowlisted));
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::delegate_boost_v2_with_proof(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+            None,
+            &proof,
+        )
+        .unwrap()],
+        &staker,
+    )
+    .await
+    .expect("delegate boost with a proof should succeed");
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 1_000_000_000);
+
+    // Proofs too long for their one byte count can't be encoded.
+    assert_eq!(
+        ore_miner_delegation::instruction::delegate_boost_v2_with_proof(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+            None,
+            &[[0; 32]; 256],
+        )
+        .unwrap_err(),
+        ProgramError::InvalidInstructionData
+    );
+}
+
+#[tokio::test]
+pub async fn test_delegation_limits() {
+    let (mut context, miner, staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+    open_managed_proof_boost(&mut context, &miner).await;
+
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::init_managed_proof_settings(
+                miner.pubkey(),
+                miner.pubkey(),
+            ),
+            ore_miner_delegation::instruction::update_delegation_limits(
+                miner.pubkey(),
+                1_000_000_000,
+                500_000_000,
+            ),
+        ],
+        &miner,
+    )
+    .await
+    .expect("updating the delegation limits should succeed");
+
+    let settings = managed_proof_settings(&mut context, &miner).await;
+    assert_eq!(settings.min_delegation, 1_000_000_000);
+    assert_eq!(settings.min_remaining_balance, 500_000_000);
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::init_delegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            staker.pubkey(),
+            MINT_ADDRESS,
+        )],
+        &staker,
+    )
+    .await
+    .expect("init delegate boost should succeed");
+
+    let delegate = |amount| {
+        ore_miner_delegation::instruction::delegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            amount,
+            None,
+        )
+    };
+
+    // A first delegation must reach the minimum.
+    let err = process(&mut context, &[delegate(999_999_999)], &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::DelegationBelowMinimum));
+    process(&mut context, &[delegate(1_000_000_000)], &staker)
+        .await
+        .expect("delegating the minimum should succeed");
+
+    // Top ups of an existing position can be any size.
+    process(&mut context, &[delegate(100)], &staker)
+        .await
+        .expect("topping up should succeed");
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 1_000_000_100);
+
+    // Leaving less than the minimum remaining balance withdraws everything.
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::undelegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            600_000_000,
+        )],
+        &staker,
+    )
+    .await
+    .expect("undelegate boost should succeed");
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 0);
+    assert_eq!(
+        token_balance(
+            &mut context,
+            get_associated_token_address(&staker.pubkey(), &MINT_ADDRESS)
+        )
+        .await,
+        STAKER_ORE
+    );
+}
+
+#[tokio::test]
+pub async fn test_lock_delegate_boost_v2() {
+    let (mut context, miner, staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+    open_managed_proof_boost(&mut context, &miner).await;
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::init_managed_proof_settings(
+            miner.pubkey(),
+            miner.pubkey(),
+        )],
+        &miner,
+    )
+    .await
+    .expect("init managed proof settings should succeed");
+
+    // Negative lock durations are rejected.
+    let err = process(
+        &mut context,
+        &[ore_miner_delegation::instruction::update_lock_terms(miner.pubkey(), 2_500, -1)],
+        &miner,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::InvalidInstructionData
+        ))
+    ));
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::update_lock_terms(miner.pubkey(), 2_500, 3_600)],
+        &miner,
+    )
+    .await
+    .expect("update lock terms should succeed");
+    let settings = managed_proof_settings(&mut context, &miner).await;
+    assert_eq!(settings.lock_bonus_bps, 2_500);
+    assert_eq!(settings.min_lock_duration, 3_600);
+
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::init_delegate_boost_v2(
+                staker.pubkey(),
+                miner.pubkey(),
+                staker.pubkey(),
+                MINT_ADDRESS,
+            ),
+            ore_miner_delegation::instruction::delegate_boost_v2(
+                staker.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+                1_000_000_000,
+                None,
+            ),
+        ],
+        &staker,
+    )
+    .await
+    .expect("delegate boost should succeed");
+
+    let now = context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    let lock = |locked_until| {
+        ore_miner_delegation::instruction::lock_delegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            locked_until,
+        )
+    };
+
+    // Locks shorter than the minimum duration don't qualify.
+    let err = process(&mut context, &[lock(now + 3_599)], &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InvalidLockDuration));
+
+    process(&mut context, &[lock(now + 7_200)], &staker)
+        .await
+        .expect("lock should succeed");
+    let position = delegated_boost(&mut context, &miner, &staker).await;
+    assert_eq!(position.locked_until, now + 7_200);
+    assert_eq!(position.lock_bonus_bps, 2_500);
+    assert_eq!(position.reward_weight_bps(now), BASE_REWARD_WEIGHT_BPS + 2_500);
+    assert_eq!(position.reward_weight_bps(now + 7_200), BASE_REWARD_WEIGHT_BPS);
+
+    // Locks can only be extended.
+    let err = process(&mut context, &[lock(now + 3_600)], &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InvalidLockDuration));
+
+    // Neither a partial nor a full withdrawal gets past the lock.
+    let err = process(
+        &mut context,
+        &[ore_miner_delegation::instruction::undelegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1,
+        )],
+        &staker,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::DelegationLocked));
+
+    let exit = ore_miner_delegation::instruction::exit_delegate_boost_v2(
+        staker.pubkey(),
+        miner.pubkey(),
+        staker.pubkey(),
+        MINT_ADDRESS,
+    );
+    let err = process(&mut context, &[exit.clone()], &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::DelegationLocked));
+
+    // Once the lock runs out the position can leave.
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp = now + 7_200;
+    context.set_sysvar::<Clock>(&clock);
+    process(&mut context, &[exit], &staker)
+        .await
+        .expect("exit after the lock should succeed");
+}
+
+#[tokio::test]
+pub async fn test_exit_delegate_boost_v2() {
+    let (mut context, miner, staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+    open_managed_proof_boost(&mut context, &miner).await;
+
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::init_delegate_boost_v2(
+                staker.pubkey(),
+                miner.pubkey(),
+                staker.pubkey(),
+                MINT_ADDRESS,
+            ),
+            ore_miner_delegation::instruction::delegate_boost_v2(
+                staker.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+                1_234_567_890,
+                None,
+            ),
+        ],
+        &staker,
+    )
+    .await
+    .expect("delegate boost should succeed");
+
+    // The position must be closed by the fee payer that opened it.
+    let exit = |payer| {
+        ore_miner_delegation::instruction::exit_delegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            payer,
+            MINT_ADDRESS,
+        )
+    };
+    assert!(process(&mut context, &[exit(miner.pubkey())], &staker)
+        .await
+        .is_err());
+
+    process(&mut context, &[exit(staker.pubkey())], &staker)
+        .await
+        .expect("exit should succeed");
+
+    let position = delegated_boost_v2_pda(miner.pubkey(), staker.pubkey(), MINT_ADDRESS).0;
+    assert!(context
+        .banks_client
+        .get_account(position)
+        .await
+        .unwrap()
+        .is_none());
+    let staker_tokens = get_associated_token_address(&staker.pubkey(), &MINT_ADDRESS);
+    assert_eq!(token_balance(&mut context, staker_tokens).await, STAKER_ORE);
+    let stake_address = stake_pda(managed_proof_pda(miner.pubkey()).0, boost_pda(MINT_ADDRESS).0).0;
+    assert_eq!(
+        mocks::read_stake(&get_account(&mut context, stake_address).await.data).balance,
+        0
+    );
+}
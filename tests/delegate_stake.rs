@@ -0,0 +1,70 @@
+mod common;
+mod mocks;
+
+use common::{
+    delegated_stake, delegation_error, mine, open_managed_proof, process, setup, token_balance,
+};
+use ore_api::consts::MINT_ADDRESS;
+use ore_miner_delegation::{error::OreDelegationError, pda::managed_proof_pda};
+use solana_sdk::signer::Signer;
+use spl_associated_token_account::{
+    get_associated_token_address,
+    instruction::create_associated_token_account_idempotent,
+};
+
+#[tokio::test]
+pub async fn test_undelegate_stake_claims_rewards() {
+    let (mut context, miner, _staker) = setup().await;
+    let managed_proof = managed_proof_pda(miner.pubkey()).0;
+    open_managed_proof(&mut context, &miner).await;
+
+    mocks::set_mine_reward(managed_proof, 10_000);
+    process(&mut context, &[mine(miner.pubkey())], &miner)
+        .await
+        .expect("mine should succeed");
+
+    let beneficiary = get_associated_token_address(&miner.pubkey(), &MINT_ADDRESS);
+    process(
+        &mut context,
+        &[
+            create_associated_token_account_idempotent(
+                &miner.pubkey(),
+                &miner.pubkey(),
+                &MINT_ADDRESS,
+                &spl_token::id(),
+            ),
+            ore_miner_delegation::instruction::undelegate_stake(
+                miner.pubkey(),
+                miner.pubkey(),
+                beneficiary,
+                4_000,
+            ),
+        ],
+        &miner,
+    )
+    .await
+    .expect("undelegate stake should succeed");
+
+    let delegated_stake = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(delegated_stake.amount, 6_000);
+    assert_eq!(token_balance(&mut context, beneficiary).await, 4_000);
+}
+
+#[tokio::test]
+pub async fn test_delegate_stake_is_removed() {
+    let (mut context, miner, _staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+
+    let err = process(
+        &mut context,
+        &[ore_miner_delegation::instruction::delegate_stake(
+            miner.pubkey(),
+            miner.pubkey(),
+            1,
+        )],
+        &miner,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InstructionRemoved));
+}
@@ -0,0 +1,340 @@
+mod common;
+mod mocks;
+
+use common::{
+    delegated_boost, delegation_error, get_account, managed_proof_settings, open_managed_proof,
+    process, set_deposit_fee, setup, token_balance, STAKER_ORE,
+};
+use ore_api::consts::MINT_ADDRESS;
+use ore_boost_api::state::{boost_pda, stake_pda};
+use ore_miner_delegation::{
+    consts::{MAX_DEPOSIT_FEE_BPS, MIN_FEE_CHANGE_DELAY},
+    error::OreDelegationError,
+    pda::managed_proof_pda,
+};
+use solana_program::{clock::Clock, pubkey::Pubkey};
+use solana_sdk::signer::Signer;
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+
+#[tokio::test]
+pub async fn test_delegate_boost_v2_deposit_fee() {
+    let (mut context, miner, staker) = setup().await;
+    let managed_proof = managed_proof_pda(miner.pubkey()).0;
+    let fee_recipient = Pubkey::new_unique();
+    open_managed_proof(&mut context, &miner).await;
+    set_deposit_fee(&mut context, &miner, 500, fee_recipient).await;
+
+    // The increase is only scheduled, with the default notice.
+    let now = context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    let settings = managed_proof_settings(&mut context, &miner).await;
+    assert_eq!(settings.deposit_fee_bps, 0);
+    assert_eq!(settings.pending_deposit_fee_bps, 500);
+    assert_eq!(settings.pending_deposit_fee_at, now + MIN_FEE_CHANGE_DELAY);
+
+    // Fees above the maximum and shorter notice are rejected.
+    for ix in [
+        ore_miner_delegation::instruction::update_deposit_fee(
+            miner.pubkey(),
+            MAX_DEPOSIT_FEE_BPS + 1,
+            fee_recipient,
+            0,
+        ),
+        ore_miner_delegation::instruction::update_deposit_fee(
+            miner.pubkey(),
+            500,
+            fee_recipient,
+            MIN_FEE_CHANGE_DELAY - 1,
+        ),
+    ] {
+        assert!(process(&mut context, &[ix], &miner).await.is_err());
+    }
+
+    // Skip ahead by whole stake windows until the fee applies.
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp += MIN_FEE_CHANGE_DELAY;
+    context.set_sysvar::<Clock>(&clock);
+
+    let init_ix = ore_miner_delegation::instruction::init_delegate_boost_v2(
+        staker.pubkey(),
+        miner.pubkey(),
+        staker.pubkey(),
+        MINT_ADDRESS,
+    );
+    process(&mut context, &[init_ix], &staker)
+        .await
+        .expect("init delegate boost should succeed");
+
+    // The fee account is required while the fee is on.
+    assert!(process(
+        &mut context,
+        &[ore_miner_delegation::instruction::delegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+            None,
+        )],
+        &staker,
+    )
+    .await
+    .is_err());
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::delegate_boost_v2_with_deposit_fee(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+            None,
+            fee_recipient,
+        )],
+        &staker,
+    )
+    .await
+    .expect("delegate boost with a deposit fee should succeed");
+
+    let stake_address = stake_pda(managed_proof, boost_pda(MINT_ADDRESS).0).0;
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 950_000_000);
+    assert_eq!(
+        mocks::read_stake(&get_account(&mut context, stake_address).await.data).balance,
+        950_000_000
+    );
+    assert_eq!(
+        token_balance(
+            &mut context,
+            get_associated_token_address(&fee_recipient, &MINT_ADDRESS)
+        )
+        .await,
+        50_000_000
+    );
+    assert_eq!(
+        token_balance(
+            &mut context,
+            get_associated_token_address(&staker.pubkey(), &MINT_ADDRESS)
+        )
+        .await,
+        STAKER_ORE - 1_000_000_000
+    );
+
+    // A decrease applies right away.
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::update_deposit_fee(
+            miner.pubkey(),
+            100,
+            fee_recipient,
+            0,
+        )],
+        &miner,
+    )
+    .await
+    .expect("lowering the deposit fee should succeed");
+    let settings = managed_proof_settings(&mut context, &miner).await;
+    assert_eq!(settings.deposit_fee_bps, 100);
+    assert_eq!(settings.pending_deposit_fee_at, 0);
+}
+
+#[tokio::test]
+pub async fn test_delegate_boost_v2_fee_guard() {
+    let (mut context, miner, staker) = setup().await;
+    let fee_recipient = Pubkey::new_unique();
+    open_managed_proof(&mut context, &miner).await;
+    set_deposit_fee(&mut context, &miner, 500, fee_recipient).await;
+
+    // Skip ahead by whole stake windows until the fee applies.
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp += MIN_FEE_CHANGE_DELAY;
+    context.set_sysvar::<Clock>(&clock);
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::init_delegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            staker.pubkey(),
+            MINT_ADDRESS,
+        )],
+        &staker,
+    )
+    .await
+    .expect("init delegate boost should succeed");
+
+    let delegate = |max_deposit_fee_bps| {
+        ore_miner_delegation::instruction::delegate_boost_v2_with_deposit_fee(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+            max_deposit_fee_bps,
+            fee_recipient,
+        )
+    };
+
+    // A fee above the limit the staker set aborts the deposit.
+    let err = process(&mut context, &[delegate(Some(400))], &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::DepositFeeAboveAccepted));
+
+    process(&mut context, &[delegate(Some(500))], &staker)
+        .await
+        .expect("delegate within the accepted fee should succeed");
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 950_000_000);
+
+    // Data holding only the amount, as sent before the limit, is still
+    // accepted, and without an allowlist the bytes after the args aren't read.
+    let mut amount_only = delegate(None);
+    amount_only.data.truncate(1 + 8);
+    let mut trailing = delegate(None);
+    trailing.data.push(u8::MAX);
+    for ix in [amount_only, trailing] {
+        process(&mut context, &[ix], &staker)
+            .await
+            .expect("delegate without a limit should succeed");
+    }
+    assert_eq!(
+        delegated_boost(&mut context, &miner, &staker).await.amount,
+        3 * 950_000_000
+    );
+}
+
+#[tokio::test]
+pub async fn test_deposit_fee_increase_applies_from_its_effective_time() {
+    let (mut context, miner, staker) = setup().await;
+    let fee_recipient = Pubkey::new_unique();
+    open_managed_proof(&mut context, &miner).await;
+    set_deposit_fee(&mut context, &miner, 500, fee_recipient).await;
+    let effective_at = managed_proof_settings(&mut context, &miner)
+        .await
+        .pending_deposit_fee_at;
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::init_delegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            staker.pubkey(),
+            MINT_ADDRESS,
+        )],
+        &staker,
+    )
+    .await
+    .expect("init delegate boost should succeed");
+
+    // Deposits pay the old fee until the increase takes effect, the new one
+    // from then on. Both times are at the same point of a stake window.
+    let fee_tokens = get_associated_token_address(&fee_recipient, &MINT_ADDRESS);
+    for (now, fee) in [(effective_at - 600, 0), (effective_at, 50_000_000)] {
+        let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+        clock.unix_timestamp = now;
+        context.set_sysvar::<Clock>(&clock);
+
+        let amount_before = delegated_boost(&mut context, &miner, &staker).await.amount;
+        let fees_before = token_balance(&mut context, fee_tokens).await;
+        process(
+            &mut context,
+            &[ore_miner_delegation::instruction::delegate_boost_v2_with_deposit_fee(
+                staker.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+                1_000_000_000,
+                None,
+                fee_recipient,
+            )],
+            &staker,
+        )
+        .await
+        .expect("delegate boost should succeed");
+
+        assert_eq!(
+            delegated_boost(&mut context, &miner, &staker).await.amount,
+            amount_before + 1_000_000_000 - fee
+        );
+        assert_eq!(token_balance(&mut context, fee_tokens).await, fees_before + fee);
+    }
+}
+
+#[tokio::test]
+pub async fn test_deposit_fee_recipient_changes_right_away() {
+    let (mut context, miner, staker) = setup().await;
+    let fee_recipient = Pubkey::new_unique();
+    let new_fee_recipient = Pubkey::new_unique();
+    open_managed_proof(&mut context, &miner).await;
+    set_deposit_fee(&mut context, &miner, 500, fee_recipient).await;
+
+    // Skip ahead by whole stake windows until the fee applies.
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp += MIN_FEE_CHANGE_DELAY;
+    context.set_sysvar::<Clock>(&clock);
+
+    process(
+        &mut context,
+        &[
+            create_associated_token_account_idempotent(
+                &miner.pubkey(),
+                &new_fee_recipient,
+                &MINT_ADDRESS,
+                &spl_token::id(),
+            ),
+            ore_miner_delegation::instruction::update_deposit_fee(
+                miner.pubkey(),
+                500,
+                new_fee_recipient,
+                0,
+            ),
+        ],
+        &miner,
+    )
+    .await
+    .expect("changing the fee recipient should succeed");
+
+    // The recipient doesn't change what a delegator pays, so it isn't
+    // scheduled like a fee increase.
+    let settings = managed_proof_settings(&mut context, &miner).await;
+    assert_eq!(settings.deposit_fee_recipient, new_fee_recipient);
+    assert_eq!(settings.deposit_fee_bps, 500);
+    assert_eq!(settings.pending_deposit_fee_at, 0);
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::init_delegate_boost_v2(
+            staker.pubkey(),
+            miner.pubkey(),
+            staker.pubkey(),
+            MINT_ADDRESS,
+        )],
+        &staker,
+    )
+    .await
+    .expect("init delegate boost should succeed");
+
+    let delegate = |fee_recipient| {
+        ore_miner_delegation::instruction::delegate_boost_v2_with_deposit_fee(
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+            Some(500),
+            fee_recipient,
+        )
+    };
+    let err = process(&mut context, &[delegate(fee_recipient)], &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InvalidDepositFeeAccount));
+
+    process(&mut context, &[delegate(new_fee_recipient)], &staker)
+        .await
+        .expect("delegate paying the new recipient should succeed");
+    assert_eq!(
+        token_balance(
+            &mut context,
+            get_associated_token_address(&new_fee_recipient, &MINT_ADDRESS)
+        )
+        .await,
+        50_000_000
+    );
+}
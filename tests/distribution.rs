@@ -0,0 +1,124 @@
+mod common;
+mod mocks;
+
+use common::{
+    delegated_stake, get_account, mine, open_managed_proof, process, setup, token_balance,
+    STAKER_ORE,
+};
+use ore_api::consts::MINT_ADDRESS;
+use ore_miner_delegation::{
+    error::OreDelegationError,
+    merkle::{distribution_leaf, MerkleTree},
+    pda::{claim_bitmap_pda, distribution_pda, managed_proof_pda},
+    state::Distribution,
+    utils::AccountDeserializeV1,
+};
+use solana_program::clock::Clock;
+use solana_program_test::BanksClientError;
+use solana_sdk::{signer::Signer, transaction::TransactionError};
+use spl_associated_token_account::get_associated_token_address;
+
+#[tokio::test]
+pub async fn test_distribution_claims() {
+    let (mut context, miner, staker) = setup().await;
+    let managed_proof = managed_proof_pda(miner.pubkey()).0;
+    open_managed_proof(&mut context, &miner).await;
+
+    mocks::set_mine_reward(managed_proof, 10_000);
+    process(&mut context, &[mine(miner.pubkey())], &miner)
+        .await
+        .expect("mine should succeed");
+
+    let allocations = [(staker.pubkey(), 3_000u64), (miner.pubkey(), 2_000)];
+    let tree = MerkleTree::new(
+        allocations
+            .iter()
+            .enumerate()
+            .map(|(i, (staker, amount))| distribution_leaf(i as u32, staker, *amount))
+            .collect(),
+    );
+    let now = context.banks_client.get_sysvar::<Clock>().await.unwrap().unix_timestamp;
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::post_distribution(
+            miner.pubkey(),
+            miner.pubkey(),
+            1,
+            tree.root(),
+            5_000,
+            2,
+            now + 3_600,
+        )],
+        &miner,
+    )
+    .await
+    .expect("post distribution should succeed");
+
+    // the distribution is funded from the miner's stake
+    let delegated_stake = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(delegated_stake.amount, 5_000);
+
+    let claim = |index: u32, amount: u64| {
+        ore_miner_delegation::instruction::claim_distribution(
+            staker.pubkey(),
+            miner.pubkey(),
+            1,
+            index,
+            amount,
+            &tree.proof(index as usize).unwrap(),
+        )
+        .unwrap()
+    };
+    let claim_error = |err: BanksClientError| match err {
+        BanksClientError::TransactionError(TransactionError::InstructionError(_, err)) => {
+            OreDelegationError::from_instruction_error(&err)
+        }
+        _ => None,
+    };
+
+    // anyone can crank the claim, the ORE goes to the staker
+    let err = process(&mut context, &[claim(0, 3_001)], &miner).await.unwrap_err();
+    assert_eq!(claim_error(err), Some(OreDelegationError::InvalidDistributionProof));
+    process(&mut context, &[claim(0, 3_000)], &miner)
+        .await
+        .expect("claim should succeed");
+
+    let staker_tokens = get_associated_token_address(&staker.pubkey(), &MINT_ADDRESS);
+    assert_eq!(token_balance(&mut context, staker_tokens).await, STAKER_ORE + 3_000);
+
+    let err = process(&mut context, &[claim(0, 3_000)], &staker).await.unwrap_err();
+    assert_eq!(claim_error(err), Some(OreDelegationError::DistributionAlreadyClaimed));
+
+    let distribution = get_account(&mut context, distribution_pda(miner.pubkey(), 1).0).await;
+    let distribution = Distribution::try_from_bytes(&distribution.data).unwrap();
+    assert_eq!(distribution.claimed, 3_000);
+    assert_eq!(distribution.remaining(), 2_000);
+
+    // the miner takes back what is left unclaimed once the distribution expires
+    let close = ore_miner_delegation::instruction::close_distribution(miner.pubkey(), miner.pubkey(), 1);
+    let err = process(&mut context, &[close.clone()], &miner).await.unwrap_err();
+    assert_eq!(claim_error(err), Some(OreDelegationError::DistributionNotExpired));
+
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp = now + 3_600;
+    context.set_sysvar::<Clock>(&clock);
+
+    let mut wrong_payer = close.clone();
+    wrong_payer.accounts[1].pubkey = staker.pubkey();
+    let err = process(&mut context, &[wrong_payer], &miner).await.unwrap_err();
+    assert_eq!(claim_error(err), Some(OreDelegationError::CloseAccountFeePayerMissmatch));
+
+    process(&mut context, &[close], &miner)
+        .await
+        .expect("close distribution should succeed");
+
+    let delegated_stake = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(delegated_stake.amount, 7_000);
+    for address in [
+        distribution_pda(miner.pubkey(), 1).0,
+        claim_bitmap_pda(distribution_pda(miner.pubkey(), 1).0).0,
+    ] {
+        let account = context.banks_client.get_account(address).await.unwrap();
+        assert!(account.map_or(true, |account| account.lamports == 0));
+    }
+}
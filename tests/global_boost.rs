@@ -0,0 +1,67 @@
+use ore_api::state::proof_pda;
+use ore_miner_delegation::{
+    global_boost::{
+        reservation_status, Directory, Reservation, ReservationStatus, ROTATION_INTERVAL,
+    },
+    pda::managed_proof_pda,
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use steel::{AccountDeserialize, Discriminator};
+
+fn reservation_data(reservation: &Reservation) -> Vec<u8> {
+    let mut data = vec![0u8; 8];
+    data[0] = Reservation::discriminator();
+    data.extend_from_slice(bytemuck::bytes_of(reservation));
+    data
+}
+
+#[test]
+pub fn test_reservation_status() {
+    let miner = Pubkey::new_unique();
+    let boost = Pubkey::new_unique();
+    let mut reservation = Reservation {
+        authority: proof_pda(managed_proof_pda(miner).0).0,
+        boost: Pubkey::default(),
+        ts: 0,
+    };
+
+    // a fresh reservation can rotate right away
+    let status = reservation_status(miner, &reservation_data(&reservation)).unwrap();
+    assert_eq!(status.boost, None);
+    assert!(status.can_rotate(0));
+
+    reservation.boost = boost;
+    reservation.ts = 1_000;
+    let status = reservation_status(miner, &reservation_data(&reservation)).unwrap();
+    assert_eq!(
+        status,
+        ReservationStatus {
+            boost: Some(boost),
+            next_rotation_at: 1_000 + ROTATION_INTERVAL,
+        }
+    );
+    assert!(!status.can_rotate(1_000 + ROTATION_INTERVAL - 1));
+    assert!(status.can_rotate(1_000 + ROTATION_INTERVAL));
+
+    // another miner's reservation is rejected
+    assert_eq!(
+        reservation_status(Pubkey::new_unique(), &reservation_data(&reservation)),
+        Err(ProgramError::InvalidAccountData)
+    );
+}
+
+#[test]
+pub fn test_directory_active_boosts() {
+    let mut data = vec![0u8; 8 + std::mem::size_of::<Directory>()];
+    data[0] = Directory::discriminator();
+    let boosts = [Pubkey::new_unique(), Pubkey::new_unique()];
+    {
+        let directory = Directory::try_from_bytes_mut(&mut data).unwrap();
+        directory.boosts[..2].copy_from_slice(&boosts);
+        directory.len = 2;
+    }
+
+    let directory = Directory::try_from_bytes(&data).unwrap();
+    assert_eq!(directory.active_boosts(), &boosts);
+    assert!(Reservation::try_from_bytes(&data).is_err());
+}
@@ -0,0 +1,343 @@
+mod mocks;
+
+use ore_api::consts::{BUS_ADDRESSES, MINT_ADDRESS};
+use ore_boost_api::state::{boost_pda, stake_pda};
+use ore_miner_delegation::{
+    instruction,
+    pda::{delegated_boost_pda, delegated_boost_v2_pda, delegated_stake_pda, managed_proof_pda},
+    state::{DelegatedBoost, DelegatedBoostV2, DelegatedStake},
+    utils::AccountDeserializeV1,
+};
+use proptest::{collection::vec, prelude::*};
+use solana_program::{clock::Clock, pubkey::Pubkey};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::Account, compute_budget::ComputeBudgetInstruction, instruction::Instruction,
+    signature::Keypair, signer::Signer, transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use steel::AccountDeserialize as _;
+
+const MINERS: usize = 2;
+const STAKERS: usize = 3;
+const MINTS: usize = 2;
+const STAKER_BALANCE: u64 = 10_000_000_000;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Init { staker: usize, miner: usize, mint: usize },
+    Delegate { staker: usize, miner: usize, mint: usize, amount: u64 },
+    Undelegate { staker: usize, miner: usize, mint: usize, amount: u64 },
+    DelegateLegacy { staker: usize, miner: usize, mint: usize, amount: u64 },
+    Migrate { staker: usize, miner: usize, mint: usize },
+    Close { staker: usize, miner: usize, mint: usize },
+    Mine { miner: usize, reward: u64 },
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let position = (0..STAKERS, 0..MINERS, 0..MINTS);
+    let amount = 0..2_000_000_000u64;
+    prop_oneof![
+        position
+            .clone()
+            .prop_map(|(staker, miner, mint)| Op::Init { staker, miner, mint }),
+        (position.clone(), amount.clone()).prop_map(|((staker, miner, mint), amount)| {
+            Op::Delegate { staker, miner, mint, amount }
+        }),
+        (position.clone(), amount.clone()).prop_map(|((staker, miner, mint), amount)| {
+            Op::Undelegate { staker, miner, mint, amount }
+        }),
+        (position.clone(), amount).prop_map(|((staker, miner, mint), amount)| {
+            Op::DelegateLegacy { staker, miner, mint, amount }
+        }),
+        position
+            .clone()
+            .prop_map(|(staker, miner, mint)| Op::Migrate { staker, miner, mint }),
+        position.prop_map(|(staker, miner, mint)| Op::Close { staker, miner, mint }),
+        (0..MINERS, 0..1_000_000_000u64).prop_map(|(miner, reward)| Op::Mine { miner, reward }),
+    ]
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn test_delegation_accounting_invariants(ops in vec(op(), 1..24)) {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(run(ops));
+    }
+}
+
+struct World {
+    context: ProgramTestContext,
+    miners: Vec<Keypair>,
+    stakers: Vec<Keypair>,
+    mints: Vec<Pubkey>,
+    nonce: u32,
+}
+
+async fn run(ops: Vec<Op>) {
+    let mut world = World::new().await;
+    world.check_invariants().await;
+
+    // Every case opens one position, so it moves boost balances at least once.
+    let opening = [
+        Op::Init { staker: 0, miner: 0, mint: 0 },
+        Op::Delegate { staker: 0, miner: 0, mint: 0, amount: 1_000_000_000 },
+    ];
+    let mut balance_changes = 0;
+    for op in opening.iter().chain(&ops) {
+        // Individual steps may fail; the invariants must hold either way.
+        let applied = world.apply(op).await.is_ok();
+        if applied && matches!(op, Op::Delegate { .. } | Op::Undelegate { .. }) {
+            balance_changes += 1;
+        }
+        world.check_invariants().await;
+    }
+    assert!(balance_changes > 0, "no delegate or undelegate succeeded");
+}
+
+impl World {
+    async fn new() -> Self {
+        let miners: Vec<Keypair> = (0..MINERS).map(|_| Keypair::new()).collect();
+        let stakers: Vec<Keypair> = (0..STAKERS).map(|_| Keypair::new()).collect();
+        let mut mints = vec![MINT_ADDRESS];
+        mints.extend((1..MINTS).map(|_| Pubkey::new_unique()));
+
+        let mut program_test = mocks::program_test();
+        for mint in mints.iter().skip(1) {
+            mocks::add_mint(&mut program_test, *mint, Pubkey::new_unique(), u64::MAX);
+            mocks::add_boost(&mut program_test, *mint);
+        }
+        for wallet in miners.iter().chain(stakers.iter()) {
+            program_test.add_account(
+                wallet.pubkey(),
+                Account {
+                    lamports: 10_000_000_000,
+                    ..Account::default()
+                },
+            );
+        }
+        for staker in stakers.iter() {
+            for mint in mints.iter() {
+                program_test.add_account(
+                    get_associated_token_address(&staker.pubkey(), mint),
+                    mocks::token_account(staker.pubkey(), *mint, STAKER_BALANCE),
+                );
+            }
+        }
+
+        let mut world = Self {
+            context: program_test.start_with_context().await,
+            miners,
+            stakers,
+            mints,
+            nonce: 0,
+        };
+
+        // Start at the top of the hour, when every stake window is open.
+        let mut clock = world.context.banks_client.get_sysvar::<Clock>().await.unwrap();
+        clock.unix_timestamp -= clock.unix_timestamp % 3600;
+        world.context.set_sysvar::<Clock>(&clock);
+
+        for miner in 0..MINERS {
+            let miner_key = world.miners[miner].pubkey();
+            let managed_proof = managed_proof_pda(miner_key).0;
+            let mut ixs = vec![
+                instruction::open_managed_proof(miner_key, miner_key),
+                instruction::init_delegate_stake(miner_key, miner_key, miner_key),
+            ];
+            for mint in world.mints.clone() {
+                ixs.push(instruction::open_managed_proof_boost(miner_key, miner_key, mint));
+                ixs.push(create_associated_token_account_idempotent(
+                    &miner_key,
+                    &managed_proof,
+                    &mint,
+                    &spl_token::id(),
+                ));
+            }
+            world
+                .process(&ixs, miner, None)
+                .await
+                .expect("miner setup should succeed");
+        }
+
+        world
+    }
+
+    async fn apply(&mut self, op: &Op) -> Result<(), ()> {
+        match *op {
+            Op::Init { staker, miner, mint } => {
+                let (staker_key, miner_key, mint) = self.position(staker, miner, mint);
+                let ix = instruction::init_delegate_boost_v2(staker_key, miner_key, staker_key, mint);
+                self.process(&[ix], miner, Some(staker)).await
+            }
+            Op::Delegate { staker, miner, mint, amount } => {
+                let (staker_key, miner_key, mint) = self.position(staker, miner, mint);
+                let ix = instruction::delegate_boost_v2(staker_key, miner_key, mint, amount, None);
+                self.process(&[ix], miner, Some(staker)).await
+            }
+            Op::Undelegate { staker, miner, mint, amount } => {
+                let (staker_key, miner_key, mint) = self.position(staker, miner, mint);
+                let ix = instruction::undelegate_boost_v2(staker_key, miner_key, mint, amount);
+                self.process(&[ix], miner, Some(staker)).await
+            }
+            Op::DelegateLegacy { staker, miner, mint, amount } => {
+                let (staker_key, miner_key, mint) = self.position(staker, miner, mint);
+                let mut ixs = vec![];
+                if self
+                    .account(delegated_boost_pda(miner_key, staker_key, mint).0)
+                    .await
+                    .is_none()
+                {
+                    ixs.push(instruction::init_delegate_boost(
+                        staker_key, miner_key, staker_key, mint,
+                    ));
+                }
+                ixs.push(instruction::delegate_boost(staker_key, miner_key, mint, amount));
+                self.process(&ixs, miner, Some(staker)).await
+            }
+            Op::Migrate { staker, miner, mint } => {
+                let (staker_key, miner_key, mint) = self.position(staker, miner, mint);
+                let ix = instruction::migrate_boost_to_v2(staker_key, miner_key, mint);
+                self.process(&[ix], miner, Some(staker)).await
+            }
+            Op::Close { staker, miner, mint } => {
+                let (staker_key, miner_key, mint) = self.position(staker, miner, mint);
+                let ix = instruction::close_delegate_boost_v2(staker_key, miner_key, staker_key, mint);
+                self.process(&[ix], miner, Some(staker)).await
+            }
+            Op::Mine { miner, reward } => {
+                let miner_key = self.miners[miner].pubkey();
+                mocks::set_mine_reward(managed_proof_pda(miner_key).0, reward);
+                let ix = instruction::mine_with_boost(
+                    miner_key,
+                    BUS_ADDRESSES[0],
+                    drillx::Solution::new([0; 16], [0; 8]),
+                );
+                self.process(&[ix], miner, None).await
+            }
+        }
+    }
+
+    async fn check_invariants(&mut self) {
+        for miner in 0..MINERS {
+            let miner_key = self.miners[miner].pubkey();
+            let managed_proof = managed_proof_pda(miner_key).0;
+
+            // Every delegated boost is backed by the managed proof's boost stake.
+            for mint in self.mints.clone() {
+                let stake_address = stake_pda(managed_proof, boost_pda(mint).0).0;
+                let stake_balance = match self.account(stake_address).await {
+                    Some(account) => mocks::read_stake(&account.data).balance,
+                    None => 0,
+                };
+
+                let mut delegated = 0u128;
+                for staker in 0..STAKERS {
+                    let staker_key = self.stakers[staker].pubkey();
+                    let v2_address = delegated_boost_v2_pda(miner_key, staker_key, mint).0;
+                    if let Some(account) = self.account(v2_address).await {
+                        delegated += DelegatedBoostV2::try_from_bytes(&account.data)
+                            .unwrap()
+                            .amount as u128;
+                    }
+                    // Positions not yet migrated are backed by the same stake.
+                    let v1_address = delegated_boost_pda(miner_key, staker_key, mint).0;
+                    if let Some(account) = self.account(v1_address).await {
+                        delegated += DelegatedBoost::try_from_bytes(&account.data)
+                            .unwrap()
+                            .amount as u128;
+                    }
+                }
+                assert_eq!(
+                    delegated, stake_balance as u128,
+                    "delegated boosts for miner {} and mint {} don't match the boost stake",
+                    miner, mint
+                );
+            }
+
+            // The miner's delegated stake is always claimable from the proof.
+            let delegated_stake = self
+                .account(delegated_stake_pda(miner_key, miner_key).0)
+                .await
+                .map(|account| DelegatedStake::try_from_bytes(&account.data).unwrap().amount)
+                .unwrap_or(0);
+            let proof_balance = self
+                .account(ore_api::state::proof_pda(managed_proof).0)
+                .await
+                .map(|account| {
+                    ore_api::state::Proof::try_from_bytes(&account.data)
+                        .unwrap()
+                        .balance
+                })
+                .unwrap_or(0);
+            assert!(
+                delegated_stake <= proof_balance,
+                "miner {} has {} delegated stake but only {} in its proof",
+                miner,
+                delegated_stake,
+                proof_balance
+            );
+        }
+    }
+
+    fn position(&self, staker: usize, miner: usize, mint: usize) -> (Pubkey, Pubkey, Pubkey) {
+        (
+            self.stakers[staker].pubkey(),
+            self.miners[miner].pubkey(),
+            self.mints[mint],
+        )
+    }
+
+    /// Sends `ixs` paid for by the miner and signed by the staker when any
+    /// instruction requires it.
+    ///
+    /// Each transaction carries a distinct compute unit limit so repeated
+    /// operations within one blockhash aren't rejected as duplicates.
+    async fn process(
+        &mut self,
+        ixs: &[Instruction],
+        miner: usize,
+        staker: Option<usize>,
+    ) -> Result<(), ()> {
+        self.nonce += 1;
+        let mut all_ixs = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+            1_000_000 + self.nonce,
+        )];
+        all_ixs.extend_from_slice(ixs);
+
+        let payer = &self.miners[miner];
+        let mut signers = vec![payer];
+        if let Some(staker) = staker.map(|staker| &self.stakers[staker]) {
+            let must_sign = ixs
+                .iter()
+                .flat_map(|ix| ix.accounts.iter())
+                .any(|meta| meta.is_signer && meta.pubkey == staker.pubkey());
+            if must_sign {
+                signers.push(staker);
+            }
+        }
+        let tx = Transaction::new_signed_with_payer(
+            &all_ixs,
+            Some(&payer.pubkey()),
+            &signers,
+            self.context.last_blockhash,
+        );
+        self.context
+            .banks_client
+            .process_transaction(tx)
+            .await
+            .map_err(|_| ())
+    }
+
+    async fn account(&mut self, address: Pubkey) -> Option<Account> {
+        self.context.banks_client.get_account(address).await.unwrap()
+    }
+}
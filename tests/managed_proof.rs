@@ -0,0 +1,142 @@
+mod common;
+mod mocks;
+
+use common::{delegated_stake, get_account, open_managed_proof, process, setup};
+use ore_api::consts::{BUS_ADDRESSES, MINT_ADDRESS};
+use ore_miner_delegation::{
+    pda::{delegated_stake_pda, managed_proof_pda, managed_proof_settings_pda, ManagedProofId},
+    state::{DelegatedStake, ManagedProof},
+    utils::AccountDeserializeV1,
+};
+use solana_program::pubkey::Pubkey;
+use solana_sdk::{signature::Keypair, signer::Signer, transaction::Transaction};
+use steel::AccountDeserialize as _;
+
+#[tokio::test]
+pub async fn test_indexed_managed_proofs() {
+    let (mut context, miner, _staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+
+    let rigs = [
+        ManagedProofId::indexed(miner.pubkey(), 0),
+        ManagedProofId::indexed(miner.pubkey(), 1),
+    ];
+    for rig in rigs {
+        process(
+            &mut context,
+            &[
+                ore_miner_delegation::instruction::open_managed_proof(rig, miner.pubkey()),
+                ore_miner_delegation::instruction::init_delegate_stake(
+                    miner.pubkey(),
+                    rig,
+                    miner.pubkey(),
+                ),
+            ],
+            &miner,
+        )
+        .await
+        .expect("open indexed managed proof should succeed");
+    }
+
+    let managed_proof = managed_proof_pda(rigs[1]).0;
+    assert_ne!(managed_proof, managed_proof_pda(miner.pubkey()).0);
+    assert_ne!(managed_proof, managed_proof_pda(rigs[0]).0);
+
+    let account = get_account(&mut context, managed_proof).await;
+    let account = ManagedProof::try_from_bytes(&account.data).unwrap();
+    assert_eq!(account.index(), Some(1));
+    assert_eq!(account.miner_authority, miner.pubkey());
+    assert_eq!(account.settings_bump, managed_proof_settings_pda(rigs[1]).1);
+
+    mocks::set_mine_reward(managed_proof, 7_000);
+    let ix = ore_miner_delegation::instruction::mine(
+        rigs[1],
+        BUS_ADDRESSES[0],
+        drillx::Solution::new([0; 16], [0; 8]),
+    );
+    process(&mut context, &[ix], &miner)
+        .await
+        .expect("mine with an indexed managed proof should succeed");
+
+    // Only the indexed proof's stake is credited.
+    let stake = get_account(&mut context, delegated_stake_pda(rigs[1], miner.pubkey()).0).await;
+    assert_eq!(DelegatedStake::try_from_bytes(&stake.data).unwrap().amount, 7_000);
+    let stake = get_account(&mut context, delegated_stake_pda(rigs[0], miner.pubkey()).0).await;
+    assert_eq!(DelegatedStake::try_from_bytes(&stake.data).unwrap().amount, 0);
+    let original = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(original.amount, 0);
+
+    // The original proof still opens at its index-less address and is not indexed.
+    let original = get_account(&mut context, managed_proof_pda(miner.pubkey()).0).await;
+    let original = ManagedProof::try_from_bytes(&original.data).unwrap();
+    assert_eq!(original.index(), None);
+}
+
+#[tokio::test]
+pub async fn test_sponsor_pays_rent_for_new_miner() {
+    // `setup` funds both wallets; the second one sponsors a miner with none.
+    let (mut context, _miner, sponsor) = setup().await;
+    let miner = Keypair::new();
+    let managed_proof = managed_proof_pda(miner.pubkey()).0;
+
+    let blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[
+            ore_miner_delegation::instruction::open_managed_proof(miner.pubkey(), sponsor.pubkey()),
+            ore_miner_delegation::instruction::init_delegate_stake(
+                miner.pubkey(),
+                miner.pubkey(),
+                sponsor.pubkey(),
+            ),
+            ore_miner_delegation::instruction::open_managed_proof_boost(
+                miner.pubkey(),
+                sponsor.pubkey(),
+                MINT_ADDRESS,
+            ),
+        ],
+        Some(&sponsor.pubkey()),
+        &[&sponsor, &miner],
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .expect("sponsored onboarding should succeed");
+
+    let miner_account = context.banks_client.get_account(miner.pubkey()).await.unwrap();
+    assert!(miner_account.map_or(true, |account| account.lamports == 0));
+
+    let proof = get_account(&mut context, managed_proof).await;
+    let proof = ManagedProof::try_from_bytes(&proof.data).unwrap();
+    assert_eq!(proof.payer, sponsor.pubkey());
+    let delegated_stake = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(delegated_stake.payer, sponsor.pubkey());
+}
+
+#[tokio::test]
+pub async fn test_update_miner_authority() {
+    let (mut context, miner, _staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+
+    let new_authority = Pubkey::new_unique();
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::update_miner_authority(
+            miner.pubkey(),
+            new_authority,
+        )],
+        &miner,
+    )
+    .await
+    .expect("update miner authority should succeed");
+
+    let ore_proof = ore_api::state::proof_pda(managed_proof_pda(miner.pubkey()).0).0;
+    let ore_proof = get_account(&mut context, ore_proof).await;
+    let ore_proof = ore_api::state::Proof::try_from_bytes(&ore_proof.data).unwrap();
+    assert_eq!(ore_proof.miner, new_authority);
+}
@@ -0,0 +1,30 @@
+use ore_miner_delegation::merkle::{allowlist_leaf, verify, MerkleTree};
+use solana_program::pubkey::Pubkey;
+
+#[test]
+pub fn test_allowlist_proofs() {
+    let stakers: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+    let tree = MerkleTree::new(stakers.iter().map(allowlist_leaf).collect());
+    let root = tree.root();
+
+    for (i, staker) in stakers.iter().enumerate() {
+        let proof = tree.proof(i).unwrap();
+        assert!(verify(&proof, &root, allowlist_leaf(staker)));
+    }
+
+    // Unknown staker and proof for another leaf are rejected
+    let outsider = Pubkey::new_unique();
+    let proof = tree.proof(0).unwrap();
+    assert!(!verify(&proof, &root, allowlist_leaf(&outsider)));
+    assert!(!verify(&proof, &root, allowlist_leaf(&stakers[1])));
+    assert!(tree.proof(stakers.len()).is_none());
+}
+
+#[test]
+pub fn test_single_leaf_tree() {
+    let staker = Pubkey::new_unique();
+    let tree = MerkleTree::new(vec![allowlist_leaf(&staker)]);
+
+    assert_eq!(tree.root(), allowlist_leaf(&staker));
+    assert!(verify(&tree.proof(0).unwrap(), &tree.root(), allowlist_leaf(&staker)));
+}
@@ -0,0 +1,187 @@
+mod common;
+mod mocks;
+
+use common::{
+    delegated_stake, delegation_error, get_account, mine, open_managed_proof, process, setup,
+};
+use ore_api::consts::BUS_ADDRESSES;
+use ore_miner_delegation::{
+    error::OreDelegationError,
+    global_boost::{reservation_pda, GLOBAL_BOOST_ID},
+    instruction::MineBatchEntry,
+    pda::managed_proof_pda,
+    state::ManagedProof,
+    utils::AccountDeserializeV1,
+};
+use solana_program::{program_error::ProgramError, pubkey::Pubkey};
+use solana_program_test::BanksClientError;
+use solana_sdk::{
+    account::Account,
+    instruction::AccountMeta,
+    signature::Keypair,
+    signer::Signer,
+    transaction::{Transaction, TransactionError},
+};
+use steel::AccountDeserialize as _;
+
+#[tokio::test]
+pub async fn test_mine_credits_mock_reward() {
+    let (mut context, miner, _staker) = setup().await;
+    let managed_proof = managed_proof_pda(miner.pubkey()).0;
+    open_managed_proof(&mut context, &miner).await;
+
+    mocks::set_mine_reward(managed_proof, 42_000);
+    process(&mut context, &[mine(miner.pubkey())], &miner)
+        .await
+        .expect("mine should succeed");
+
+    let delegated_stake = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(delegated_stake.amount, 42_000);
+    assert_eq!(delegated_stake.payer, miner.pubkey());
+
+    let ore_proof = get_account(&mut context, ore_api::state::proof_pda(managed_proof).0).await;
+    let ore_proof = ore_api::state::Proof::try_from_bytes(&ore_proof.data).unwrap();
+    assert_eq!(ore_proof.balance, 42_000);
+
+    let stats = get_account(&mut context, managed_proof).await;
+    let stats = ManagedProof::try_from_bytes(&stats.data).unwrap();
+    assert_eq!(stats.total_submissions, 1);
+    assert_eq!(stats.total_rewards, 42_000);
+    assert_eq!(stats.reward_rate, 0);
+    assert!(stats.last_mine_at > 0);
+}
+
+#[tokio::test]
+pub async fn test_mine_without_boost() {
+    let (mut context, miner, _staker) = setup().await;
+    let managed_proof = managed_proof_pda(miner.pubkey()).0;
+    open_managed_proof(&mut context, &miner).await;
+
+    mocks::set_mine_reward(managed_proof, 500);
+    let ix = ore_miner_delegation::instruction::mine(
+        miner.pubkey(),
+        BUS_ADDRESSES[0],
+        drillx::Solution::new([0; 16], [0; 8]),
+    );
+    process(&mut context, &[ix], &miner)
+        .await
+        .expect("mine without boost should succeed");
+
+    let delegated_stake = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(delegated_stake.amount, 500);
+}
+
+#[tokio::test]
+pub async fn test_mine_batch() {
+    let miners = [Keypair::new(), Keypair::new()];
+    let mut program_test = mocks::program_test();
+    for miner in miners.iter() {
+        program_test.add_account(
+            miner.pubkey(),
+            Account {
+                lamports: 1_000_000_000,
+                ..Account::default()
+            },
+        );
+    }
+    let mut context = program_test.start_with_context().await;
+
+    let mut entries = vec![];
+    for (i, miner) in miners.iter().enumerate() {
+        open_managed_proof(&mut context, miner).await;
+        mocks::set_mine_reward(managed_proof_pda(miner.pubkey()).0, 1_000 * (i as u64 + 1));
+        entries.push(MineBatchEntry {
+            miner: miner.pubkey().into(),
+            bus: BUS_ADDRESSES[i],
+            solution: drillx::Solution::new([0; 16], [0; 8]),
+        });
+    }
+
+    let blockhash = context.banks_client.get_latest_blockhash().await.unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ore_miner_delegation::instruction::mine_batch_with_boost(&entries)],
+        Some(&miners[0].pubkey()),
+        &[&miners[0], &miners[1]],
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .expect("mine batch should succeed");
+
+    for (i, miner) in miners.iter().enumerate() {
+        let delegated_stake = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+        assert_eq!(delegated_stake.amount, 1_000 * (i as u64 + 1));
+    }
+
+    // A global boost reservation can't be shared across the batch.
+    let mut ix = ore_miner_delegation::instruction::mine_batch(&entries);
+    let boost = Pubkey::new_unique();
+    let ore_proof = ore_api::state::proof_pda(managed_proof_pda(miners[0].pubkey()).0).0;
+    for (address, account) in [
+        (boost, mocks::program_account(GLOBAL_BOOST_ID, vec![0; 8])),
+        (ore_api::state::proof_pda(boost).0, mocks::proof_account(boost)),
+        (
+            reservation_pda(ore_proof).0,
+            mocks::program_account(GLOBAL_BOOST_ID, vec![0; 8]),
+        ),
+    ] {
+        context.set_account(&address, &account.into());
+        ix.accounts.push(AccountMeta::new(address, false));
+    }
+    let blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&blockhash)
+        .await
+        .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix],
+        Some(&miners[0].pubkey()),
+        &[&miners[0], &miners[1]],
+        blockhash,
+    );
+    let err = context.banks_client.process_transaction(tx).await.unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::GlobalBoostInBatch));
+}
+
+#[tokio::test]
+pub async fn test_mine_failure_leaves_stake_untouched() {
+    let (mut context, miner, _staker) = setup().await;
+    let managed_proof = managed_proof_pda(miner.pubkey()).0;
+    open_managed_proof(&mut context, &miner).await;
+
+    mocks::fail_next(managed_proof, ProgramError::Custom(7));
+    assert!(process(&mut context, &[mine(miner.pubkey())], &miner)
+        .await
+        .is_err());
+
+    let delegated_stake = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(delegated_stake.amount, 0);
+}
+
+#[tokio::test]
+pub async fn test_mine_rejects_other_miners_managed_proof() {
+    let (mut context, miner, other) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+    open_managed_proof(&mut context, &other).await;
+
+    // Point the miner's instruction at the other miner's managed proof.
+    let mut ix = mine(miner.pubkey());
+    let theirs = managed_proof_pda(other.pubkey()).0;
+    for meta in ix.accounts.iter_mut() {
+        if meta.pubkey == managed_proof_pda(miner.pubkey()).0 {
+            meta.pubkey = theirs;
+        }
+    }
+
+    let err = process(&mut context, &[ix], &miner).await.unwrap_err();
+    let BanksClientError::TransactionError(TransactionError::InstructionError(_, err)) = err
+    else {
+        panic!("expected an instruction error, got {err:?}");
+    };
+    assert_eq!(
+        OreDelegationError::from_instruction_error(&err),
+        Some(OreDelegationError::InvalidAccountAddress)
+    );
+}
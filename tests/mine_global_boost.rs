@@ -0,0 +1,207 @@
+mod common;
+mod mocks;
+
+use common::{delegated_stake, delegation_error, get_account, open_managed_proof, process};
+use ore_api::consts::BUS_ADDRESSES;
+use ore_miner_delegation::{
+    error::OreDelegationError,
+    global_boost::{
+        directory_pda, reservation_pda, Directory, Reservation, GLOBAL_BOOST_ID, ROTATION_INTERVAL,
+    },
+    pda::managed_proof_pda,
+};
+use solana_program::{clock::Clock, pubkey::Pubkey};
+use solana_sdk::{account::Account, signature::Keypair, signer::Signer};
+use steel::AccountDeserialize as _;
+
+#[tokio::test]
+pub async fn test_mine_with_global_boost() {
+    let miner = Keypair::new();
+    let boost = Pubkey::new_unique();
+    let ore_proof = ore_api::state::proof_pda(managed_proof_pda(miner.pubkey()).0).0;
+
+    let mut program_test = mocks::program_test();
+    program_test.add_account(
+        miner.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(boost, mocks::program_account(GLOBAL_BOOST_ID, vec![0; 8]));
+    program_test.add_account(ore_api::state::proof_pda(boost).0, mocks::proof_account(boost));
+    program_test.add_account(
+        reservation_pda(ore_proof).0,
+        mocks::program_account(GLOBAL_BOOST_ID, vec![0; 8]),
+    );
+    let mut context = program_test.start_with_context().await;
+    open_managed_proof(&mut context, &miner).await;
+
+    let ix = ore_miner_delegation::instruction::mine_with_global_boost(
+        miner.pubkey(),
+        BUS_ADDRESSES[0],
+        drillx::Solution::new([0; 16], [0; 8]),
+        boost,
+    );
+    process(&mut context, &[ix], &miner)
+        .await
+        .expect("mine with global boost should succeed");
+
+    let delegated_stake = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(delegated_stake.amount, mocks::DEFAULT_MINE_REWARD);
+
+    // A boost not owned by the global boost program is rejected.
+    let ix = ore_miner_delegation::instruction::mine_with_global_boost(
+        miner.pubkey(),
+        BUS_ADDRESSES[0],
+        drillx::Solution::new([0; 16], [0; 8]),
+        Pubkey::new_unique(),
+    );
+    assert!(process(&mut context, &[ix], &miner).await.is_err());
+}
+
+#[tokio::test]
+pub async fn test_mine_rotates_stale_global_boost() {
+    let miner = Keypair::new();
+    let stale_boost = Pubkey::new_unique();
+    let boost = Pubkey::new_unique();
+    let other_boost = Pubkey::new_unique();
+    let ore_proof = ore_api::state::proof_pda(managed_proof_pda(miner.pubkey()).0).0;
+    let reservation_address = reservation_pda(ore_proof).0;
+
+    let mut program_test = mocks::program_test();
+    program_test.add_account(
+        miner.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(boost, mocks::program_account(GLOBAL_BOOST_ID, vec![0; 8]));
+    program_test.add_account(ore_api::state::proof_pda(boost).0, mocks::proof_account(boost));
+    program_test.add_account(other_boost, mocks::program_account(GLOBAL_BOOST_ID, vec![0; 8]));
+    program_test.add_account(
+        ore_api::state::proof_pda(other_boost).0,
+        mocks::proof_account(other_boost),
+    );
+    let mut directory: Directory = bytemuck::Zeroable::zeroed();
+    directory.boosts[0] = boost;
+    directory.len = 1;
+    program_test.add_account(directory_pda().0, mocks::global_boost_account(&directory));
+    program_test.add_account(
+        reservation_address,
+        mocks::global_boost_account(&Reservation {
+            authority: ore_proof,
+            boost: stale_boost,
+            ts: 0,
+        }),
+    );
+    let mut context = program_test.start_with_context().await;
+    open_managed_proof(&mut context, &miner).await;
+
+    let mine_ix = |boost: Pubkey| {
+        ore_miner_delegation::instruction::mine_and_rotate_global_boost(
+            miner.pubkey(),
+            BUS_ADDRESSES[0],
+            drillx::Solution::new([0; 16], [0; 8]),
+            boost,
+        )
+    };
+    process(&mut context, &[mine_ix(boost)], &miner)
+        .await
+        .expect("mine should rotate the stale reservation");
+
+    let reservation = get_account(&mut context, reservation_address).await;
+    let reservation = *Reservation::try_from_bytes(&reservation.data).unwrap();
+    assert_eq!(reservation.boost, boost);
+    assert!(reservation.ts > 0);
+
+    let delegated_stake = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(delegated_stake.amount, mocks::DEFAULT_MINE_REWARD);
+
+    // A reservation already on the boost isn't rotated again.
+    process(&mut context, &[mine_ix(boost)], &miner)
+        .await
+        .expect("mine with a current reservation should succeed");
+    let rotated = get_account(&mut context, reservation_address).await;
+    assert_eq!(Reservation::try_from_bytes(&rotated.data).unwrap().ts, reservation.ts);
+
+    // A reservation that holds another boost and can't rotate yet fails the
+    // mine instead of mining without a boost.
+    let err = process(&mut context, &[mine_ix(other_boost)], &miner)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::ReservedForAnotherBoost));
+
+    // Once the rotation is due the reservation rotates even if it holds the
+    // supplied boost, and a rotation onto another boost fails the mine.
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp = reservation.ts + ROTATION_INTERVAL;
+    context.set_sysvar::<Clock>(&clock);
+    let err = process(&mut context, &[mine_ix(other_boost)], &miner)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::RotatedToAnotherBoost));
+
+    process(&mut context, &[mine_ix(boost)], &miner)
+        .await
+        .expect("mine should rotate the due reservation");
+    let rotated = get_account(&mut context, reservation_address).await;
+    let rotated = Reservation::try_from_bytes(&rotated.data).unwrap();
+    assert_eq!(rotated.boost, boost);
+    assert_eq!(rotated.ts, reservation.ts + ROTATION_INTERVAL);
+}
+
+#[tokio::test]
+pub async fn test_register_and_rotate_global_boost() {
+    let miner = Keypair::new();
+    let boost = Pubkey::new_unique();
+    let ore_proof = ore_api::state::proof_pda(managed_proof_pda(miner.pubkey()).0).0;
+    let reservation_address = reservation_pda(ore_proof).0;
+
+    let mut program_test = mocks::program_test();
+    program_test.add_account(
+        miner.pubkey(),
+        Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        },
+    );
+    let mut directory: Directory = bytemuck::Zeroable::zeroed();
+    directory.boosts[0] = boost;
+    directory.len = 1;
+    program_test.add_account(directory_pda().0, mocks::global_boost_account(&directory));
+    program_test.add_account(
+        reservation_address,
+        mocks::global_boost_account(&Reservation {
+            authority: ore_proof,
+            boost: Pubkey::default(),
+            ts: 0,
+        }),
+    );
+    let mut context = program_test.start_with_context().await;
+    open_managed_proof(&mut context, &miner).await;
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::register_global_boost(
+            miner.pubkey(),
+            miner.pubkey(),
+        )],
+        &miner,
+    )
+    .await
+    .expect("register global boost should succeed");
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::rotate_global_boost(miner.pubkey())],
+        &miner,
+    )
+    .await
+    .expect("rotate global boost should succeed");
+
+    let reservation = get_account(&mut context, reservation_address).await;
+    let reservation = Reservation::try_from_bytes(&reservation.data).unwrap();
+    assert_eq!(reservation.boost, boost);
+}
@@ -0,0 +1,510 @@
+//! Native stand-ins for the ORE, ORE boost and global boost programs.
+//!
+//! They implement just enough of each interface for this program's CPIs to
+//! succeed, keep their state in the same accounts the real programs use, and
+//! can be steered per signer with [`set_mine_reward`] and [`fail_next`].
+
+#![allow(dead_code)]
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use bytemuck::{Pod, Zeroable};
+use ore_api::{
+    consts::{MINT_ADDRESS, PROOF, TREASURY, TREASURY_ADDRESS, TREASURY_BUMP, TREASURY_TOKENS_ADDRESS},
+    instruction::OreInstruction,
+    state::{proof_pda, Proof},
+};
+use ore_boost_api::state::{boost_pda, stake_pda};
+use ore_miner_delegation::global_boost::{
+    BoostInstruction as GlobalBoostInstruction, Directory, Reservation, GLOBAL_BOOST_ID,
+};
+use solana_program::{
+    account_info::AccountInfo, clock::Clock, entrypoint::ProgramResult, program::invoke_signed,
+    program_error::ProgramError, program_pack::Pack, pubkey::Pubkey, rent::Rent,
+    system_instruction, sysvar::Sysvar,
+};
+use solana_program_test::{processor, ProgramTest};
+use solana_sdk::account::Account;
+use spl_associated_token_account::get_associated_token_address;
+use steel::{AccountDeserialize, Discriminator};
+
+/// ORE credited to a proof per mine unless overridden.
+pub const DEFAULT_MINE_REWARD: u64 = 1_000_000;
+
+/// ORE held by the treasury at genesis.
+pub const TREASURY_SUPPLY: u64 = 1_000_000_000_000_000;
+
+#[derive(Default)]
+struct Behavior {
+    mine_reward: Option<u64>,
+    fail_next: Option<ProgramError>,
+}
+
+static BEHAVIOR: LazyLock<Mutex<HashMap<Pubkey, Behavior>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Sets the ORE credited to `authority`'s proof on each mine.
+pub fn set_mine_reward(authority: Pubkey, reward: u64) {
+    BEHAVIOR.lock().unwrap().entry(authority).or_default().mine_reward = Some(reward);
+}
+
+/// Makes the next mock instruction signed by `authority` fail with `error`.
+pub fn fail_next(authority: Pubkey, error: ProgramError) {
+    BEHAVIOR.lock().unwrap().entry(authority).or_default().fail_next = Some(error);
+}
+
+fn check_failure(signer: &AccountInfo) -> ProgramResult {
+    match BEHAVIOR
+        .lock()
+        .unwrap()
+        .get_mut(signer.key)
+        .and_then(|behavior| behavior.fail_next.take())
+    {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+fn mine_reward(authority: &Pubkey) -> u64 {
+    BEHAVIOR
+        .lock()
+        .unwrap()
+        .get(authority)
+        .and_then(|behavior| behavior.mine_reward)
+        .unwrap_or(DEFAULT_MINE_REWARD)
+}
+
+/// Boost stake account as kept by the mock boost program.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct MockStake {
+    pub authority: Pubkey,
+    pub boost: Pubkey,
+    pub balance: u64,
+}
+
+/// Registers this program and the mocks, and seeds the ORE mint, treasury and
+/// the ORE boost deposits account.
+pub fn program_test() -> ProgramTest {
+    program_test_with(false)
+}
+
+/// Same as [`program_test`], but runs this program from its SBF build so its
+/// compute units are metered. Requires `cargo build-sbf`.
+pub fn sbf_program_test() -> ProgramTest {
+    program_test_with(true)
+}
+
+fn program_test_with(prefer_bpf: bool) -> ProgramTest {
+    let mut program_test = ProgramTest::default();
+    program_test.prefer_bpf(prefer_bpf);
+    program_test.add_program(
+        "ore_miner_delegation",
+        ore_miner_delegation::id(),
+        processor!(ore_miner_delegation::process_instruction),
+    );
+    program_test.prefer_bpf(false);
+    program_test.add_program("mock_ore", ore_api::id(), processor!(process_ore));
+    program_test.add_program("mock_ore_boost", ore_boost_api::id(), processor!(process_boost));
+    program_test.add_program(
+        "mock_global_boost",
+        GLOBAL_BOOST_ID,
+        processor!(process_global_boost),
+    );
+
+    add_mint(&mut program_test, MINT_ADDRESS, TREASURY_ADDRESS, TREASURY_SUPPLY);
+    program_test.add_account(
+        TREASURY_TOKENS_ADDRESS,
+        token_account(TREASURY_ADDRESS, MINT_ADDRESS, TREASURY_SUPPLY),
+    );
+    add_boost(&mut program_test, MINT_ADDRESS);
+
+    let boost_config = ore_boost_api::state::config_pda().0;
+    program_test.add_account(boost_config, program_account(ore_boost_api::id(), vec![]));
+    program_test.add_account(proof_pda(boost_config).0, proof_account(boost_config));
+
+    program_test
+}
+
+/// An account owned by `owner` holding `data`.
+pub fn program_account(owner: Pubkey, data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+/// An empty ORE proof belonging to `authority`.
+pub fn proof_account(authority: Pubkey) -> Account {
+    let mut data = vec![0; 8 + std::mem::size_of::<Proof>()];
+    data[0] = Proof::discriminator();
+    let proof = Proof::try_from_bytes_mut(&mut data).unwrap();
+    proof.authority = authority;
+    proof.miner = authority;
+    program_account(ore_api::id(), data)
+}
+
+/// Seeds an initialized SPL mint.
+pub fn add_mint(program_test: &mut ProgramTest, mint: Pubkey, authority: Pubkey, supply: u64) {
+    let mut data = vec![0; spl_token::state::Mint::LEN];
+    spl_token::state::Mint {
+        mint_authority: Some(authority).into(),
+        supply,
+        decimals: ore_api::consts::TOKEN_DECIMALS,
+        is_initialized: true,
+        freeze_authority: None.into(),
+    }
+    .pack_into_slice(&mut data);
+    program_test.add_account(mint, token_program_account(data));
+}
+
+/// Seeds the boost for `mint` and its deposits account.
+pub fn add_boost(program_test: &mut ProgramTest, mint: Pubkey) {
+    let boost_address = boost_pda(mint).0;
+    program_test.add_account(boost_address, program_account(ore_boost_api::id(), vec![]));
+    program_test.add_account(
+        get_associated_token_address(&boost_address, &mint),
+        token_account(boost_address, mint, 0),
+    );
+}
+
+/// An initialized SPL token account holding `amount` of `mint`.
+pub fn token_account(owner: Pubkey, mint: Pubkey, amount: u64) -> Account {
+    let mut data = vec![0; spl_token::state::Account::LEN];
+    spl_token::state::Account {
+        mint,
+        owner,
+        amount,
+        state: spl_token::state::AccountState::Initialized,
+        ..Default::default()
+    }
+    .pack_into_slice(&mut data);
+    token_program_account(data)
+}
+
+fn token_program_account(data: Vec<u8>) -> Account {
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn find<'a, 'info>(
+    accounts: &'a [AccountInfo<'info>],
+    key: &Pubkey,
+) -> Result<&'a AccountInfo<'info>, ProgramError> {
+    accounts
+        .iter()
+        .find(|info| info.key == key)
+        .ok_or(ProgramError::NotEnoughAccountKeys)
+}
+
+fn create_pda<'info>(
+    payer: &AccountInfo<'info>,
+    target: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    space: usize,
+    owner: &Pubkey,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    invoke_signed(
+        &system_instruction::create_account(
+            payer.key,
+            target.key,
+            Rent::get()?.minimum_balance(space),
+            space as u64,
+            owner,
+        ),
+        accounts,
+        &[seeds],
+    )
+}
+
+fn token_transfer<'info>(
+    from: &AccountInfo<'info>,
+    to: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    accounts: &[AccountInfo<'info>],
+    amount: u64,
+    seeds: &[&[u8]],
+) -> ProgramResult {
+    let signers: &[&[&[u8]]] = if seeds.is_empty() { &[] } else { &[seeds] };
+    invoke_signed(
+        &spl_token::instruction::transfer(
+            &spl_token::id(),
+            from.key,
+            to.key,
+            authority.key,
+            &[],
+            amount,
+        )?,
+        accounts,
+        signers,
+    )
+}
+
+/// Mock ORE program: `open`, `mine`, `claim` and `update`.
+pub fn process_ore(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (tag, args) = data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let signer = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_failure(signer)?;
+
+    let proof_address = proof_pda(*signer.key);
+    let proof_info = find(accounts, &proof_address.0)?;
+
+    match OreInstruction::try_from(*tag).or(Err(ProgramError::InvalidInstructionData))? {
+        OreInstruction::Open => {
+            let miner = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let payer = accounts.get(2).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let space = 8 + std::mem::size_of::<Proof>();
+            create_pda(
+                payer,
+                proof_info,
+                accounts,
+                space,
+                &ore_api::id(),
+                &[PROOF, signer.key.as_ref(), &[proof_address.1]],
+            )?;
+            let mut data = proof_info.try_borrow_mut_data()?;
+            data[0] = Proof::discriminator();
+            let proof = Proof::try_from_bytes_mut(&mut data)?;
+            proof.authority = *signer.key;
+            proof.miner = *miner.key;
+            proof.challenge = proof_address.0.to_bytes();
+            proof.last_hash_at = solana_program::clock::Clock::get()?.unix_timestamp;
+        }
+        OreInstruction::Mine => {
+            check_mine_instruction(accounts, data)?;
+            let mut data = proof_info.try_borrow_mut_data()?;
+            let proof = Proof::try_from_bytes_mut(&mut data)?;
+            let reward = mine_reward(signer.key);
+            proof.balance += reward;
+            proof.total_hashes += 1;
+            proof.total_rewards += reward;
+            proof.last_hash_at = solana_program::clock::Clock::get()?.unix_timestamp;
+        }
+        OreInstruction::Claim => {
+            let amount = args
+                .get(..8)
+                .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+                .ok_or(ProgramError::InvalidInstructionData)?;
+            let beneficiary = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            {
+                let mut data = proof_info.try_borrow_mut_data()?;
+                let proof = Proof::try_from_bytes_mut(&mut data)?;
+                proof.balance = proof
+                    .balance
+                    .checked_sub(amount)
+                    .ok_or(ProgramError::InsufficientFunds)?;
+            }
+            let treasury = find(accounts, &TREASURY_ADDRESS)?;
+            let treasury_tokens = find(accounts, &TREASURY_TOKENS_ADDRESS)?;
+            token_transfer(
+                treasury_tokens,
+                beneficiary,
+                treasury,
+                accounts,
+                amount,
+                &[TREASURY, &[TREASURY_BUMP]],
+            )?;
+        }
+        OreInstruction::Update => {
+            let miner = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+            let mut data = proof_info.try_borrow_mut_data()?;
+            Proof::try_from_bytes_mut(&mut data)?.miner = *miner.key;
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+
+    Ok(())
+}
+
+/// Fails unless a mine CPI matches what `ore_api::sdk::mine` builds for the
+/// same signer, bus, solution and boost config, so the program's hand-built
+/// instruction can't drift from the SDK. Global boost sets have no SDK
+/// builder, so only their leading accounts are compared.
+fn check_mine_instruction(accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let [signer, bus, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let args = data
+        .get(1..)
+        .and_then(|args| bytemuck::try_from_bytes::<ore_api::instruction::Mine>(args).ok())
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let boost_config = accounts.get(6).map_or(Pubkey::default(), |info| *info.key);
+    let expected = ore_api::sdk::mine(
+        *signer.key,
+        *signer.key,
+        *bus.key,
+        drillx::Solution::new(args.digest, args.nonce),
+        boost_config,
+    );
+    if expected.data != data {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    let compared = if accounts.len() == expected.accounts.len() {
+        expected.accounts.len()
+    } else {
+        6
+    };
+    for (meta, info) in expected.accounts.iter().zip(accounts).take(compared) {
+        if meta.pubkey != *info.key || meta.is_writable != info.is_writable {
+            return Err(ProgramError::InvalidArgument);
+        }
+    }
+    Ok(())
+}
+
+/// Mock ORE boost program: `open`, `deposit` and `withdraw`.
+pub fn process_boost(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+    let (tag, args) = data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    let signer = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_failure(signer)?;
+
+    let mint = accounts
+        .iter()
+        .find(|info| *info.owner == spl_token::id() && info.data_len() == spl_token::state::Mint::LEN)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    let boost_address = boost_pda(*mint.key);
+    let stake_address = stake_pda(*signer.key, boost_address.0);
+    let stake_info = find(accounts, &stake_address.0)?;
+
+    let open = ore_boost_api::sdk::open(Pubkey::default(), Pubkey::default(), Pubkey::default());
+    let deposit = ore_boost_api::sdk::deposit(Pubkey::default(), Pubkey::default(), 0);
+    let withdraw = ore_boost_api::sdk::withdraw(Pubkey::default(), Pubkey::default(), 0);
+    let amount = || {
+        args.get(..8)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap()))
+            .ok_or(ProgramError::InvalidInstructionData)
+    };
+
+    if *tag == open.data[0] {
+        let payer = accounts.get(1).ok_or(ProgramError::NotEnoughAccountKeys)?;
+        create_pda(
+            payer,
+            stake_info,
+            accounts,
+            std::mem::size_of::<MockStake>(),
+            &ore_boost_api::id(),
+            &[
+                ore_boost_api::consts::STAKE,
+                signer.key.as_ref(),
+                boost_address.0.as_ref(),
+                &[stake_address.1],
+            ],
+        )?;
+        let mut data = stake_info.try_borrow_mut_data()?;
+        let stake = bytemuck::from_bytes_mut::<MockStake>(&mut data);
+        stake.authority = *signer.key;
+        stake.boost = boost_address.0;
+    } else if *tag == deposit.data[0] {
+        let amount = amount()?;
+        let sender = find(accounts, &get_associated_token_address(signer.key, mint.key))?;
+        let deposits = find(accounts, &get_associated_token_address(&boost_address.0, mint.key))?;
+        token_transfer(sender, deposits, signer, accounts, amount, &[])?;
+        let mut data = stake_info.try_borrow_mut_data()?;
+        bytemuck::from_bytes_mut::<MockStake>(&mut data).balance += amount;
+    } else if *tag == withdraw.data[0] {
+        let amount = amount()?;
+        {
+            let mut data = stake_info.try_borrow_mut_data()?;
+            let stake = bytemuck::from_bytes_mut::<MockStake>(&mut data);
+            stake.balance = stake
+                .balance
+                .checked_sub(amount)
+                .ok_or(ProgramError::InsufficientFunds)?;
+        }
+        let beneficiary = find(accounts, &get_associated_token_address(signer.key, mint.key))?;
+        let boost = find(accounts, &boost_address.0)?;
+        let deposits = find(accounts, &get_associated_token_address(&boost_address.0, mint.key))?;
+        token_transfer(
+            deposits,
+            beneficiary,
+            boost,
+            accounts,
+            amount,
+            &[
+                ore_boost_api::consts::BOOST,
+                mint.key.as_ref(),
+                &[boost_address.1],
+            ],
+        )?;
+    } else {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+
+    Ok(())
+}
+
+/// Mock global boost program: `register` and `rotate` accept any reservation.
+pub fn process_global_boost(
+    _program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    let tag = data.first().ok_or(ProgramError::InvalidInstructionData)?;
+    let signer = accounts.first().ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if !signer.is_signer {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+    check_failure(signer)?;
+
+    match GlobalBoostInstruction::try_from(*tag).or(Err(ProgramError::InvalidInstructionData))? {
+        GlobalBoostInstruction::Register => Ok(()),
+        GlobalBoostInstruction::Rotate => rotate_reservation(accounts),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Moves a reservation onto the first boost of the directory. Placeholder
+/// reservations and directories without a layout are left alone.
+fn rotate_reservation(accounts: &[AccountInfo]) -> ProgramResult {
+    let [_signer, directory_info, _proof_info, reservation_info, ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+    let directory_data = directory_info.try_borrow_data()?;
+    let mut reservation_data = reservation_info.try_borrow_mut_data()?;
+    if let (Ok(directory), Ok(reservation)) = (
+        Directory::try_from_bytes(&directory_data),
+        Reservation::try_from_bytes_mut(&mut reservation_data),
+    ) {
+        if let Some(boost) = directory.active_boosts().first() {
+            reservation.boost = *boost;
+            reservation.ts = Clock::get()?.unix_timestamp;
+        }
+    }
+    Ok(())
+}
+
+/// A global boost program account holding `account`.
+pub fn global_boost_account<T: Discriminator + Pod>(account: &T) -> Account {
+    let mut data = vec![0; 8];
+    data[0] = T::discriminator();
+    data.extend_from_slice(bytemuck::bytes_of(account));
+    program_account(GLOBAL_BOOST_ID, data)
+}
+
+/// Reads the boost stake kept by the mock boost program.
+pub fn read_stake(data: &[u8]) -> MockStake {
+    *bytemuck::from_bytes::<MockStake>(data)
+}
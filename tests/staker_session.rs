@@ -0,0 +1,356 @@
+mod common;
+mod mocks;
+
+use common::{
+    delegated_boost, delegation_error, get_account, open_managed_proof, open_managed_proof_boost,
+    process, set_deposit_fee, setup, token_balance, STAKER_ORE,
+};
+use ore_api::consts::MINT_ADDRESS;
+use ore_miner_delegation::{
+    consts::MIN_FEE_CHANGE_DELAY, error::OreDelegationError, pda::staker_session_pda,
+    state::StakerSession, utils::AccountDeserializeV1,
+};
+use solana_program::{clock::Clock, program_option::COption, program_pack::Pack, pubkey::Pubkey};
+use solana_sdk::{account::Account, signature::Keypair, signer::Signer, transaction::Transaction};
+use spl_associated_token_account::get_associated_token_address;
+
+#[tokio::test]
+pub async fn test_staker_session() {
+    let (mut context, miner, staker) = setup().await;
+    let operator = Keypair::new();
+    context.set_account(
+        &operator.pubkey(),
+        &Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        }
+        .into(),
+    );
+    open_managed_proof(&mut context, &miner).await;
+    open_managed_proof_boost(&mut context, &miner).await;
+
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::init_delegate_boost_v2(
+                staker.pubkey(),
+                miner.pubkey(),
+                staker.pubkey(),
+                MINT_ADDRESS,
+            ),
+            ore_miner_delegation::instruction::approve_staker_session(
+                staker.pubkey(),
+                staker.pubkey(),
+                operator.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+                i64::MAX,
+                2_000_000_000,
+            ),
+        ],
+        &staker,
+    )
+    .await
+    .expect("approve staker session should succeed");
+
+    let delegate = |amount| {
+        ore_miner_delegation::instruction::delegate_boost_v2_with_session(
+            operator.pubkey(),
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            amount,
+        )
+    };
+    let undelegate = |amount| {
+        ore_miner_delegation::instruction::undelegate_boost_v2_with_session(
+            operator.pubkey(),
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            amount,
+        )
+    };
+
+    process(&mut context, &[delegate(1_500_000_000)], &operator)
+        .await
+        .expect("operator should delegate within the allowance");
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 1_500_000_000);
+    assert!(process(&mut context, &[delegate(1_000_000_000)], &operator)
+        .await
+        .is_err());
+
+    let staker_tokens = get_associated_token_address(&staker.pubkey(), &MINT_ADDRESS);
+    process(&mut context, &[undelegate(500_000_000)], &operator)
+        .await
+        .expect("operator should undelegate to the staker");
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 1_000_000_000);
+    assert_eq!(
+        token_balance(&mut context, staker_tokens).await,
+        STAKER_ORE - 1_000_000_000
+    );
+
+    // The session only covers the managed proof the staker approved it for.
+    let other_miner = Pubkey::new_unique();
+    for ix in [
+        ore_miner_delegation::instruction::delegate_boost_v2_with_session(
+            operator.pubkey(),
+            staker.pubkey(),
+            other_miner,
+            MINT_ADDRESS,
+            1,
+        ),
+        ore_miner_delegation::instruction::undelegate_boost_v2_with_session(
+            operator.pubkey(),
+            staker.pubkey(),
+            other_miner,
+            MINT_ADDRESS,
+            1,
+        ),
+    ] {
+        let err = process(&mut context, &[ix], &operator).await.unwrap_err();
+        assert_eq!(delegation_error(err), Some(OreDelegationError::SessionManagedProofMismatch));
+    }
+
+    // Withdrawals can't be redirected to the operator.
+    let operator_tokens = Pubkey::new_unique();
+    context.set_account(
+        &operator_tokens,
+        &mocks::token_account(operator.pubkey(), MINT_ADDRESS, 0).into(),
+    );
+    let mut redirected = undelegate(500_000_000);
+    redirected.accounts[12].pubkey = operator_tokens;
+    assert!(process(&mut context, &[redirected], &operator).await.is_err());
+
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::revoke_staker_session(
+            staker.pubkey(),
+            staker.pubkey(),
+            operator.pubkey(),
+            MINT_ADDRESS,
+        )],
+        &staker,
+    )
+    .await
+    .expect("revoke staker session should succeed");
+    assert!(process(&mut context, &[undelegate(500_000_000)], &operator)
+        .await
+        .is_err());
+
+    // An expired session can't be used either.
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::approve_staker_session(
+            staker.pubkey(),
+            staker.pubkey(),
+            operator.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            0,
+            2_000_000_000,
+        )],
+        &staker,
+    )
+    .await
+    .expect("approve staker session should succeed");
+    assert!(process(&mut context, &[delegate(1)], &operator)
+        .await
+        .is_err());
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 1_000_000_000);
+}
+
+#[tokio::test]
+pub async fn test_staker_session_approval_is_exclusive() {
+    let (mut context, miner, staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+    let first = Pubkey::new_unique();
+    let second = Pubkey::new_unique();
+    let staker_tokens = get_associated_token_address(&staker.pubkey(), &MINT_ADDRESS);
+
+    let approve = |operator, expires_at| {
+        ore_miner_delegation::instruction::approve_staker_session(
+            staker.pubkey(),
+            staker.pubkey(),
+            operator,
+            miner.pubkey(),
+            MINT_ADDRESS,
+            expires_at,
+            1_000_000_000,
+        )
+    };
+    let token_delegate = |account: Account| {
+        spl_token::state::Account::unpack(&account.data).unwrap().delegate
+    };
+
+    process(&mut context, &[approve(first, i64::MAX)], &staker)
+        .await
+        .expect("approve staker session should succeed");
+
+    // A second operator would silently take over the token approval.
+    let err = process(&mut context, &[approve(second, i64::MAX)], &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::TokenAccountAlreadyDelegated));
+
+    // The approval outlives the session until it is revoked.
+    process(&mut context, &[approve(first, 0)], &staker)
+        .await
+        .expect("approving the same operator again should succeed");
+    let first_session = staker_session_pda(staker.pubkey(), MINT_ADDRESS, first).0;
+    assert_eq!(
+        token_delegate(get_account(&mut context, staker_tokens).await),
+        COption::Some(first_session)
+    );
+    process(
+        &mut context,
+        &[ore_miner_delegation::instruction::revoke_staker_session(
+            staker.pubkey(),
+            staker.pubkey(),
+            first,
+            MINT_ADDRESS,
+        )],
+        &staker,
+    )
+    .await
+    .expect("revoking an expired session should succeed");
+    assert_eq!(
+        token_delegate(get_account(&mut context, staker_tokens).await),
+        COption::None
+    );
+
+    process(&mut context, &[approve(second, i64::MAX)], &staker)
+        .await
+        .expect("approving another operator after a revoke should succeed");
+    assert_eq!(
+        token_delegate(get_account(&mut context, staker_tokens).await),
+        COption::Some(staker_session_pda(staker.pubkey(), MINT_ADDRESS, second).0)
+    );
+}
+
+#[tokio::test]
+pub async fn test_staker_session_rejects_deposit_fee() {
+    let (mut context, miner, staker) = setup().await;
+    let operator = Keypair::new();
+    context.set_account(
+        &operator.pubkey(),
+        &Account {
+            lamports: 1_000_000_000,
+            ..Account::default()
+        }
+        .into(),
+    );
+    open_managed_proof(&mut context, &miner).await;
+    open_managed_proof_boost(&mut context, &miner).await;
+    set_deposit_fee(&mut context, &miner, 500, Pubkey::new_unique()).await;
+
+    process(
+        &mut context,
+        &[
+            ore_miner_delegation::instruction::init_delegate_boost_v2(
+                staker.pubkey(),
+                miner.pubkey(),
+                staker.pubkey(),
+                MINT_ADDRESS,
+            ),
+            ore_miner_delegation::instruction::approve_staker_session(
+                staker.pubkey(),
+                staker.pubkey(),
+                operator.pubkey(),
+                miner.pubkey(),
+                MINT_ADDRESS,
+                i64::MAX,
+                2_000_000_000,
+            ),
+        ],
+        &staker,
+    )
+    .await
+    .expect("approve staker session should succeed");
+
+    // Once the fee applies, the operator can no longer deposit for the staker.
+    let mut clock = context.banks_client.get_sysvar::<Clock>().await.unwrap();
+    clock.unix_timestamp += MIN_FEE_CHANGE_DELAY;
+    context.set_sysvar::<Clock>(&clock);
+
+    let err = process(
+        &mut context,
+        &[ore_miner_delegation::instruction::delegate_boost_v2_with_session(
+            operator.pubkey(),
+            staker.pubkey(),
+            miner.pubkey(),
+            MINT_ADDRESS,
+            1_000_000_000,
+        )],
+        &operator,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::DepositFeeAboveAccepted));
+    assert_eq!(delegated_boost(&mut context, &miner, &staker).await.amount, 0);
+}
+
+#[tokio::test]
+pub async fn test_staker_session_rent_returns_to_payer() {
+    let (mut context, miner, staker) = setup().await;
+    let operator = Pubkey::new_unique();
+    open_managed_proof(&mut context, &miner).await;
+
+    // The miner sponsors the session's rent.
+    let blockhash = context
+        .banks_client
+        .get_new_latest_blockhash(&context.last_blockhash)
+        .await
+        .unwrap();
+    let tx = Transaction::new_signed_with_payer(
+        &[ore_miner_delegation::instruction::approve_staker_session(
+            staker.pubkey(),
+            miner.pubkey(),
+            operator,
+            miner.pubkey(),
+            MINT_ADDRESS,
+            i64::MAX,
+            1_000_000_000,
+        )],
+        Some(&miner.pubkey()),
+        &[&miner, &staker],
+        blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(tx)
+        .await
+        .expect("sponsored approval should succeed");
+
+    let session_address = staker_session_pda(staker.pubkey(), MINT_ADDRESS, operator).0;
+    let session = get_account(&mut context, session_address).await;
+    assert_eq!(StakerSession::try_from_bytes(&session.data).unwrap().payer, miner.pubkey());
+
+    let revoke = |payer| {
+        ore_miner_delegation::instruction::revoke_staker_session(
+            staker.pubkey(),
+            payer,
+            operator,
+            MINT_ADDRESS,
+        )
+    };
+    let err = process(&mut context, &[revoke(staker.pubkey())], &staker)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::CloseAccountFeePayerMissmatch));
+
+    let miner_lamports = get_account(&mut context, miner.pubkey()).await.lamports;
+    process(&mut context, &[revoke(miner.pubkey())], &staker)
+        .await
+        .expect("revoke staker session should succeed");
+    assert_eq!(
+        get_account(&mut context, miner.pubkey()).await.lamports,
+        miner_lamports + session.lamports
+    );
+    assert!(context
+        .banks_client
+        .get_account(session_address)
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -66,7 +66,7 @@ pub async fn test_mine_with_boost() {
         &ore_api::id(),
     );
 
-    let ix = ore_miner_delegation::instruction::open_managed_proof(miner.pubkey());
+    let ix = ore_miner_delegation::instruction::open_managed_proof(miner.pubkey(), miner.pubkey());
 
     let ix_delegate_stake = ore_miner_delegation::instruction::init_delegate_stake(
         miner.pubkey(),
@@ -235,7 +235,7 @@ pub async fn test_open_managed_proof_boost_stake_and_unstake_v4() {
         &ore_api::id(),
     );
 
-    let ix = ore_miner_delegation::instruction::open_managed_proof(miner.pubkey());
+    let ix = ore_miner_delegation::instruction::open_managed_proof(miner.pubkey(), miner.pubkey());
 
     let ix_delegate_stake = ore_miner_delegation::instruction::init_delegate_stake(
         miner.pubkey(),
@@ -425,7 +425,7 @@ pub async fn test_open_managed_proof_boost_stake_and_unstake_v4() {
     let initial_claimed_amount = staker_token_account.amount;
 
 
-    let ix = ore_miner_delegation::instruction::open_managed_proof_boost(miner.pubkey(), ore_api::consts::MINT_ADDRESS);
+    let ix = ore_miner_delegation::instruction::open_managed_proof_boost(miner.pubkey(), miner.pubkey(), ore_api::consts::MINT_ADDRESS);
     let mut tx = Transaction::new_with_payer(&[ix], Some(&miner.pubkey()));
 
     let blockhash = context
@@ -484,7 +484,7 @@ pub async fn test_open_managed_proof_boost_stake_and_unstake_v4() {
     context.set_sysvar::<Clock>(&new_clock);
 
     // Delegate Boost
-    let ix = ore_miner_delegation::instruction::delegate_boost_v2(staker.pubkey(), miner.pubkey(), ore_api::consts::MINT_ADDRESS, initial_claimed_amount);
+    let ix = ore_miner_delegation::instruction::delegate_boost_v2(staker.pubkey(), miner.pubkey(), ore_api::consts::MINT_ADDRESS, initial_claimed_amount, None);
     let mut tx = Transaction::new_with_payer(&[ix], Some(&miner.pubkey()));
 
     let blockhash = context
@@ -0,0 +1,247 @@
+mod common;
+mod mocks;
+
+use common::{
+    delegated_stake, delegation_error, get_account, mine, open_managed_proof, process, setup,
+};
+use ore_miner_delegation::{
+    error::OreDelegationError,
+    pda::{delegated_stake_pda, managed_proof_pda, managed_proof_settings_pda},
+    state::{DelegatedStake, ManagedProof},
+    utils::{AccountDeserializeV1, AccountDiscriminator, LayoutVersion},
+};
+use solana_program::pubkey::Pubkey;
+use solana_program_test::BanksClientError;
+use solana_sdk::{instruction::InstructionError, signer::Signer, transaction::TransactionError};
+
+#[tokio::test]
+pub async fn test_upgrade_account() {
+    let (mut context, miner, _staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+
+    // Rewrite the delegated stake with its layout from before the payer.
+    let stake_address = delegated_stake_pda(miner.pubkey(), miner.pubkey()).0;
+    let mut legacy_stake = get_account(&mut context, stake_address).await;
+    legacy_stake.data.truncate(8 + 16);
+    legacy_stake.data[1] = 0;
+    context.set_account(&stake_address, &legacy_stake.into());
+
+    let managed_proof = managed_proof_pda(miner.pubkey()).0;
+    let upgrade = |account, seeds: &[Pubkey]| {
+        ore_miner_delegation::instruction::upgrade_account(miner.pubkey(), account, seeds)
+    };
+    let stake_seeds = [miner.pubkey(), managed_proof];
+
+    // The address is checked against the seed accounts before upgrading.
+    let err = process(&mut context, &[upgrade(stake_address, &[])], &miner)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::NotEnoughAccountKeys
+        ))
+    ));
+    let err = process(
+        &mut context,
+        &[upgrade(stake_address, &[Pubkey::new_unique(), managed_proof])],
+        &miner,
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InvalidAccountAddress));
+
+    process(&mut context, &[upgrade(stake_address, &stake_seeds)], &miner)
+        .await
+        .expect("upgrade account should succeed");
+
+    let upgraded = get_account(&mut context, stake_address).await;
+    assert_eq!(upgraded.data.len(), 8 + std::mem::size_of::<DelegatedStake>());
+    assert_eq!(upgraded.data[1], DelegatedStake::layout_version());
+    let upgraded = DelegatedStake::try_from_bytes(&upgraded.data).unwrap();
+    assert_eq!(upgraded.amount, 0);
+    assert_eq!(upgraded.payer, Pubkey::default());
+
+    let err = process(&mut context, &[upgrade(stake_address, &stake_seeds)], &miner)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::AccountAlreadyUpgraded));
+
+    // A managed proof is checked against its miner, whose index is read from
+    // the legacy layout.
+    let mut legacy = get_account(&mut context, managed_proof).await;
+    legacy.data.truncate(8 + 40);
+    legacy.data[1] = 0;
+    context.set_account(&managed_proof, &legacy.into());
+    let err = process(&mut context, &[upgrade(managed_proof, &[Pubkey::new_unique()])], &miner)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InvalidAccountAddress));
+    process(&mut context, &[upgrade(managed_proof, &[miner.pubkey()])], &miner)
+        .await
+        .expect("upgrade managed proof should succeed");
+    let upgraded = get_account(&mut context, managed_proof).await;
+    assert_eq!(upgraded.data.len(), 8 + std::mem::size_of::<ManagedProof>());
+
+    // Accounts of other programs are left alone.
+    let foreign = Pubkey::new_unique();
+    let mut data = vec![0; 8 + 16];
+    data[0] = AccountDiscriminator::DelegatedStake as u8;
+    context.set_account(
+        &foreign,
+        &mocks::program_account(Pubkey::new_unique(), data).into(),
+    );
+    let err = process(&mut context, &[upgrade(foreign, &stake_seeds)], &miner)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::InvalidAccountOwner
+        ))
+    ));
+
+    // So are program accounts of no known type.
+    let unknown = Pubkey::new_unique();
+    context.set_account(
+        &unknown,
+        &mocks::program_account(ore_miner_delegation::id(), vec![0xff; 8 + 16]).into(),
+    );
+    let err = process(&mut context, &[upgrade(unknown, &[])], &miner)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InvalidAccountDiscriminator));
+}
+
+#[tokio::test]
+pub async fn test_mine_upgrades_legacy_accounts() {
+    let (mut context, miner, _staker) = setup().await;
+    let managed_proof = managed_proof_pda(miner.pubkey()).0;
+    open_managed_proof(&mut context, &miner).await;
+
+    // Rewrite the managed proof with its layout from before mining statistics.
+    let mut legacy = get_account(&mut context, managed_proof).await;
+    legacy.data.truncate(8 + 40);
+    legacy.data[1] = 0;
+    context.set_account(&managed_proof, &legacy.into());
+
+    // And the delegated stake with its layout from before the payer.
+    let stake_address = delegated_stake_pda(miner.pubkey(), miner.pubkey()).0;
+    let mut legacy_stake = get_account(&mut context, stake_address).await;
+    legacy_stake.data.truncate(8 + 16);
+    legacy_stake.data[1] = 0;
+    context.set_account(&stake_address, &legacy_stake.into());
+
+    process(&mut context, &[mine(miner.pubkey())], &miner)
+        .await
+        .expect("mine should upgrade the managed proof");
+
+    let upgraded = get_account(&mut context, managed_proof).await;
+    assert_eq!(upgraded.data.len(), 8 + std::mem::size_of::<ManagedProof>());
+    assert_eq!(upgraded.data[1], ManagedProof::layout_version());
+    let upgraded = ManagedProof::try_from_bytes(&upgraded.data).unwrap();
+    assert_eq!(upgraded.miner_authority, miner.pubkey());
+    assert_eq!(upgraded.payer, miner.pubkey());
+    assert_eq!(upgraded.total_submissions, 1);
+
+    // The stake's payer was never recorded, so it stays unknown.
+    let upgraded = delegated_stake(&mut context, miner.pubkey(), miner.pubkey()).await;
+    assert_eq!(upgraded.payer, Pubkey::default());
+}
+
+#[tokio::test]
+pub async fn test_mine_checks_legacy_managed_proof_before_upgrading() {
+    let (mut context, miner, other) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+    open_managed_proof(&mut context, &other).await;
+
+    // The other miner's managed proof still uses the first layout.
+    let theirs = managed_proof_pda(other.pubkey()).0;
+    let mut legacy = get_account(&mut context, theirs).await;
+    legacy.data.truncate(8 + 40);
+    legacy.data[1] = 0;
+    context.set_account(&theirs, &legacy.into());
+
+    let mut ix = mine(miner.pubkey());
+    for meta in ix.accounts.iter_mut() {
+        if meta.pubkey == managed_proof_pda(miner.pubkey()).0 {
+            meta.pubkey = theirs;
+        }
+    }
+
+    let err = process(&mut context, &[ix], &miner).await.unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InvalidAccountAddress));
+    assert_eq!(get_account(&mut context, theirs).await.data.len(), 8 + 40);
+}
+
+#[tokio::test]
+pub async fn test_mine_checks_legacy_delegated_stake_before_upgrading() {
+    let (mut context, miner, _staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+
+    // An empty program account in place of the delegated stake is rejected
+    // rather than upgraded.
+    let stake_address = delegated_stake_pda(miner.pubkey(), miner.pubkey()).0;
+    context.set_account(
+        &stake_address,
+        &mocks::program_account(ore_miner_delegation::id(), vec![]).into(),
+    );
+    let err = process(&mut context, &[mine(miner.pubkey())], &miner)
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::UninitializedAccount
+        ))
+    ));
+
+    // So is a legacy account of another type.
+    let mut data = vec![0; 8 + 16];
+    data[0] = AccountDiscriminator::DelegatedBoost as u8;
+    context.set_account(
+        &stake_address,
+        &mocks::program_account(ore_miner_delegation::id(), data).into(),
+    );
+    let err = process(&mut context, &[mine(miner.pubkey())], &miner)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InvalidAccountDiscriminator));
+}
+
+#[tokio::test]
+pub async fn test_update_deposit_fee_checks_legacy_settings_before_upgrading() {
+    let (mut context, miner, _staker) = setup().await;
+    open_managed_proof(&mut context, &miner).await;
+
+    // A short account of another type at the settings address isn't upgraded.
+    let (settings_address, bump) = managed_proof_settings_pda(miner.pubkey());
+    let mut data = vec![0; 8 + 16];
+    data[0] = AccountDiscriminator::DelegatedBoost as u8;
+    data[8] = bump;
+    context.set_account(
+        &settings_address,
+        &mocks::program_account(ore_miner_delegation::id(), data.clone()).into(),
+    );
+    let update =
+        ore_miner_delegation::instruction::update_deposit_fee(miner.pubkey(), 100, miner.pubkey(), 0);
+    let err = process(&mut context, &[update.clone()], &miner)
+        .await
+        .unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InvalidAccountDiscriminator));
+
+    // Nor are legacy settings at an address that isn't the managed proof's.
+    let elsewhere = Pubkey::new_unique();
+    data[0] = AccountDiscriminator::ManagedProofSettings as u8;
+    context.set_account(
+        &elsewhere,
+        &mocks::program_account(ore_miner_delegation::id(), data).into(),
+    );
+    let mut update = update;
+    update.accounts[2].pubkey = elsewhere;
+    let err = process(&mut context, &[update], &miner).await.unwrap_err();
+    assert_eq!(delegation_error(err), Some(OreDelegationError::InvalidAccountAddress));
+}